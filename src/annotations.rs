@@ -0,0 +1,386 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+use crate::Language;
+
+/// Result column from `-- returns: name:type, ...` annotation, see [Query::returns](crate::Query::returns).
+///
+/// ```
+/// let text = "-- name: select_user\n-- returns: id:i64, name:text\nSELECT id, name FROM users;";
+/// let queries = rsyesql::parse_detailed(text).unwrap();
+/// let returns = queries["select_user"].returns.as_ref().unwrap();
+/// assert_eq!((returns[1].name.as_str(), returns[1].ty.as_str()), ("name", "text"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    /// Column name.
+    pub name: String,
+    /// Column type as written in annotation.
+    pub ty: String,
+}
+
+/// Retry policy from `retries=3 backoff=100ms` tag line attributes, see [Query::retry](crate::Query::retry).
+///
+/// Used by `execute_transactions` of database integrations on transient errors (serialization
+/// failures, deadlocks, busy database).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// Number of retries after first attempt.
+    pub retries: u32,
+    /// Delay before first retry, doubled for every next retry.
+    pub backoff: Duration,
+}
+
+impl Retry {
+    /// Delay before retry, `retry` is 0-based.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let text = "-- name: transfer retries=3 backoff=100ms\nSELECT 1;";
+    /// let queries = rsyesql::ParseOptions::new().attributes(true).parse_detailed(text).unwrap();
+    /// let retry = queries["transfer"].retry().unwrap();
+    /// assert_eq!(retry.retries, 3);
+    /// assert_eq!(retry.delay(2), Duration::from_millis(400));
+    /// ```
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+/// Test of query from `-- test: params id=1 expect_rows>=1` line under tag, see
+/// [Query::fixtures](crate::Query::fixtures).
+///
+/// Line has optional `params` word, `name=value` parameters (`null` is `NULL`, numeric name is
+/// positional parameter, value with spaces is quoted by `'...'`) and optional
+/// `expect_rows<op><count>` (ops: `=`, `!=`, `<`, `<=`, `>`, `>=`) or `expect_error`
+/// expectation. Without expectation query should not fail.
+///
+/// ```
+/// let text = "-- name: select_user\n-- test: params id=1 expect_rows=1\nSELECT * FROM users WHERE id = :id;";
+/// let queries = rsyesql::parse_detailed(text).unwrap();
+/// let fixture = &queries["select_user"].fixtures[0];
+/// assert_eq!(fixture.params, [("id".to_owned(), "1".to_owned())]);
+/// assert_eq!(fixture.check(Ok::<_, String>(1)), Ok(()));
+/// assert!(fixture.check(Ok::<_, String>(0)).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    /// Line of `-- test:` annotation.
+    pub line: usize,
+    /// Parameters as `(name, value)`.
+    pub params: Vec<(String, String)>,
+    /// Expected result.
+    pub expect: Option<Expect>,
+}
+
+/// Expected result of [Fixture].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expect {
+    /// Number of returned rows (or modified rows for query without result) compared with count.
+    Rows(Comparison, usize),
+    /// Query fails.
+    Error,
+}
+
+/// Comparison operator of [Expect::Rows].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// `=`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl Comparison {
+    /// Operator as written in annotation.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+
+    /// `left <op> right` is true.
+    pub fn matches(self, left: usize, right: usize) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+impl Fixture {
+    /// Check number of rows of executed query (or error) against expectation, `Err` is message.
+    pub fn check<E: fmt::Display>(&self, result: Result<usize, E>) -> Result<(), String> {
+        match (self.expect, result) {
+            (Some(Expect::Error), Err(_)) => Ok(()),
+            (Some(Expect::Error), Ok(rows)) => Err(format!("expected error, got {} rows", rows)),
+            (_, Err(err)) => Err(format!("query failed: {}", err)),
+            (Some(Expect::Rows(op, count)), Ok(rows)) if !op.matches(rows, count) => Err(format!(
+                "expected rows {} {}, got {}",
+                op.as_str(),
+                count,
+                rows
+            )),
+            (_, Ok(_)) => Ok(()),
+        }
+    }
+}
+
+// Fixture of `-- test:` line value, `None` if value is not valid.
+pub(crate) fn parse_fixture(value: &str, line: usize) -> Option<Fixture> {
+    const OPS: &[(&str, Comparison)] = &[
+        (">=", Comparison::Ge),
+        ("<=", Comparison::Le),
+        ("!=", Comparison::Ne),
+        ("=", Comparison::Eq),
+        (">", Comparison::Gt),
+        ("<", Comparison::Lt),
+    ];
+    let mut fixture = Fixture {
+        line,
+        params: Vec::new(),
+        expect: None,
+    };
+    for (idx, token) in tokens(value).into_iter().enumerate() {
+        let expect = if idx == 0 && token == "params" {
+            continue;
+        } else if token == "expect_error" {
+            Expect::Error
+        } else if let Some(rest) = token.strip_prefix("expect_rows") {
+            let (op, count) = OPS
+                .iter()
+                .find_map(|&(op, cmp)| Some((cmp, rest.strip_prefix(op)?)))?;
+            Expect::Rows(op, count.parse().ok()?)
+        } else {
+            let (name, value) = token.split_once('=')?;
+            let positional = !name.is_empty() && name.bytes().all(|c| c.is_ascii_digit());
+            if !(positional || is_key(name)) || value.is_empty() {
+                return None;
+            }
+            fixture.params.push((name.to_owned(), value.to_owned()));
+            continue;
+        };
+        if fixture.expect.replace(expect).is_some() {
+            return None;
+        }
+    }
+    Some(fixture)
+}
+
+// Words separated by whitespace outside of `'...'` quotes.
+fn tokens(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (idx, c) in value.char_indices() {
+        if c == '\'' {
+            quoted = !quoted;
+        }
+        match (start, c.is_whitespace() && !quoted) {
+            (None, false) => start = Some(idx),
+            (Some(begin), true) => {
+                tokens.push(&value[begin..idx]);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens.extend(start.map(|begin| &value[begin..]));
+    tokens
+}
+
+// Value of `-- key: value` comment line.
+pub(crate) fn annotation<'a>(line: &'a str, key: &str, language: &Language) -> Option<&'a str> {
+    let value = language
+        .comment(line)?
+        .trim_start()
+        .strip_prefix(key)?
+        .trim_start()
+        .strip_prefix(':')?;
+    Some(value.trim())
+}
+
+// Columns of `returns` annotation, `None` if any column is not `name:type`.
+pub(crate) fn parse_returns(value: &str) -> Option<Vec<Column>> {
+    value
+        .split(',')
+        .map(|column| {
+            let (name, ty) = column.split_once(':')?;
+            let (name, ty) = (name.trim(), ty.trim());
+            if name.is_empty() || ty.is_empty() {
+                return None;
+            }
+            Some(Column {
+                name: name.to_owned(),
+                ty: ty.to_owned(),
+            })
+        })
+        .collect()
+}
+
+// `-- endif` line.
+pub(crate) fn is_endif(line: &str, language: &Language) -> bool {
+    language
+        .comment(line)
+        .is_some_and(|rest| rest.trim() == "endif")
+}
+
+// Condition of `-- if:` line (`key=value` or `key`) satisfied by flags.
+pub(crate) fn condition_matches(condition: &str, flags: &[(String, String)]) -> bool {
+    match condition.split_once('=') {
+        Some((key, value)) => flags
+            .iter()
+            .any(|(k, v)| k == key.trim() && v == value.trim()),
+        None => flags.iter().any(|(k, _)| k == condition),
+    }
+}
+
+// Tag and trailing `key=value` attributes of tag line.
+pub(crate) fn split_attributes(value: &str) -> (&str, Vec<(&str, &str)>) {
+    let mut tag = value;
+    let mut attributes = Vec::new();
+    while let Some(idx) = tag.rfind(char::is_whitespace) {
+        let attribute = match tag[idx..].trim_start().split_once('=') {
+            Some((key, value)) if is_key(key) && !value.is_empty() => (key, value),
+            _ => break,
+        };
+        attributes.push(attribute);
+        tag = tag[..idx].trim_end();
+    }
+    attributes.reverse();
+    (tag, attributes)
+}
+
+fn is_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Duration with unit: `ms`, `s`, `m` or `h`.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let idx = value.find(|c: char| !c.is_ascii_digit())?;
+    let number = value[..idx].parse::<u64>().ok()?;
+    let millis = match &value[idx..] {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => return None,
+    };
+    Some(Duration::from_millis(number.checked_mul(millis)?))
+}
+
+// Value of typed attribute (`timeout`, `retries`, `backoff`) is valid.
+pub(crate) fn is_valid_attribute(key: &str, value: &str) -> bool {
+    match key {
+        "timeout" | "backoff" => parse_duration(value).is_some(),
+        "retries" => value.parse::<u32>().is_ok(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn annotation_value() {
+        assert_eq!(
+            annotation(" --  returns : a:b ", "returns", &Language::sql()),
+            Some("a:b")
+        );
+        assert_eq!(annotation("-- name: x", "returns", &Language::sql()), None);
+    }
+
+    #[test]
+    fn conditions() {
+        let flags = [("env".to_owned(), "test".to_owned())];
+        assert!(condition_matches("env = test", &flags));
+        assert!(condition_matches("env", &flags));
+        assert!(!condition_matches("env=prod", &flags));
+        assert!(is_endif(" --  endif ", &Language::sql()));
+        assert!(!is_endif("-- endif x", &Language::sql()));
+    }
+
+    #[test]
+    fn tag_attributes() {
+        assert_eq!(
+            split_attributes("select users timeout=5s x_1=a=b"),
+            ("select users", vec![("timeout", "5s"), ("x_1", "a=b")])
+        );
+        assert_eq!(split_attributes("x =1"), ("x =1", vec![]));
+        assert_eq!(split_attributes("a=1"), ("a=1", vec![]));
+        assert_eq!(parse_duration("100ms"), Some(Duration::from_millis(100)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(parse_duration("s"), None);
+        assert!(is_valid_attribute("retries", "3"));
+        assert!(!is_valid_attribute("retries", "-1"));
+        assert!(!is_valid_attribute("backoff", "100"));
+        assert!(is_valid_attribute("other", "100"));
+    }
+
+    #[test]
+    fn fixtures() {
+        let fixture = parse_fixture("params id=1 name=x 2=null expect_rows>=2", 3).unwrap();
+        assert_eq!(fixture.line, 3);
+        assert_eq!(
+            fixture.params,
+            [
+                ("id".to_owned(), "1".to_owned()),
+                ("name".to_owned(), "x".to_owned()),
+                ("2".to_owned(), "null".to_owned())
+            ]
+        );
+        assert_eq!(fixture.expect, Some(Expect::Rows(Comparison::Ge, 2)));
+        assert_eq!(
+            fixture.check(Ok::<_, &str>(1)).unwrap_err(),
+            "expected rows >= 2, got 1"
+        );
+        assert_eq!(
+            parse_fixture("expect_error", 1).unwrap().check(Err("x")),
+            Ok(())
+        );
+        assert_eq!(
+            parse_fixture("", 1).unwrap().check(Err("x")).unwrap_err(),
+            "query failed: x"
+        );
+        assert_eq!(tokens(" a='b  c' d "), ["a='b  c'", "d"]);
+        assert_eq!(parse_fixture("expect_rows~1", 1), None);
+        assert_eq!(parse_fixture("expect_error expect_rows=1", 1), None);
+        assert_eq!(parse_fixture("id", 1), None);
+        assert_eq!(parse_fixture("x params", 1), None);
+    }
+
+    #[test]
+    fn returns_columns() {
+        let columns = parse_returns("id:i64, created_at : timestamp with time zone").unwrap();
+        assert_eq!(columns[0].name, "id");
+        assert_eq!(columns[1].ty, "timestamp with time zone");
+        assert_eq!(parse_returns("id:i64, name"), None);
+        assert_eq!(parse_returns(""), None);
+    }
+}
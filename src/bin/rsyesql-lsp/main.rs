@@ -0,0 +1,337 @@
+//! Language server for SQL files with tagged queries, communicates over stdio.
+//!
+//! - diagnostics: parse errors (for example queries without tag) and duplicate tags;
+//! - document symbols: every `-- name:` tag;
+//! - completion: `-- name: ` marker at start of line.
+//!
+//! There is no go-to-definition: queries can not reference snippets or include other files, so
+//! there is nothing to jump to.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, DocumentSymbolRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    CompletionTextEdit, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentSymbol, DocumentSymbolParams,
+    DocumentSymbolResponse, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    SymbolKind, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit,
+};
+
+const TAG_MARKER: &str = "-- name: ";
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+fn main() -> Result<(), BoxError> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions::default()),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    main_loop(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+fn main_loop(connection: Connection) -> Result<(), BoxError> {
+    // Documents by URI, `Uri` itself is not suitable as key because of interior mutability.
+    let mut documents: HashMap<String, String> = HashMap::new();
+    for msg in &connection.receiver {
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    return Ok(());
+                }
+                let resp = handle_request(&documents, req)?;
+                connection.sender.send(Message::Response(resp))?;
+            }
+            Message::Notification(not) => {
+                if let Some(params) = handle_notification(&mut documents, not)? {
+                    let not = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+                    connection.sender.send(Message::Notification(not))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn handle_request(documents: &HashMap<String, String>, req: Request) -> Result<Response, BoxError> {
+    Ok(match req.method.as_str() {
+        DocumentSymbolRequest::METHOD => {
+            let (id, params) =
+                req.extract::<DocumentSymbolParams>(DocumentSymbolRequest::METHOD)?;
+            let text = documents.get(params.text_document.uri.as_str());
+            let symbols = text.map(|text| symbols(text)).unwrap_or_default();
+            Response::new_ok(id, DocumentSymbolResponse::Nested(symbols))
+        }
+        Completion::METHOD => {
+            let (id, params) = req.extract::<CompletionParams>(Completion::METHOD)?;
+            let position = params.text_document_position;
+            let items = documents
+                .get(position.text_document.uri.as_str())
+                .map(|text| completions(text, position.position))
+                .unwrap_or_default();
+            Response::new_ok(id, CompletionResponse::Array(items))
+        }
+        _ => Response::new_err(
+            req.id,
+            ErrorCode::MethodNotFound as i32,
+            format!("Unknown method: {}", req.method),
+        ),
+    })
+}
+
+// Update documents, returns diagnostics which should be published.
+fn handle_notification(
+    documents: &mut HashMap<String, String>,
+    not: Notification,
+) -> Result<Option<PublishDiagnosticsParams>, BoxError> {
+    let (uri, diagnostics) = match not.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: DidOpenTextDocumentParams = serde_json::from_value(not.params)?;
+            let uri = params.text_document.uri;
+            let diagnostics = diagnostics(&params.text_document.text);
+            documents.insert(uri.as_str().to_owned(), params.text_document.text);
+            (uri, diagnostics)
+        }
+        DidChangeTextDocument::METHOD => {
+            let params: DidChangeTextDocumentParams = serde_json::from_value(not.params)?;
+            let uri = params.text_document.uri;
+            // Full sync, last change contains whole document.
+            let text = match params.content_changes.into_iter().last() {
+                Some(change) => change.text,
+                None => return Ok(None),
+            };
+            let diagnostics = diagnostics(&text);
+            documents.insert(uri.as_str().to_owned(), text);
+            (uri, diagnostics)
+        }
+        DidCloseTextDocument::METHOD => {
+            let params: DidCloseTextDocumentParams = serde_json::from_value(not.params)?;
+            documents.remove(params.text_document.uri.as_str());
+            (params.text_document.uri, vec![])
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    }))
+}
+
+/// Tag defined with `-- name:` line.
+#[derive(Debug, PartialEq)]
+struct Tag<'a> {
+    name: &'a str,
+    /// Range of name.
+    range: Range,
+}
+
+// Tags in definition order, same marker as default parser options.
+fn tags(text: &str) -> Vec<Tag<'_>> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let name = line
+                .trim_start()
+                .strip_prefix("--")?
+                .trim_start()
+                .strip_prefix("name")?
+                .trim_start()
+                .strip_prefix(':')?
+                .trim();
+            let start = name.as_ptr() as usize - line.as_ptr() as usize;
+            let start = utf16_len(&line[..start]);
+            Some(Tag {
+                name,
+                range: Range::new(
+                    Position::new(idx as u32, start),
+                    Position::new(idx as u32, start + utf16_len(name)),
+                ),
+            })
+        })
+        .collect()
+}
+
+fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    if let Err(err) = rsyesql::parse_detailed(text) {
+        let line = text.lines().nth(err.line().saturating_sub(1)).unwrap_or("");
+        let start = line
+            .chars()
+            .take(err.column().saturating_sub(1))
+            .map(char::len_utf16)
+            .sum::<usize>() as u32;
+        let line_idx = err.line().saturating_sub(1) as u32;
+        diagnostics.push(Diagnostic {
+            range: Range::new(
+                Position::new(line_idx, start),
+                Position::new(line_idx, utf16_len(line)),
+            ),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("rsyesql".to_owned()),
+            message: err.to_string(),
+            ..Default::default()
+        });
+    }
+
+    let mut seen: HashMap<&str, u32> = HashMap::new();
+    for tag in tags(text) {
+        if let Some(&first) = seen.get(tag.name) {
+            diagnostics.push(Diagnostic {
+                range: tag.range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("rsyesql".to_owned()),
+                message: format!(
+                    r#"Duplicate tag "{}", first defined at line: {}"#,
+                    tag.name,
+                    first + 1
+                ),
+                ..Default::default()
+            });
+        } else {
+            seen.insert(tag.name, tag.range.start.line);
+        }
+    }
+
+    diagnostics
+}
+
+fn symbols(text: &str) -> Vec<DocumentSymbol> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let tags = tags(text);
+    tags.iter()
+        .enumerate()
+        .map(|(idx, tag)| {
+            // Symbol spans until next tag.
+            let end = tags
+                .get(idx + 1)
+                .map_or(lines.len(), |next| next.range.start.line as usize);
+            let last = lines[..end]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .unwrap_or(0);
+            #[allow(deprecated)]
+            DocumentSymbol {
+                name: tag.name.to_owned(),
+                detail: None,
+                kind: SymbolKind::FUNCTION,
+                tags: None,
+                deprecated: None,
+                range: Range::new(
+                    Position::new(tag.range.start.line, 0),
+                    Position::new(last as u32, utf16_len(lines[last])),
+                ),
+                selection_range: tag.range,
+                children: None,
+            }
+        })
+        .collect()
+}
+
+// Complete tag marker if line before cursor is its prefix.
+fn completions(text: &str, position: Position) -> Vec<CompletionItem> {
+    let line = text.lines().nth(position.line as usize).unwrap_or("");
+    let mut units = 0;
+    let before = line
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16() as u32;
+            units <= position.character
+        })
+        .collect::<String>();
+    let prefix = before.trim_start();
+    if !TAG_MARKER.starts_with(prefix) {
+        return vec![];
+    }
+
+    let start = utf16_len(&before) - utf16_len(prefix);
+    vec![CompletionItem {
+        label: TAG_MARKER.trim_end().to_owned(),
+        kind: Some(CompletionItemKind::KEYWORD),
+        detail: Some("Query tag".to_owned()),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+            Range::new(
+                Position::new(position.line, start),
+                Position::new(position.line, start + utf16_len(prefix)),
+            ),
+            TAG_MARKER.to_owned(),
+        ))),
+        ..Default::default()
+    }]
+}
+
+fn utf16_len(s: &str) -> u32 {
+    s.chars().map(char::len_utf16).sum::<usize>() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "-- name: a\nSELECT 1;\n\n--name:  ы\nSELECT 2;\n-- name: a\nSELECT 3;\n";
+
+    #[test]
+    fn tags_with_ranges() {
+        let tags = tags(TEXT);
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[1].name, "ы");
+        assert_eq!(
+            tags[1].range,
+            Range::new(Position::new(3, 9), Position::new(3, 10))
+        );
+    }
+
+    #[test]
+    fn duplicate_and_parse_error_diagnostics() {
+        let diagnostics = diagnostics(TEXT);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(5, 9));
+        assert!(diagnostics[0].message.contains("line: 1"));
+
+        let diagnostics = super::diagnostics("\nSELECT 1;\n-- name: a\nSELECT 2;");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(1, 0), Position::new(1, 9))
+        );
+    }
+
+    #[test]
+    fn symbol_ranges() {
+        let symbols = symbols(TEXT);
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(
+            symbols[0].range,
+            Range::new(Position::new(0, 0), Position::new(1, 9))
+        );
+        assert_eq!(symbols[2].range.end, Position::new(6, 9));
+    }
+
+    #[test]
+    fn complete_marker() {
+        let items = completions("  --\nSELECT 1;", Position::new(0, 4));
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit::new(
+                Range::new(Position::new(0, 2), Position::new(0, 4)),
+                TAG_MARKER.to_owned()
+            )))
+        );
+        assert!(completions("SELECT 1;", Position::new(0, 3)).is_empty());
+    }
+}
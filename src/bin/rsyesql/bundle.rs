@@ -0,0 +1,90 @@
+//! `bundle` command: merge SQL files into one file, tags prefixed by file stem.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{collect_files, option_value};
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dirs = vec![];
+    let mut output = None;
+    let mut separator = ".".to_owned();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--separator" => separator = option_value(&arg, &mut args)?,
+            _ if !arg.starts_with('-') => sql_dirs.push(PathBuf::from(arg)),
+            _ => return Err(format!("Unknown option for bundle: {}", arg)),
+        }
+    }
+    if sql_dirs.is_empty() {
+        return Err("bundle requires SQL directory".to_owned());
+    }
+
+    let mut files = vec![];
+    for dir in &sql_dirs {
+        for path in collect_files(dir, "sql").map_err(|e| format!("{}: {}", dir.display(), e))? {
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let text = prefix_tags(&path, &text, &separator)?;
+            files.push((path, text));
+        }
+    }
+    // Same tag after prefix (same file stem in different directories) reported with locations.
+    rsyesql::parse_many(files.iter().map(|(path, text)| (path, text)))
+        .map_err(|e| e.to_string())?;
+
+    let mut bundle = String::new();
+    for (path, text) in &files {
+        if !bundle.is_empty() {
+            bundle.push('\n');
+        }
+        bundle.push_str(&format!("-- file: {}\n", path.display()));
+        bundle.push_str(text);
+        if !text.ends_with('\n') {
+            bundle.push('\n');
+        }
+    }
+    match output {
+        Some(path) => fs::write(&path, bundle).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => print!("{}", bundle),
+    }
+    Ok(true)
+}
+
+/// Text of file with tag on every tag line prefixed by file stem and separator.
+fn prefix_tags(path: &Path, text: &str, separator: &str) -> Result<String, String> {
+    rsyesql::parse_detailed(text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| format!("{}: file name is not UTF-8", path.display()))?;
+
+    // Every tag line, also lines which continue tag defined above.
+    let mut result = String::with_capacity(text.len());
+    let mut pos = 0;
+    for token in rsyesql::tokens(text) {
+        if let rsyesql::TokenKind::TagLine { tag } = token.kind {
+            result.push_str(&text[pos..tag.start]);
+            result.push_str(stem);
+            result.push_str(separator);
+            pos = tag.start;
+        }
+    }
+    result.push_str(&text[pos..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_every_tag_line() {
+        let path = Path::new("sql/users.sql");
+        assert_eq!(
+            prefix_tags(path, "--name: a\rx\r-- name: b\ry\r-- name:a\rz", ".").unwrap(),
+            "--name: users.a\rx\r-- name: users.b\ry\r-- name:users.a\rz"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! `check` command: run lint rules over queries in SQL files.
+
+use std::fs;
+use std::path::PathBuf;
+
+use rsyesql::lint::{Linter, MaxLength, NoSelectStar, RequireDoc, TagNaming, TrailingSemicolon};
+use rsyesql::TagNames;
+
+use crate::{collect_files, option_value};
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dirs = vec![];
+    let mut linter = Linter::new()
+        .rule(TrailingSemicolon)
+        .rule(NoSelectStar::default());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sql" => sql_dirs.push(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--max-length" => {
+                let value = option_value(&arg, &mut args)?;
+                let max = value
+                    .parse()
+                    .map_err(|_| format!("Invalid value for --max-length: {}", value))?;
+                linter = linter.rule(MaxLength(max));
+            }
+            "--require-doc" => linter = linter.rule(RequireDoc),
+            "--rust-idents" => linter = linter.rule(TagNaming(TagNames::RustIdent)),
+            _ => return Err(format!("Unknown option for check: {}", arg)),
+        }
+    }
+    if sql_dirs.is_empty() {
+        return Err("check requires --sql".to_owned());
+    }
+
+    let mut ok = true;
+    for dir in &sql_dirs {
+        let files = collect_files(dir, "sql").map_err(|e| format!("{}: {}", dir.display(), e))?;
+        for path in files {
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let queries =
+                rsyesql::parse_detailed(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+            for finding in linter.check(&text, &queries) {
+                ok = false;
+                println!(
+                    r#"{}:{}: {} ("{}"): {}"#,
+                    path.display(),
+                    finding.line,
+                    finding.rule,
+                    finding.tag,
+                    finding.message
+                );
+            }
+        }
+    }
+    Ok(ok)
+}
@@ -0,0 +1,34 @@
+//! `duplicates` command: report queries which are same after normalization of literals.
+
+use std::path::PathBuf;
+
+use crate::{load_queries, option_value};
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dirs = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sql" => sql_dirs.push(PathBuf::from(option_value(&arg, &mut args)?)),
+            _ => return Err(format!("Unknown option for duplicates: {}", arg)),
+        }
+    }
+    if sql_dirs.is_empty() {
+        return Err("duplicates requires --sql".to_owned());
+    }
+
+    // Same tag can be defined in different files, so files are part of key.
+    let mut queries = vec![];
+    for dir in &sql_dirs {
+        for (path, file_queries) in load_queries(dir)? {
+            for (tag, query) in file_queries {
+                queries.push((format!(r#""{}" ({})"#, tag, path.display()), query));
+            }
+        }
+    }
+
+    let groups = rsyesql::duplicates(queries);
+    for group in &groups {
+        println!("duplicate queries: {}", group.join(", "));
+    }
+    Ok(groups.is_empty())
+}
@@ -0,0 +1,152 @@
+//! `lint` command: compare tags defined in SQL files with lookups in Rust sources.
+
+use std::fs;
+use std::path::PathBuf;
+
+use regex::Regex;
+use rsyesql::indexmap::IndexMap;
+
+use crate::{collect_files, load_tags, option_value};
+
+const DEFAULT_PATTERN: &str = r#"\.get\(\s*"((?:[^"\\]|\\.)*)"\s*\)"#;
+
+#[derive(Debug, PartialEq)]
+struct Lookup {
+    line: usize,
+    tag: String,
+}
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dirs = vec![];
+    let mut src_dirs = vec![];
+    let mut pattern = DEFAULT_PATTERN.to_owned();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sql" => sql_dirs.push(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--src" => src_dirs.push(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--pattern" => pattern = option_value(&arg, &mut args)?,
+            _ => return Err(format!("Unknown option for lint: {}", arg)),
+        }
+    }
+    if sql_dirs.is_empty() || src_dirs.is_empty() {
+        return Err("lint requires both --sql and --src".to_owned());
+    }
+
+    let re = Regex::new(&pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    if re.captures_len() < 2 {
+        return Err("Pattern should have capture group for tag".to_owned());
+    }
+
+    let mut tags = sql_dirs
+        .iter()
+        .map(|dir| load_tags(dir))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .map(|(tag, path)| (tag, (path, false)))
+        .collect::<IndexMap<_, _>>();
+
+    let mut ok = true;
+    for dir in &src_dirs {
+        let files = collect_files(dir, "rs").map_err(|e| format!("{}: {}", dir.display(), e))?;
+        for path in files {
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            for lookup in scan_source(&re, &text) {
+                match tags.get_mut(&lookup.tag) {
+                    Some((_, used)) => *used = true,
+                    None => {
+                        ok = false;
+                        println!(
+                            r#"missing tag "{}" ({}:{})"#,
+                            lookup.tag,
+                            path.display(),
+                            lookup.line
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    for (tag, (path, used)) in &tags {
+        if !used {
+            ok = false;
+            println!(r#"unused tag "{}" ({})"#, tag, path.display());
+        }
+    }
+
+    Ok(ok)
+}
+
+// Find all tag lookups in Rust source.
+fn scan_source(re: &Regex, text: &str) -> Vec<Lookup> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(idx, line)| {
+            re.captures_iter(line).map(move |caps| Lookup {
+                line: idx + 1,
+                tag: unescape(&caps[1]),
+            })
+        })
+        .collect()
+}
+
+// Value of Rust string literal with escapes.
+fn unescape(literal: &str) -> String {
+    let mut value = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some('0') => value.push('\0'),
+            Some(c) => value.push(c),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_default_pattern() {
+        let re = Regex::new(DEFAULT_PATTERN).unwrap();
+        let text = "let a = 1;\nqueries.get(\"select\").unwrap();\nqueries.get( \"a\" ); self.queries.get(\"b\\\"c\")\nmap.get(\"d\")";
+        assert_eq!(
+            scan_source(&re, text),
+            vec![
+                Lookup {
+                    line: 2,
+                    tag: "select".to_owned()
+                },
+                Lookup {
+                    line: 3,
+                    tag: "a".to_owned()
+                },
+                Lookup {
+                    line: 3,
+                    tag: "b\"c".to_owned()
+                },
+                Lookup {
+                    line: 4,
+                    tag: "d".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_ignores_non_literal_lookups() {
+        let re = Regex::new(DEFAULT_PATTERN).unwrap();
+        assert_eq!(scan_source(&re, "queries.get(name)"), vec![]);
+    }
+}
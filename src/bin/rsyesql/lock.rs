@@ -0,0 +1,43 @@
+//! `lock` command: write manifest with checksum and source file of every query.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rsyesql::indexmap::IndexMap;
+use rsyesql::{Manifest, Query};
+
+use crate::{collect_files, option_value};
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dir = None;
+    let mut output = PathBuf::from("queries.lock");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = PathBuf::from(option_value(&arg, &mut args)?),
+            _ if !arg.starts_with('-') && sql_dir.is_none() => sql_dir = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unknown option for lock: {}", arg)),
+        }
+    }
+    let sql_dir = sql_dir.ok_or("lock requires SQL directory")?;
+
+    let manifest = Manifest::from_queries(&load_detailed(&sql_dir)?);
+    fs::write(&output, manifest.to_string()).map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!(
+        "{}: {} queries locked",
+        output.display(),
+        manifest.entries.len()
+    );
+    Ok(true)
+}
+
+/// Parse every `.sql` file in directory to one map, files are relative to directory.
+pub fn load_detailed(dir: &Path) -> Result<IndexMap<String, Query>, String> {
+    let files = collect_files(dir, "sql").map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut texts = vec![];
+    for path in files {
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+        texts.push((relative, text));
+    }
+    rsyesql::parse_many(texts).map_err(|e| e.to_string())
+}
@@ -0,0 +1,127 @@
+//! Command line tools for SQL files with tagged queries.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rsyesql::indexmap::IndexMap;
+
+mod bundle;
+mod check;
+mod duplicates;
+mod lint;
+#[cfg(feature = "checksums")]
+mod lock;
+#[cfg(feature = "checksums")]
+mod verify;
+mod watch;
+
+const USAGE: &str = "Usage: rsyesql <command> [options]
+
+Commands:
+    bundle <dir>... [-o <file>] [--separator <s>]
+        Merge SQL files into one file, tags prefixed by file stem and
+        separator (default: `.`), same tags in different files reported.
+    check --sql <dir> [--max-length <n>] [--require-doc] [--rust-idents]
+        Run lint rules: queries end with `;` and do not use `SELECT *`,
+        optionally limit query length, require comment above tag and
+        require tags to be Rust identifiers.
+    duplicates --sql <dir>
+        Report queries which differ only by whitespace and literal values.
+    lint --sql <dir> --src <dir> [--pattern <regex>]
+        Report tags which are never looked up in Rust sources and lookups
+        which reference non-existent tags.
+    lock <dir> [-o <file>]
+        Write manifest (default: `queries.lock`) with tag, checksum and
+        source file of every query (feature `checksums`).
+    verify <dir> [--lock <file>]
+        Report queries which differ from manifest written by `lock`
+        (feature `checksums`).
+    watch <dir> --emit <file> [--placeholder dollar|question] [--format rust|js|ts]
+          [--interval <ms>] [--once]
+        Regenerate codegen output (query constants, parameter and row
+        structs) on change of SQL files, parse errors printed with
+        locations. `--format js|ts` emits JavaScript or TypeScript module.";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("bundle") => bundle::run(args),
+        Some("check") => check::run(args),
+        Some("duplicates") => duplicates::run(args),
+        Some("lint") => lint::run(args),
+        #[cfg(feature = "checksums")]
+        Some("lock") => lock::run(args),
+        #[cfg(feature = "checksums")]
+        Some("verify") => verify::run(args),
+        Some("watch") => watch::run(args),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            Ok(true)
+        }
+        _ => Err(USAGE.to_owned()),
+    };
+
+    process::exit(match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(error) => {
+            eprintln!("{}", error);
+            2
+        }
+    });
+}
+
+/// Recursively collect files with given extension, sorted by path.
+fn collect_files(dir: &Path, extension: &str) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension() == Some(OsStr::new(extension)) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Queries parsed from file.
+type FileQueries = (PathBuf, IndexMap<String, String>);
+
+/// Parse every `.sql` file in directory.
+fn load_queries(dir: &Path) -> Result<Vec<FileQueries>, String> {
+    let files = collect_files(dir, "sql").map_err(|e| format!("{}: {}", dir.display(), e))?;
+    files
+        .into_iter()
+        .map(|path| {
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let queries = rsyesql::parse(text).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok((path, queries))
+        })
+        .collect()
+}
+
+/// Parse every `.sql` file in directory, tags mapped to files where they defined.
+fn load_tags(dir: &Path) -> Result<IndexMap<String, PathBuf>, String> {
+    let mut tags = IndexMap::new();
+    for (path, queries) in load_queries(dir)? {
+        for tag in queries.keys() {
+            tags.entry(tag.clone()).or_insert_with(|| path.clone());
+        }
+    }
+    Ok(tags)
+}
+
+/// Take value for option from arguments.
+fn option_value(name: &str, args: &mut impl Iterator<Item = String>) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for option: {}", name))
+}
@@ -0,0 +1,30 @@
+//! `verify` command: compare queries with manifest written by `lock`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use rsyesql::Manifest;
+
+use crate::lock::load_detailed;
+use crate::option_value;
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dir = None;
+    let mut lock = PathBuf::from("queries.lock");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lock" => lock = PathBuf::from(option_value(&arg, &mut args)?),
+            _ if !arg.starts_with('-') && sql_dir.is_none() => sql_dir = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unknown option for verify: {}", arg)),
+        }
+    }
+    let sql_dir = sql_dir.ok_or("verify requires SQL directory")?;
+
+    let text = fs::read_to_string(&lock).map_err(|e| format!("{}: {}", lock.display(), e))?;
+    let manifest = Manifest::parse(&text).map_err(|e| format!("{}: {}", lock.display(), e))?;
+    let drift = manifest.verify(&load_detailed(&sql_dir)?);
+    for drift in &drift {
+        println!("{}", drift);
+    }
+    Ok(drift.is_empty())
+}
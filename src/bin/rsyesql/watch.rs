@@ -0,0 +1,154 @@
+//! `watch` command: regenerate codegen output on change of SQL files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use rsyesql::codegen::{Codegen, JsDialect};
+use rsyesql::{FileError, Placeholder};
+
+use crate::{collect_files, option_value};
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut sql_dir = None;
+    let mut emit = None;
+    let mut codegen = Codegen::new();
+    let mut interval = Duration::from_millis(500);
+    let mut once = false;
+    let mut format = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--emit" => emit = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--placeholder" => {
+                let value = option_value(&arg, &mut args)?;
+                let placeholder = match value.as_str() {
+                    "dollar" => Placeholder::Dollar,
+                    "question" => Placeholder::Question,
+                    _ => return Err(format!("Invalid value for --placeholder: {}", value)),
+                };
+                codegen = codegen.placeholder(placeholder);
+            }
+            "--interval" => {
+                let value = option_value(&arg, &mut args)?;
+                let ms = value
+                    .parse()
+                    .map_err(|_| format!("Invalid value for --interval: {}", value))?;
+                interval = Duration::from_millis(ms);
+            }
+            "--format" => {
+                let value = option_value(&arg, &mut args)?;
+                format = match value.as_str() {
+                    "rust" => None,
+                    "js" => Some(JsDialect::JavaScript),
+                    "ts" => Some(JsDialect::TypeScript),
+                    _ => return Err(format!("Invalid value for --format: {}", value)),
+                };
+            }
+            "--once" => once = true,
+            _ if !arg.starts_with('-') && sql_dir.is_none() => sql_dir = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unknown option for watch: {}", arg)),
+        }
+    }
+    let sql_dir = sql_dir.ok_or("watch requires SQL directory")?;
+    let emit = emit.ok_or("watch requires --emit")?;
+
+    let mut state = None;
+    loop {
+        let files = snapshot(&sql_dir)?;
+        if state.as_ref() != Some(&files) {
+            let ok = match generate(&codegen, format, &files) {
+                Ok(code) => write_changed(&emit, &code)?,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    false
+                }
+            };
+            if once {
+                return Ok(ok);
+            }
+            state = Some(files);
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// SQL files with modification time and size, sorted by path.
+type Snapshot = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+fn snapshot(dir: &Path) -> Result<Snapshot, String> {
+    let files = collect_files(dir, "sql").map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            // File can be removed after listing, this is change on next check.
+            let (modified, len) = fs::metadata(&path)
+                .map(|meta| (meta.modified().ok(), meta.len()))
+                .unwrap_or((None, 0));
+            (path, modified, len)
+        })
+        .collect())
+}
+
+/// Code for queries of all files (Rust or JS module), error has location of parse error.
+fn generate(
+    codegen: &Codegen,
+    format: Option<JsDialect>,
+    files: &Snapshot,
+) -> Result<String, String> {
+    let mut texts = vec![];
+    for (path, _, _) in files {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        texts.push((path, text));
+    }
+    let queries = rsyesql::ParseOptions::new()
+        .parse_many(texts.iter().map(|(path, text)| (path, text)))
+        .map_err(|err| match err {
+            FileError::Parse { file, err } => format!(
+                "{}:{}:{}: {}",
+                file.display(),
+                err.line(),
+                err.column(),
+                err
+            ),
+            err => err.to_string(),
+        })?;
+
+    if let Some(dialect) = format {
+        let mut modules = vec![];
+        for (path, text) in &texts {
+            // Doc comments are read from source text, so modules are generated per file.
+            let file_queries = queries
+                .iter()
+                .filter(|(_, query)| query.file.as_deref() == Some(path.as_path()))
+                .map(|(tag, query)| (tag.clone(), query.clone()))
+                .collect();
+            let module = codegen
+                .js_module(dialect, text, &file_queries)
+                .map_err(|e| e.to_string())?;
+            if !module.is_empty() {
+                modules.push(module);
+            }
+        }
+        return Ok(modules.join("\n"));
+    }
+
+    let texts = queries.iter().map(|(tag, query)| (tag, &query.text));
+    let mut code = codegen.generate(texts).map_err(|e| e.to_string())?;
+    let rows = codegen.row_structs(&queries).map_err(|e| e.to_string())?;
+    if !rows.is_empty() {
+        code.push('\n');
+        code.push_str(&rows);
+    }
+    Ok(code)
+}
+
+/// Write code if file content is different, so build is not triggered without changes.
+fn write_changed(path: &Path, code: &str) -> Result<bool, String> {
+    let code = format!("// Generated by `rsyesql watch`, do not edit.\n\n{}", code);
+    if fs::read_to_string(path).ok().as_deref() != Some(code.as_str()) {
+        fs::write(path, code).map_err(|e| format!("{}: {}", path.display(), e))?;
+        println!("{}: updated", path.display());
+    }
+    Ok(true)
+}
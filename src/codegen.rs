@@ -0,0 +1,692 @@
+//! Rust code generation from queries, for `build.rs` scripts.
+//!
+//! ```ignore
+//! // build.rs
+//! let queries = rsyesql::parse(std::fs::read_to_string("sql/queries.sql")?)?;
+//! let code = rsyesql::codegen::Codegen::new().generate(&queries)?;
+//! std::fs::write(std::path::Path::new(&std::env::var("OUT_DIR")?).join("queries.rs"), code)?;
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/queries.rs"));
+//!
+//! let params = SelectUserParams::<&(dyn ToSql + Sync)> { id: &42, name: &"user" };
+//! client.query(SELECT_USER, &params.into_params()).await?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+use crate::rows::rust_type;
+use crate::{positional, Placeholder, Query};
+
+quick_error! {
+    /// The error type for [Codegen].
+    #[derive(Debug, PartialEq)]
+    pub enum CodegenError {
+        /// Tag can not be converted to Rust identifier.
+        InvalidTag(tag: String) {
+            display(r#"Tag "{}" can not be converted to Rust identifier"#, tag)
+        }
+        /// Parameter name is not Rust identifier.
+        InvalidParam { tag: String, param: String } {
+            display(r#"Parameter "{}" of "{}" is not Rust identifier"#, param, tag)
+        }
+        /// Column name in `-- returns:` annotation is not Rust identifier.
+        InvalidColumn { tag: String, column: String } {
+            display(r#"Column "{}" of "{}" is not Rust identifier"#, column, tag)
+        }
+        /// Different tags are converted to same identifier, like `select-user` and `select_user`.
+        DuplicateName { tag: String, other: String, name: String } {
+            display(r#"Tags "{}" and "{}" generate same identifier "{}""#, other, tag, name)
+        }
+    }
+}
+
+/// Generator of constant with query text and parameters struct for every query.
+///
+/// For query `select_user` with text `SELECT * FROM users WHERE id = :id AND name = :name`
+/// generated:
+///
+/// ```ignore
+/// /// Query `select_user` with positional placeholders.
+/// pub const SELECT_USER: &str = "SELECT * FROM users WHERE id = $1 AND name = $2";
+///
+/// /// Parameters of query `select_user`.
+/// #[derive(Debug, Clone)]
+/// pub struct SelectUserParams<P> {
+///     pub id: P,
+///     pub name: P,
+/// }
+///
+/// impl<P> SelectUserParams<P> {
+///     /// Parameters in order of placeholders.
+///     pub fn into_params(self) -> [P; 2] {
+///         [self.id, self.name]
+///     }
+/// }
+/// ```
+///
+/// Struct is not generated for queries without named parameters.
+#[derive(Debug, Clone)]
+pub struct Codegen {
+    placeholder: Placeholder,
+    row_driver: Option<RowDriver>,
+}
+
+impl Default for Codegen {
+    fn default() -> Self {
+        Self {
+            placeholder: Placeholder::Dollar,
+            row_driver: None,
+        }
+    }
+}
+
+/// Language of module generated by [Codegen::js_module].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsDialect {
+    /// ES module.
+    JavaScript,
+    /// TypeScript module, constants with type annotations.
+    TypeScript,
+}
+
+/// Driver row type for `TryFrom<&Row>` implementations, see [Codegen::row_structs].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowDriver {
+    /// `tokio_postgres::Row`.
+    Postgres,
+    /// `rusqlite::Row`.
+    Sqlite,
+}
+
+impl Codegen {
+    /// Create generator with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Placeholder style in generated queries (default: [Placeholder::Dollar]).
+    pub fn placeholder(mut self, placeholder: Placeholder) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Generate `TryFrom<&Row>` for row structs (default: none), see [row_structs](Self::row_structs).
+    pub fn row_driver(mut self, driver: RowDriver) -> Self {
+        self.row_driver = Some(driver);
+        self
+    }
+
+    /// Generate code for every query in definition order.
+    pub fn generate<I, T, Q>(&self, queries: I) -> Result<String, CodegenError>
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let mut code = String::new();
+        let mut names = Names::default();
+        for (tag, query) in queries {
+            let tag = tag.as_ref();
+            let words = words(tag).ok_or_else(|| CodegenError::InvalidTag(tag.to_owned()))?;
+            let query = positional(query.as_ref(), self.placeholder);
+            let constant = names.insert(words.join("_").to_uppercase(), tag)?;
+
+            if !code.is_empty() {
+                code.push('\n');
+            }
+            writeln!(
+                code,
+                "/// Query `{}` with positional placeholders.\npub const {}: &str = {:?};",
+                tag, constant, query.text
+            )
+            .unwrap();
+
+            if query.params.is_empty() {
+                continue;
+            }
+
+            let mut fields: Vec<&str> = vec![];
+            for param in &query.params {
+                if !crate::TagNames::RustIdent.is_valid(param) {
+                    return Err(CodegenError::InvalidParam {
+                        tag: tag.to_owned(),
+                        param: param.clone(),
+                    });
+                }
+                if !fields.contains(&param.as_str()) {
+                    fields.push(param);
+                }
+            }
+
+            let name = names.insert(
+                words.iter().map(|w| camel(w)).collect::<String>() + "Params",
+                tag,
+            )?;
+            writeln!(
+                code,
+                "\n/// Parameters of query `{}`.\n#[derive(Debug, Clone)]\npub struct {}<P> {{",
+                tag, name
+            )
+            .unwrap();
+            for field in &fields {
+                writeln!(code, "    pub {}: P,", field).unwrap();
+            }
+            code.push_str("}\n\n");
+
+            // `?` placeholders can repeat parameter, so values are cloned.
+            let repeated = fields.len() != query.params.len();
+            let values = query
+                .params
+                .iter()
+                .map(|param| match repeated {
+                    true => format!("self.{}.clone()", param),
+                    false => format!("self.{}", param),
+                })
+                .collect::<Vec<_>>();
+            writeln!(
+                code,
+                "impl<P{}> {}<P> {{\n    /// Parameters in order of placeholders.\n    pub fn into_params(self) -> [P; {}] {{\n        [{}]\n    }}\n}}",
+                if repeated { ": Clone" } else { "" },
+                name,
+                values.len(),
+                values.join(", ")
+            )
+            .unwrap();
+        }
+        Ok(code)
+    }
+
+    /// Generate struct for every query with `-- returns:` annotation, fields are annotated columns.
+    ///
+    /// Column types are mapped to Rust types (`bigint` to `i64`, `text?` to `Option<String>`, ...,
+    /// other types are used as is). With [row_driver](Self::row_driver) `TryFrom<&Row>` is
+    /// generated, row columns are checked with [check_row_shape](crate::check_row_shape) and
+    /// mismatch returns [RowError::Shape](crate::RowError::Shape).
+    ///
+    /// ```ignore
+    /// let user = SelectUserRow::try_from(&client.query_one(SELECT_USER, &[&id]).await?)?;
+    /// ```
+    pub fn row_structs(&self, queries: &IndexMap<String, Query>) -> Result<String, CodegenError> {
+        let mut code = String::new();
+        let mut names = Names::default();
+        for (tag, query) in queries {
+            let returns = match &query.returns {
+                Some(returns) => returns,
+                None => continue,
+            };
+            let words = words(tag).ok_or_else(|| CodegenError::InvalidTag(tag.clone()))?;
+            let name = names.insert(
+                words.iter().map(|w| camel(w)).collect::<String>() + "Row",
+                tag,
+            )?;
+            if let Some(column) = returns
+                .iter()
+                .find(|c| !crate::TagNames::RustIdent.is_valid(&c.name))
+            {
+                return Err(CodegenError::InvalidColumn {
+                    tag: tag.clone(),
+                    column: column.name.clone(),
+                });
+            }
+
+            if !code.is_empty() {
+                code.push('\n');
+            }
+            writeln!(
+                code,
+                "/// Row of query `{}`.\n#[derive(Debug, Clone)]\npub struct {} {{",
+                tag, name
+            )
+            .unwrap();
+            for column in returns {
+                writeln!(code, "    pub {}: {},", column.name, rust_type(&column.ty)).unwrap();
+            }
+            let columns = returns
+                .iter()
+                .map(|c| format!("{:?}", c.name))
+                .collect::<Vec<_>>();
+            writeln!(
+                code,
+                "}}\n\nimpl {} {{\n    /// Column names from returns annotation.\n    pub const COLUMNS: &'static [&'static str] = &[{}];\n}}",
+                name,
+                columns.join(", ")
+            )
+            .unwrap();
+
+            let (row, names, get) = match self.row_driver {
+                Some(RowDriver::Postgres) => (
+                    "tokio_postgres::Row",
+                    "row.columns().iter().map(|c| c.name())",
+                    "try_get",
+                ),
+                Some(RowDriver::Sqlite) => {
+                    ("rusqlite::Row<'_>", "row.as_ref().column_names()", "get")
+                }
+                None => continue,
+            };
+            writeln!(
+                code,
+                "\nimpl std::convert::TryFrom<&{row}> for {name} {{
+    type Error = rsyesql::RowError;
+
+    fn try_from(row: &{row}) -> Result<Self, Self::Error> {{
+        rsyesql::check_row_shape(Self::COLUMNS, {names})?;
+        Ok(Self {{",
+                row = row,
+                name = name,
+                names = names
+            )
+            .unwrap();
+            for column in returns {
+                writeln!(
+                    code,
+                    "            {name}: row.{get}({name:?}).map_err(|err| rsyesql::RowError::Value {{
+                column: {name:?}.to_owned(),
+                err: err.into(),
+            }})?,",
+                    name = column.name,
+                    get = get
+                )
+                .unwrap();
+            }
+            code.push_str("        })\n    }\n}\n");
+        }
+        Ok(code)
+    }
+
+    /// Generate `macro_rules!` wrappers of `sqlx::query!` and `sqlx::query_as!` with query text
+    /// inline, so sqlx checks queries against database at compile time.
+    ///
+    /// For query `select_user` macros `select_user!(args...)` and
+    /// `select_user_as!(Output, args...)` are generated, arguments are in order of positional
+    /// placeholders ([placeholder](Self::placeholder) should match database).
+    ///
+    /// ```ignore
+    /// #[macro_use]
+    /// mod queries {
+    ///     include!(concat!(env!("OUT_DIR"), "/sqlx_queries.rs"));
+    /// }
+    ///
+    /// let user = select_user_as!(User, id).fetch_one(&pool).await?;
+    /// ```
+    pub fn sqlx_macros<I, T, Q>(&self, queries: I) -> Result<String, CodegenError>
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let mut code = String::new();
+        let mut names = Names::default();
+        for (tag, query) in queries {
+            let tag = tag.as_ref();
+            let words = words(tag).ok_or_else(|| CodegenError::InvalidTag(tag.to_owned()))?;
+            let name = words.join("_").to_lowercase();
+            // Macro name can not be keyword, like `type`.
+            if !crate::TagNames::RustIdent.is_valid(&name) {
+                return Err(CodegenError::InvalidTag(tag.to_owned()));
+            }
+            names.insert(format!("{}_as", name), tag)?;
+            let name = names.insert(name, tag)?;
+            let query = positional(query.as_ref(), self.placeholder);
+
+            if !code.is_empty() {
+                code.push('\n');
+            }
+            writeln!(
+                code,
+                "/// `sqlx::query!` with query `{tag}`.
+macro_rules! {name} {{
+    ($($args:expr),* $(,)?) => {{
+        sqlx::query!({text:?}, $($args),*)
+    }};
+}}
+
+/// `sqlx::query_as!` with query `{tag}`.
+macro_rules! {name}_as {{
+    ($out:path $(, $args:expr)* $(,)?) => {{
+        sqlx::query_as!($out, {text:?}, $($args),*)
+    }};
+}}",
+                tag = tag,
+                name = name,
+                text = query.text
+            )
+            .unwrap();
+        }
+        Ok(code)
+    }
+
+    /// Generate ES module (or TypeScript module) exporting constant with query text for every
+    /// query parsed from `text`, for JavaScript services using same SQL files.
+    ///
+    /// Comment above tag line is JSDoc of constant, queries with named parameters also export
+    /// parameter names in order of placeholders.
+    ///
+    /// ```
+    /// use rsyesql::codegen::{Codegen, JsDialect};
+    ///
+    /// let text = "-- User by id.\n-- name: select-user\nSELECT * FROM users WHERE id = :id;";
+    /// let queries = rsyesql::parse_detailed(text).unwrap();
+    /// let code = Codegen::new().js_module(JsDialect::TypeScript, text, &queries).unwrap();
+    /// assert_eq!(
+    ///     code,
+    ///     r#"/**
+    ///  * User by id.
+    ///  */
+    /// export const SELECT_USER: string = "SELECT * FROM users WHERE id = $1;";
+    ///
+    /// /** Parameters of query `select-user` in order of placeholders. */
+    /// export const SELECT_USER_PARAMS: readonly string[] = ["id"];
+    /// "#
+    /// );
+    /// ```
+    pub fn js_module(
+        &self,
+        dialect: JsDialect,
+        text: &str,
+        queries: &IndexMap<String, Query>,
+    ) -> Result<String, CodegenError> {
+        let lines = text.lines().collect::<Vec<_>>();
+        let (string_type, params_type) = match dialect {
+            JsDialect::JavaScript => ("", ""),
+            JsDialect::TypeScript => (": string", ": readonly string[]"),
+        };
+        let mut code = String::new();
+        let mut names = Names::default();
+        for (tag, query) in queries {
+            let words = words(tag).ok_or_else(|| CodegenError::InvalidTag(tag.clone()))?;
+            let name = names.insert(words.join("_").to_uppercase(), tag)?;
+            let doc = crate::lint::doc_comment(&lines, query.line);
+            let query = positional(&query.text, self.placeholder);
+
+            if !code.is_empty() {
+                code.push('\n');
+            }
+            match doc {
+                Some(doc) => {
+                    code.push_str("/**\n");
+                    for line in doc.split('\n') {
+                        let line = line.replace("*/", "*\\/");
+                        writeln!(code, " *{}{}", if line.is_empty() { "" } else { " " }, line)
+                            .unwrap();
+                    }
+                    code.push_str(" */\n");
+                }
+                None => {
+                    writeln!(code, "/** Query `{}` with positional placeholders. */", tag).unwrap()
+                }
+            }
+            writeln!(
+                code,
+                "export const {}{} = {};",
+                name,
+                string_type,
+                js_string(&query.text)
+            )
+            .unwrap();
+
+            if query.params.is_empty() {
+                continue;
+            }
+            let params_name = names.insert(format!("{}_PARAMS", name), tag)?;
+            let params = query
+                .params
+                .iter()
+                .map(|param| js_string(param))
+                .collect::<Vec<_>>();
+            writeln!(
+                code,
+                "\n/** Parameters of query `{}` in order of placeholders. */\nexport const {}{} = [{}];",
+                tag,
+                params_name,
+                params_type,
+                params.join(", ")
+            )
+            .unwrap();
+        }
+        Ok(code)
+    }
+}
+
+// Generated identifiers with tags, for detection of tags with same identifier.
+#[derive(Default)]
+struct Names(HashMap<String, String>);
+
+impl Names {
+    fn insert(&mut self, name: String, tag: &str) -> Result<String, CodegenError> {
+        match self.0.get(&name) {
+            Some(other) if other != tag => Err(CodegenError::DuplicateName {
+                tag: tag.to_owned(),
+                other: other.clone(),
+                name,
+            }),
+            _ => {
+                self.0.insert(name.clone(), tag.to_owned());
+                Ok(name)
+            }
+        }
+    }
+}
+
+// JavaScript string literal in double quotes.
+fn js_string(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // Line separators end string literals before ES2019.
+            '\u{2028}' | '\u{2029}' => write!(literal, "\\u{:04x}", c as u32).unwrap(),
+            c if c.is_control() => write!(literal, "\\u{:04x}", c as u32).unwrap(),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// Words of tag split by `_`, `-`, `.` and whitespace, `None` if identifier can not be created.
+fn words(tag: &str) -> Option<Vec<&str>> {
+    let words = tag
+        .split(|c: char| matches!(c, '_' | '-' | '.') || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+    let valid = words.first()?.starts_with(char::is_alphabetic)
+        && words.iter().all(|w| w.chars().all(char::is_alphanumeric));
+    valid.then_some(words)
+}
+
+fn camel(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_dollar() {
+        let queries = vec![
+            (
+                "select-user",
+                "SELECT * FROM users WHERE id = :id OR parent = :id AND name = :name",
+            ),
+            ("count.users", "SELECT \"count\"(*) FROM users"),
+        ];
+        assert_eq!(
+            Codegen::new().generate(queries).unwrap(),
+            r#"/// Query `select-user` with positional placeholders.
+pub const SELECT_USER: &str = "SELECT * FROM users WHERE id = $1 OR parent = $1 AND name = $2";
+
+/// Parameters of query `select-user`.
+#[derive(Debug, Clone)]
+pub struct SelectUserParams<P> {
+    pub id: P,
+    pub name: P,
+}
+
+impl<P> SelectUserParams<P> {
+    /// Parameters in order of placeholders.
+    pub fn into_params(self) -> [P; 2] {
+        [self.id, self.name]
+    }
+}
+
+/// Query `count.users` with positional placeholders.
+pub const COUNT_USERS: &str = "SELECT \"count\"(*) FROM users";
+"#
+        );
+    }
+
+    #[test]
+    fn generate_question_repeated() {
+        let code = Codegen::new()
+            .placeholder(Placeholder::Question)
+            .generate(vec![("x", "SELECT :a, :b, :a")])
+            .unwrap();
+        assert!(code.contains("impl<P: Clone> XParams<P> {"));
+        assert!(code.contains("[self.a.clone(), self.b.clone(), self.a.clone()]"));
+    }
+
+    #[test]
+    fn generate_sqlx_macros() {
+        let code = Codegen::new()
+            .sqlx_macros(vec![("Select-User", "SELECT * FROM users WHERE id = :id")])
+            .unwrap();
+        assert_eq!(
+            code,
+            r#"/// `sqlx::query!` with query `Select-User`.
+macro_rules! select_user {
+    ($($args:expr),* $(,)?) => {
+        sqlx::query!("SELECT * FROM users WHERE id = $1", $($args),*)
+    };
+}
+
+/// `sqlx::query_as!` with query `Select-User`.
+macro_rules! select_user_as {
+    ($out:path $(, $args:expr)* $(,)?) => {
+        sqlx::query_as!($out, "SELECT * FROM users WHERE id = $1", $($args),*)
+    };
+}
+"#
+        );
+    }
+
+    #[test]
+    fn generate_row_structs() {
+        let text = "-- name: select_user\n-- returns: id:bigint, name:text?\nSELECT id, name FROM users;\n-- name: x\nSELECT 1;";
+        let queries = crate::parse_detailed(text).unwrap();
+        assert_eq!(
+            Codegen::new()
+                .row_driver(RowDriver::Sqlite)
+                .row_structs(&queries)
+                .unwrap(),
+            r#"/// Row of query `select_user`.
+#[derive(Debug, Clone)]
+pub struct SelectUserRow {
+    pub id: i64,
+    pub name: Option<String>,
+}
+
+impl SelectUserRow {
+    /// Column names from returns annotation.
+    pub const COLUMNS: &'static [&'static str] = &["id", "name"];
+}
+
+impl std::convert::TryFrom<&rusqlite::Row<'_>> for SelectUserRow {
+    type Error = rsyesql::RowError;
+
+    fn try_from(row: &rusqlite::Row<'_>) -> Result<Self, Self::Error> {
+        rsyesql::check_row_shape(Self::COLUMNS, row.as_ref().column_names())?;
+        Ok(Self {
+            id: row.get("id").map_err(|err| rsyesql::RowError::Value {
+                column: "id".to_owned(),
+                err: err.into(),
+            })?,
+            name: row.get("name").map_err(|err| rsyesql::RowError::Value {
+                column: "name".to_owned(),
+                err: err.into(),
+            })?,
+        })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn generate_js_module() {
+        let text = "-- name: a\nSELECT '\"\\'\n  , :x, :x;\n--\n-- Ends */ here.\n--\n-- name: b\nSELECT 1;";
+        let queries = crate::parse_detailed(text).unwrap();
+        let code = Codegen::new()
+            .placeholder(Placeholder::Question)
+            .js_module(JsDialect::JavaScript, text, &queries)
+            .unwrap();
+        assert_eq!(
+            code,
+            r#"/** Query `a` with positional placeholders. */
+export const A = "SELECT '\"\\' , ?, ?;";
+
+/** Parameters of query `a` in order of placeholders. */
+export const A_PARAMS = ["x", "x"];
+
+/**
+ *
+ * Ends *\/ here.
+ *
+ */
+export const B = "SELECT 1;";
+"#
+        );
+    }
+
+    #[test]
+    fn generate_errors() {
+        assert_eq!(
+            Codegen::new().generate(vec![("1st", "SELECT 1")]),
+            Err(CodegenError::InvalidTag("1st".to_owned()))
+        );
+        assert_eq!(
+            Codegen::new().generate(vec![("x", "SELECT :type")]),
+            Err(CodegenError::InvalidParam {
+                tag: "x".to_owned(),
+                param: "type".to_owned()
+            })
+        );
+        assert_eq!(
+            Codegen::new().generate(vec![
+                ("select-user", "SELECT 1"),
+                ("select_user", "SELECT 2")
+            ]),
+            Err(CodegenError::DuplicateName {
+                tag: "select_user".to_owned(),
+                other: "select-user".to_owned(),
+                name: "SELECT_USER".to_owned()
+            })
+        );
+        assert_eq!(
+            Codegen::new().sqlx_macros(vec![("type", "SELECT 1")]),
+            Err(CodegenError::InvalidTag("type".to_owned()))
+        );
+        assert_eq!(
+            Codegen::new().sqlx_macros(vec![("a", "SELECT 1"), ("a_as", "SELECT 2")]),
+            Err(CodegenError::DuplicateName {
+                tag: "a_as".to_owned(),
+                other: "a".to_owned(),
+                name: "a_as".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! [deadpool-postgres](https://docs.rs/deadpool-postgres/) integration.
+//!
+//! Every query prepared with [Client::prepare_cached] when client taken from pool, so statement
+//! cache of every connection contains all queries.
+//!
+//! ```ignore
+//! let queries = rsyesql::parse(include_str!("./queries.sql"))?;
+//! let pool = rsyesql::deadpool::PooledQueries::new(pool, &queries);
+//! let client = pool.get().await?;
+//! let rows = client.query("select_user", &[&user_id]).await?;
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+
+use deadpool_postgres::tokio_postgres::types::ToSql;
+use deadpool_postgres::tokio_postgres::{self, Row, Statement};
+use deadpool_postgres::{Client, Pool, PoolError};
+use indexmap::IndexMap;
+
+use crate::postgres;
+use crate::{Query, QueryExecutor};
+
+quick_error! {
+    /// The error type for [PooledQueries].
+    #[derive(Debug)]
+    pub enum Error {
+        /// Failed to get client from pool.
+        Pool(err: PoolError) {
+            display("Failed to get client from pool: {}", err)
+            cause(err)
+            from()
+        }
+        /// Query preparation or execution failed, see [postgres::Error].
+        Postgres(err: postgres::Error) {
+            display("{}", err)
+            cause(err)
+            from()
+        }
+    }
+}
+
+/// Pool which returns clients with prepared queries.
+#[derive(Debug, Clone)]
+pub struct PooledQueries {
+    pool: Pool,
+    queries: Arc<IndexMap<String, String>>,
+}
+
+impl PooledQueries {
+    /// Pair pool with queries.
+    pub fn new<I, T, Q>(pool: Pool, queries: I) -> Self
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let queries = queries
+            .into_iter()
+            .map(|(tag, query)| (tag.as_ref().to_owned(), query.as_ref().to_owned()))
+            .collect();
+        Self {
+            pool,
+            queries: Arc::new(queries),
+        }
+    }
+
+    /// Client from pool, statement cache warmed with every query.
+    pub async fn get(&self) -> Result<QueriesClient, Error> {
+        let client = self.pool.get().await?;
+        warm(&client, self.queries.iter()).await?;
+        Ok(QueriesClient {
+            client,
+            queries: Arc::clone(&self.queries),
+        })
+    }
+
+    /// Underlying pool.
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+}
+
+/// Prepare every query with [Client::prepare_cached].
+///
+/// Statements already in cache are not prepared again.
+pub async fn warm<I, T, Q>(client: &Client, queries: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = (T, Q)>,
+    T: AsRef<str>,
+    Q: AsRef<str>,
+{
+    for (tag, query) in queries {
+        client
+            .prepare_cached(query.as_ref())
+            .await
+            .map_err(|err| prepare_error(tag.as_ref(), err))?;
+    }
+    Ok(())
+}
+
+/// Pooled client with queries executed by tag.
+pub struct QueriesClient {
+    client: Client,
+    queries: Arc<IndexMap<String, String>>,
+}
+
+impl QueriesClient {
+    /// Cached statement by tag.
+    pub async fn statement(&self, tag: &str) -> Result<Statement, Error> {
+        let query = self
+            .queries
+            .get(tag)
+            .ok_or_else(|| postgres::Error::UnknownTag(tag.to_owned()))?;
+        self.client
+            .prepare_cached(query)
+            .await
+            .map_err(|err| prepare_error(tag, err))
+    }
+
+    /// Execute statement, returns number of modified rows.
+    pub async fn execute(&self, tag: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        let statement = self.statement(tag).await?;
+        self.client
+            .execute(&statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Execute statement, returns resulting rows.
+    pub async fn query(
+        &self,
+        tag: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        let statement = self.statement(tag).await?;
+        self.client
+            .query(&statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Execute statement, returns exactly one row.
+    pub async fn query_one(&self, tag: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error> {
+        let statement = self.statement(tag).await?;
+        self.client
+            .query_one(&statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Execute statement, returns at most one row.
+    pub async fn query_opt(
+        &self,
+        tag: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        let statement = self.statement(tag).await?;
+        self.client
+            .query_opt(&statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Pooled client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Mutable pooled client, for example for transactions.
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    /// Take pooled client back.
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+}
+
+impl QueryExecutor for QueriesClient {
+    type Params<'p> = &'p [&'p (dyn ToSql + Sync)];
+    type Row = Row;
+    type Error = Error;
+
+    fn execute(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<u64, Error>> {
+        QueriesClient::execute(self, tag, params)
+    }
+
+    fn query_many(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Vec<Row>, Error>> {
+        self.query(tag, params)
+    }
+
+    fn query_one(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Option<Row>, Error>> {
+        self.query_opt(tag, params)
+    }
+}
+
+/// Execute every query in definition order inside transaction, see
+/// [postgres::execute_all].
+pub async fn execute_all(
+    client: &mut Client,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error> {
+    Ok(postgres::execute_all(client, queries).await?)
+}
+
+/// Execute queries in definition order, every group of [transactions](crate::transactions)
+/// inside own transaction, see [postgres::execute_transactions].
+pub async fn execute_transactions(
+    client: &mut Client,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error> {
+    Ok(postgres::execute_transactions(client, queries).await?)
+}
+
+/// `EXPLAIN` of every [is_explainable](crate::is_explainable) query, see
+/// [postgres::explain_all].
+pub async fn explain_all(
+    client: &Client,
+    queries: &IndexMap<String, Query>,
+) -> IndexMap<String, Result<String, Error>> {
+    postgres::explain_all(client, queries)
+        .await
+        .into_iter()
+        .map(|(tag, plan)| (tag, plan.map_err(Error::Postgres)))
+        .collect()
+}
+
+/// Run [Query::fixtures] against test database, returns failed tests, see
+/// [postgres::run_fixtures].
+pub async fn run_fixtures(client: &mut Client, queries: &IndexMap<String, Query>) -> Vec<Error> {
+    postgres::run_fixtures(client, queries)
+        .await
+        .into_iter()
+        .map(Error::Postgres)
+        .collect()
+}
+
+fn prepare_error(tag: &str, err: tokio_postgres::Error) -> Error {
+    Error::Postgres(postgres::Error::Prepare {
+        tag: tag.to_owned(),
+        err,
+    })
+}
+
+fn query_error(tag: &str, err: tokio_postgres::Error) -> Error {
+    Error::Postgres(postgres::Error::Query {
+        tag: tag.to_owned(),
+        err,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deadpool_postgres::{Manager, Runtime};
+
+    #[test]
+    fn queries_in_order() {
+        let manager = Manager::new(tokio_postgres::Config::new(), tokio_postgres::NoTls);
+        let pool = Pool::builder(manager)
+            .runtime(Runtime::Tokio1)
+            .build()
+            .unwrap();
+        let queries = crate::parse("-- name: b\nSELECT 2;\n-- name: a\nSELECT 1;").unwrap();
+        let pool = PooledQueries::new(pool, &queries);
+        assert_eq!(
+            pool.queries.iter().collect::<Vec<_>>(),
+            [
+                (&"b".to_owned(), &"SELECT 2;".to_owned()),
+                (&"a".to_owned(), &"SELECT 1;".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_errors() {
+        let err = Error::from(postgres::Error::UnknownTag("x".to_owned()));
+        assert!(matches!(
+            err,
+            Error::Postgres(postgres::Error::UnknownTag(_))
+        ));
+        assert_eq!(
+            err.to_string(),
+            postgres::Error::UnknownTag("x".to_owned()).to_string()
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use std::error::Error;
+use std::fmt;
+
+use miette::{LabeledSpan, NamedSource, SourceCode, SourceSpan};
+
+use crate::ParseError;
+
+/// [ParseError] with source text, implements [miette::Diagnostic].
+///
+/// Rendered by miette report handlers with offending line, label and help message.
+///
+/// ```
+/// use rsyesql::ParseDiagnostic;
+///
+/// let text = "-- name: x\n-- name: y\nSELECT 1;";
+/// let err = rsyesql::parse(text).unwrap_err();
+/// let report = miette::Report::new(ParseDiagnostic::new(err, "queries.sql", text));
+/// ```
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    err: ParseError,
+    src: NamedSource<String>,
+    span: SourceSpan,
+}
+
+impl ParseDiagnostic {
+    /// Attach source text to error, `name` is file name shown in report.
+    pub fn new<N: AsRef<str>, S: Into<String>>(err: ParseError, name: N, text: S) -> Self {
+        let text = text.into();
+        let span = span_at(&text, err.offset());
+        Self {
+            err,
+            src: NamedSource::new(name, text),
+            span,
+        }
+    }
+
+    /// Underlying error.
+    pub fn error(&self) -> &ParseError {
+        &self.err
+    }
+
+    /// Take underlying error back.
+    pub fn into_error(self) -> ParseError {
+        self.err
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.err, f)
+    }
+}
+
+impl Error for ParseDiagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.err)
+    }
+}
+
+impl miette::Diagnostic for ParseDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match &self.err {
+            ParseError::TagOverwritten { .. } => "rsyesql::tag_overwritten",
+            ParseError::QueryWithoutTag { .. } => "rsyesql::query_without_tag",
+            ParseError::InvalidTag { .. } => "rsyesql::invalid_tag",
+            ParseError::TagCollision { .. } => "rsyesql::tag_collision",
+            ParseError::BinaryInput { .. } => "rsyesql::binary_input",
+            ParseError::EmptyQuery { .. } => "rsyesql::empty_query",
+            ParseError::MultipleStatements { .. } => "rsyesql::multiple_statements",
+            ParseError::MissingSemicolon { .. } => "rsyesql::missing_semicolon",
+            ParseError::LimitExceeded { .. } => "rsyesql::limit_exceeded",
+            ParseError::InvalidAnnotation { .. } => "rsyesql::invalid_annotation",
+            ParseError::UnbalancedCondition { .. } => "rsyesql::unbalanced_condition",
+            ParseError::UnsetVariable { .. } => "rsyesql::unset_variable",
+            ParseError::MetaCommand { .. } => "rsyesql::meta_command",
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match &self.err {
+            ParseError::TagOverwritten { .. } => {
+                "previous tag has no query, add query or remove tag"
+            }
+            ParseError::QueryWithoutTag { .. } => "add `-- name: <tag>` line before query",
+            ParseError::InvalidTag { .. } => "rename tag to satisfy `tag_names` rule",
+            ParseError::TagCollision { .. } => {
+                "rename one of tags, they are same after `transform_tags`"
+            }
+            ParseError::BinaryInput { .. } => "check that file is text in UTF-8",
+            ParseError::EmptyQuery { .. } => "add query after tag or remove tag",
+            ParseError::MultipleStatements { .. } => "move every statement under own tag",
+            ParseError::MissingSemicolon { .. } => "terminate query with `;`",
+            ParseError::LimitExceeded { .. } => "split file or increase limit in `ParseOptions`",
+            ParseError::InvalidAnnotation { annotation, .. }
+                if crate::annotations::annotation(annotation, "tx", &Default::default())
+                    .is_some() =>
+            {
+                "use `-- tx: begin <name>` closed by `-- tx: end`, blocks can not be nested"
+            }
+            ParseError::InvalidAnnotation { annotation, .. }
+                if crate::annotations::annotation(annotation, "test", &Default::default())
+                    .is_some() =>
+            {
+                "use `-- test: params name=value ... expect_rows>=1` or `expect_error`"
+            }
+            ParseError::InvalidAnnotation { annotation, .. } if annotation.starts_with("--") => {
+                "use `-- returns: name:type, ...`"
+            }
+            ParseError::InvalidAnnotation { annotation, .. }
+                if annotation.starts_with("retries") =>
+            {
+                "use number of retries, like `retries=3`"
+            }
+            ParseError::InvalidAnnotation { .. } => "use duration with unit, like `timeout=5s`",
+            ParseError::UnbalancedCondition { .. } => {
+                "every `-- if:` should be closed by `-- endif`"
+            }
+            ParseError::UnsetVariable { .. } => "set variable or remove `${...}` from query",
+            ParseError::MetaCommand { .. } => "run file with psql or remove meta-command",
+        };
+        Some(Box::new(help))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.src)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = match &self.err {
+            ParseError::TagOverwritten { .. } => "tag defined here",
+            ParseError::QueryWithoutTag { .. } => "query without tag",
+            ParseError::InvalidTag { .. } | ParseError::TagCollision { .. } => "tag defined here",
+            ParseError::BinaryInput { .. } => "control character",
+            ParseError::EmptyQuery { .. }
+            | ParseError::MultipleStatements { .. }
+            | ParseError::MissingSemicolon { .. } => "query tag",
+            ParseError::LimitExceeded { .. } => "limit exceeded here",
+            ParseError::InvalidAnnotation { .. } => "annotation",
+            ParseError::UnbalancedCondition { .. } => "unbalanced block",
+            ParseError::UnsetVariable { .. } => "unset variable",
+            ParseError::MetaCommand { .. } => "meta-command",
+        };
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some(label.to_owned()),
+            self.span,
+        ))))
+    }
+}
+
+// Span from offset to end of line, `\n` and `\r` are line endings.
+fn span_at(text: &str, offset: usize) -> SourceSpan {
+    let offset = offset.min(text.len());
+    let len = text[offset..]
+        .find(['\n', '\r'])
+        .unwrap_or(text.len() - offset);
+    (offset, len).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miette::{Diagnostic, NarratableReportHandler};
+
+    #[test]
+    fn span_to_line_end() {
+        assert_eq!(span_at("a\r\nbc\rd", 3), (3, 2).into());
+        assert_eq!(span_at("a\nb", 2), (2, 1).into());
+        assert_eq!(span_at("a", 5), (1, 0).into());
+    }
+
+    #[test]
+    fn render_report() {
+        let text = "-- name: x\nSELECT 1;\n-- name: y\n-- name: z\nSELECT 2;";
+        let err = crate::parse(text).unwrap_err();
+        let diagnostic = ParseDiagnostic::new(err, "queries.sql", text);
+        let label = diagnostic.labels().unwrap().next().unwrap();
+        assert_eq!((label.offset(), label.len()), (41, 1));
+
+        let mut report = String::new();
+        NarratableReportHandler::new()
+            .render_report(&mut report, &diagnostic)
+            .unwrap();
+        assert!(report.contains("tag_overwritten"));
+        assert!(report.contains("-- name: z"));
+        assert!(report.contains("previous tag has no query"));
+    }
+}
@@ -0,0 +1,200 @@
+//! [diesel](https://docs.rs/diesel/) integration, queries as `sql_query` with named binds.
+//!
+//! ```ignore
+//! let queries = rsyesql::parse(include_str!("./reports.sql"))?;
+//! let db = rsyesql::diesel::DieselQueries::new(&queries);
+//! let rows: Vec<Report> = db
+//!     .query::<Pg>("monthly_report")?
+//!     .bind::<Integer, _>("year", 2024)
+//!     .bind::<Text, _>("region", "eu".to_owned())
+//!     .into_query()?
+//!     .load(&mut conn)?;
+//! ```
+
+use std::fmt;
+
+use ::diesel::backend::Backend;
+use ::diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use ::diesel::serialize::ToSql;
+use ::diesel::sql_types::HasSqlType;
+use indexmap::IndexMap;
+
+use crate::{positional, Placeholder, PositionalQuery};
+
+quick_error! {
+    /// The error type for [DieselQueries].
+    #[derive(Debug, PartialEq)]
+    pub enum Error {
+        /// Query with tag is not defined.
+        UnknownTag(tag: String) {
+            display(r#"Unknown tag: "{}""#, tag)
+        }
+        /// Value for named parameter of query is not bound.
+        MissingParam { tag: String, param: String } {
+            display(r#"Missing parameter "{}" of "{}""#, param, tag)
+        }
+        /// Bound parameter is not used by query.
+        UnknownParam { tag: String, param: String } {
+            display(r#"Unknown parameter "{}" of "{}""#, param, tag)
+        }
+    }
+}
+
+/// Queries with positional placeholders, converted to `sql_query` by tag.
+#[derive(Debug, Clone)]
+pub struct DieselQueries {
+    placeholder: Placeholder,
+    queries: IndexMap<String, String>,
+}
+
+impl DieselQueries {
+    /// Queries with [Placeholder::Dollar] placeholders (PostgreSQL).
+    pub fn new<I, T, Q>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let queries = queries
+            .into_iter()
+            .map(|(tag, query)| (tag.as_ref().to_owned(), query.as_ref().to_owned()))
+            .collect();
+        Self {
+            placeholder: Placeholder::Dollar,
+            queries,
+        }
+    }
+
+    /// Placeholder style of backend (default: [Placeholder::Dollar]), [Placeholder::Question]
+    /// for MySQL and SQLite.
+    pub fn placeholder(mut self, placeholder: Placeholder) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Query by tag, named parameters are bound by [NamedSqlQuery::bind].
+    pub fn query<'f, DB: Backend>(&self, tag: &str) -> Result<NamedSqlQuery<'f, DB>, Error> {
+        let query = self
+            .queries
+            .get(tag)
+            .ok_or_else(|| Error::UnknownTag(tag.to_owned()))?;
+        Ok(NamedSqlQuery {
+            tag: tag.to_owned(),
+            query: positional(query, self.placeholder),
+            binds: vec![],
+        })
+    }
+}
+
+type Bind<'f, DB> =
+    Box<dyn Fn(BoxedSqlQuery<'f, DB, SqlQuery>) -> BoxedSqlQuery<'f, DB, SqlQuery> + 'f>;
+
+/// Query with values of named parameters, binds are ordered by placeholders in
+/// [into_query](Self::into_query).
+pub struct NamedSqlQuery<'f, DB: Backend> {
+    tag: String,
+    query: PositionalQuery,
+    binds: Vec<(String, Bind<'f, DB>)>,
+}
+
+impl<DB: Backend> fmt::Debug for NamedSqlQuery<'_, DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedSqlQuery")
+            .field("tag", &self.tag)
+            .field("query", &self.query)
+            .field(
+                "binds",
+                &self.binds.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'f, DB: Backend> NamedSqlQuery<'f, DB> {
+    /// Bind value of named parameter with SQL type `ST`, value is cloned for every
+    /// [Placeholder::Question] placeholder of parameter.
+    pub fn bind<ST, V>(mut self, name: &str, value: V) -> Self
+    where
+        DB: HasSqlType<ST>,
+        V: ToSql<ST, DB> + Clone + Send + 'f,
+        ST: Send + 'f,
+    {
+        let bind = move |query: BoxedSqlQuery<'f, DB, SqlQuery>| query.bind::<ST, V>(value.clone());
+        self.binds.push((name.to_owned(), Box::new(bind)));
+        self
+    }
+
+    /// `sql_query` with values bound in order of placeholders.
+    pub fn into_query(self) -> Result<BoxedSqlQuery<'f, DB, SqlQuery>, Error> {
+        let Self { tag, query, binds } = self;
+        if let Some((name, _)) = binds.iter().find(|(name, _)| !query.params.contains(name)) {
+            return Err(Error::UnknownParam {
+                tag,
+                param: name.clone(),
+            });
+        }
+        let mut result = ::diesel::sql_query(query.text).into_boxed::<DB>();
+        for param in &query.params {
+            let (_, bind) = binds
+                .iter()
+                .find(|(name, _)| name == param)
+                .ok_or_else(|| Error::MissingParam {
+                    tag: tag.clone(),
+                    param: param.clone(),
+                })?;
+            result = bind(result);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(all(test, feature = "diesel"))]
+mod tests {
+    use super::*;
+    use ::diesel::debug_query;
+    use ::diesel::pg::Pg;
+    use ::diesel::sql_types::{Integer, Text};
+
+    #[test]
+    fn binds_in_order() {
+        let queries =
+            crate::parse("-- name: x\nSELECT * FROM t WHERE a = :a AND b = :b OR a = :a;").unwrap();
+        let db = DieselQueries::new(&queries);
+        let query = db
+            .query::<Pg>("x")
+            .unwrap()
+            .bind::<Text, _>("b", "b".to_owned())
+            .bind::<Integer, _>("a", 1)
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            debug_query(&query).to_string(),
+            r#"SELECT * FROM t WHERE a = $1 AND b = $2 OR a = $1; -- binds: [1, "b"]"#
+        );
+
+        let db = db.placeholder(Placeholder::Question);
+        let query = db
+            .query::<Pg>("x")
+            .unwrap()
+            .bind::<Integer, _>("a", 1)
+            .bind::<Integer, _>("b", 2)
+            .into_query()
+            .unwrap();
+        assert_eq!(
+            debug_query(&query).to_string(),
+            "SELECT * FROM t WHERE a = ? AND b = ? OR a = ?; -- binds: [1, 2, 1]"
+        );
+
+        let query = db.query::<Pg>("x").unwrap().bind::<Integer, _>("a", 1);
+        assert_eq!(
+            query.into_query().err().unwrap().to_string(),
+            r#"Missing parameter "b" of "x""#
+        );
+        let query = db.query::<Pg>("x").unwrap().bind::<Integer, _>("c", 1);
+        assert_eq!(
+            query.into_query().err().unwrap().to_string(),
+            r#"Unknown parameter "c" of "x""#
+        );
+        assert!(matches!(db.query::<Pg>("y"), Err(Error::UnknownTag(_))));
+    }
+}
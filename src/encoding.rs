@@ -0,0 +1,42 @@
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+// Decode bytes using BOM if present, otherwise as UTF-8 with fallback to windows-1252 (superset
+// of latin-1) when bytes are not valid UTF-8.
+pub(crate) fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return text;
+    }
+
+    match UTF_8.decode_without_bom_handling_and_without_replacement(bytes) {
+        Some(text) => text,
+        None => WINDOWS_1252.decode_without_bom_handling(bytes).0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_utf8() {
+        assert_eq!(decode("SELECT 'ё';".as_bytes()), "SELECT 'ё';");
+        assert_eq!(decode(b"\xEF\xBB\xBFSELECT 1;"), "SELECT 1;");
+    }
+
+    #[test]
+    fn decode_utf16() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "SELECT 'ё';".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        assert_eq!(decode(&bytes), "SELECT 'ё';");
+    }
+
+    #[test]
+    fn decode_latin1() {
+        assert_eq!(decode(b"SELECT '\xE9';"), "SELECT 'é';");
+    }
+}
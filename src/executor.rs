@@ -0,0 +1,134 @@
+use std::future::Future;
+
+/// Queries executed by tag, implemented by driver integrations.
+///
+/// Application code written against this trait can use any driver, or mock in tests. Methods
+/// return futures, for synchronous drivers (rusqlite) futures are already completed.
+///
+/// Implemented by `postgres::PreparedQueries`, `deadpool::QueriesClient`, `sqlite::Yesql`,
+/// `mysql::PooledQueries` and `sqlx::SqlxQueries`.
+///
+/// ```
+/// use std::future::{ready, Future};
+/// use rsyesql::QueryExecutor;
+///
+/// struct Mock;
+///
+/// impl QueryExecutor for Mock {
+///     type Params<'p> = &'p [i64];
+///     type Row = Vec<i64>;
+///     type Error = String;
+///
+///     fn execute(
+///         &self,
+///         _tag: &str,
+///         _params: Self::Params<'_>,
+///     ) -> impl Future<Output = Result<u64, String>> {
+///         ready(Ok(1))
+///     }
+///
+///     fn query_many(
+///         &self,
+///         _tag: &str,
+///         params: Self::Params<'_>,
+///     ) -> impl Future<Output = Result<Vec<Vec<i64>>, String>> {
+///         ready(Ok(vec![params.to_vec()]))
+///     }
+/// }
+/// ```
+pub trait QueryExecutor {
+    /// Query parameters.
+    type Params<'p>;
+    /// Row returned by query.
+    type Row;
+    /// Execution error.
+    type Error;
+
+    /// Execute query, returns number of modified rows.
+    fn execute(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<u64, Self::Error>>;
+
+    /// Execute query, returns all rows.
+    fn query_many(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Vec<Self::Row>, Self::Error>>;
+
+    /// Execute query, returns first row or `None`.
+    ///
+    /// Default implementation takes first row from [query_many](Self::query_many).
+    fn query_one(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Option<Self::Row>, Self::Error>> {
+        let rows = self.query_many(tag, params);
+        async move { rows.await.map(|rows| rows.into_iter().next()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::future::ready;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    #[derive(Default)]
+    struct Mock {
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl QueryExecutor for Mock {
+        type Params<'p> = &'p [&'p str];
+        type Row = String;
+        type Error = ();
+
+        fn execute(
+            &self,
+            tag: &str,
+            _params: Self::Params<'_>,
+        ) -> impl Future<Output = Result<u64, ()>> {
+            self.calls.borrow_mut().push(tag.to_owned());
+            ready(Ok(0))
+        }
+
+        fn query_many(
+            &self,
+            tag: &str,
+            params: Self::Params<'_>,
+        ) -> impl Future<Output = Result<Vec<String>, ()>> {
+            self.calls.borrow_mut().push(tag.to_owned());
+            ready(Ok(params.iter().map(|p| p.to_string()).collect()))
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    async fn first_user<E: for<'p> QueryExecutor<Params<'p> = &'p [&'p str]>>(
+        executor: &E,
+    ) -> Option<E::Row> {
+        executor.query_one("select_users", &["a", "b"]).await.ok()?
+    }
+
+    #[test]
+    fn query_one_default() {
+        let mock = Mock::default();
+        assert_eq!(block_on(first_user(&mock)), Some("a".to_owned()));
+        assert_eq!(block_on(mock.execute("delete", &[])), Ok(0));
+        assert_eq!(*mock.calls.borrow(), vec!["select_users", "delete"]);
+    }
+}
@@ -0,0 +1,216 @@
+//! C ABI for sharing parser with other languages through `cdylib`.
+//!
+//! Build shared library with `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! ```c
+//! char *error = NULL;
+//! rsyesql_queries *queries = rsyesql_parse(text, strlen(text), &error);
+//! if (queries == NULL) {
+//!     fprintf(stderr, "%s\n", error);
+//!     rsyesql_string_free(error);
+//!     return 1;
+//! }
+//!
+//! rsyesql_iter *iter = rsyesql_queries_iter(queries);
+//! const char *tag, *query;
+//! while (rsyesql_iter_next(iter, &tag, &query)) {
+//!     printf("%s: %s\n", tag, query);
+//! }
+//! rsyesql_iter_free(iter);
+//! rsyesql_queries_free(queries);
+//! ```
+//!
+//! Strings returned by [rsyesql_iter_next] are owned by queries and valid until
+//! [rsyesql_queries_free].
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+use std::str;
+
+/// Parsed queries, opaque for C code.
+#[allow(non_camel_case_types)]
+pub struct rsyesql_queries {
+    queries: Vec<(CString, CString)>,
+}
+
+/// Iterator over `(tag, query)` pairs of [rsyesql_queries], opaque for C code.
+#[allow(non_camel_case_types)]
+pub struct rsyesql_iter {
+    queries: *const rsyesql_queries,
+    pos: usize,
+}
+
+/// Parse `len` bytes of UTF-8 text.
+///
+/// Returns queries, or null on error with error message written to `error` (if not null), message
+/// should be freed with [rsyesql_string_free].
+///
+/// # Safety
+///
+/// `text` should point to `len` readable bytes, `error` should be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_parse(
+    text: *const c_char,
+    len: usize,
+    error: *mut *mut c_char,
+) -> *mut rsyesql_queries {
+    let bytes = if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(text as *const u8, len)
+    };
+    let result = str::from_utf8(bytes)
+        .map_err(|err| format!("Invalid UTF-8: {}", err))
+        .and_then(|text| crate::parse(text).map_err(|err| err.to_string()));
+
+    match result {
+        Ok(queries) => {
+            let queries = queries
+                .into_iter()
+                .map(|(tag, query)| (to_cstring(tag), to_cstring(query)))
+                .collect();
+            Box::into_raw(Box::new(rsyesql_queries { queries }))
+        }
+        Err(err) => {
+            if !error.is_null() {
+                *error = to_cstring(err).into_raw();
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Number of queries.
+///
+/// # Safety
+///
+/// `queries` should be returned by [rsyesql_parse] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_queries_len(queries: *const rsyesql_queries) -> usize {
+    let queries = &*queries;
+    queries.queries.len()
+}
+
+/// Free queries returned by [rsyesql_parse], null is ignored.
+///
+/// # Safety
+///
+/// `queries` should be null or returned by [rsyesql_parse] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_queries_free(queries: *mut rsyesql_queries) {
+    if !queries.is_null() {
+        drop(Box::from_raw(queries));
+    }
+}
+
+/// Iterator over queries in definition order, should be freed with [rsyesql_iter_free].
+///
+/// # Safety
+///
+/// `queries` should be returned by [rsyesql_parse] and outlive iterator.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_queries_iter(
+    queries: *const rsyesql_queries,
+) -> *mut rsyesql_iter {
+    Box::into_raw(Box::new(rsyesql_iter { queries, pos: 0 }))
+}
+
+/// Write next pair to `tag` and `query`, returns `false` if iterator exhausted.
+///
+/// # Safety
+///
+/// `iter` should be returned by [rsyesql_queries_iter] and not freed, `tag` and `query` should
+/// be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_iter_next(
+    iter: *mut rsyesql_iter,
+    tag: *mut *const c_char,
+    query: *mut *const c_char,
+) -> bool {
+    let iter = &mut *iter;
+    let queries = &*iter.queries;
+    match queries.queries.get(iter.pos) {
+        Some((t, q)) => {
+            iter.pos += 1;
+            *tag = t.as_ptr();
+            *query = q.as_ptr();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Free iterator returned by [rsyesql_queries_iter], null is ignored.
+///
+/// # Safety
+///
+/// `iter` should be null or returned by [rsyesql_queries_iter] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_iter_free(iter: *mut rsyesql_iter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+/// Free error message returned by [rsyesql_parse], null is ignored.
+///
+/// # Safety
+///
+/// `s` should be null or returned by [rsyesql_parse] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rsyesql_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+// Parser rejects control characters, so text can not contain NUL.
+fn to_cstring(s: String) -> CString {
+    CString::new(s).expect("NUL rejected by parser")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn parse_and_iterate() {
+        let text = "--name: a\nselect 1;\n--name: b\nselect 2;";
+        unsafe {
+            let queries =
+                rsyesql_parse(text.as_ptr() as *const c_char, text.len(), ptr::null_mut());
+            assert_eq!(rsyesql_queries_len(queries), 2);
+
+            let iter = rsyesql_queries_iter(queries);
+            let (mut tag, mut query) = (ptr::null(), ptr::null());
+            let mut pairs = vec![];
+            while rsyesql_iter_next(iter, &mut tag, &mut query) {
+                pairs.push((
+                    CStr::from_ptr(tag).to_str().unwrap(),
+                    CStr::from_ptr(query).to_str().unwrap(),
+                ));
+            }
+            assert_eq!(pairs, vec![("a", "select 1;"), ("b", "select 2;")]);
+            rsyesql_iter_free(iter);
+            rsyesql_queries_free(queries);
+        }
+    }
+
+    #[test]
+    fn parse_error() {
+        let text = "select 1;";
+        unsafe {
+            let mut error = ptr::null_mut();
+            let queries = rsyesql_parse(text.as_ptr() as *const c_char, text.len(), &mut error);
+            assert!(queries.is_null());
+            assert_eq!(
+                CStr::from_ptr(error).to_str().unwrap(),
+                crate::parse(text).unwrap_err().to_string()
+            );
+            rsyesql_string_free(error);
+        }
+    }
+}
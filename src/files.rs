@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+
+use crate::{ParseOptions, Query};
+
+quick_error! {
+    /// The error type for parse SQL queries from files.
+    #[derive(Debug, PartialEq)]
+    pub enum FileError {
+        /// Failed to parse file.
+        Parse { file: PathBuf, err: crate::ParseError } {
+            display("{}: {}", file.display(), err)
+            cause(err)
+        }
+        /// Same tag defined in different files.
+        DuplicateTag { tag: String, file: PathBuf, line: usize, other_file: PathBuf, other_line: usize } {
+            display(r#"Tag "{}" at {}:{} already defined at {}:{}"#,
+                tag, file.display(), line, other_file.display(), other_line)
+        }
+    }
+}
+
+/// Parse SQL queries from file, errors and queries contain file name.
+///
+/// Same as [parse_detailed](crate::parse_detailed), but [Query::file] is set and error is
+/// [FileError] with file name.
+///
+/// ```
+/// use std::path::Path;
+///
+/// let queries = rsyesql::parse_named("users.sql", "-- name: x\nSELECT 1;").unwrap();
+/// assert_eq!(queries["x"].file.as_deref(), Some(Path::new("users.sql")));
+///
+/// let err = rsyesql::parse_named("users.sql", "SELECT 1;").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     r#"users.sql: Query without tag (line: 1): "SELECT 1;""#
+/// );
+/// ```
+pub fn parse_named<P: AsRef<Path>, S: AsRef<str>>(
+    file: P,
+    text: S,
+) -> Result<IndexMap<String, Query>, FileError> {
+    ParseOptions::new().parse_named(file, text)
+}
+
+/// Parse SQL queries from many files to one map, see [parse_named].
+///
+/// Queries are in order of files and definition in file. If same tag defined in different files
+/// [FileError::DuplicateTag] returned with both locations.
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// let files = vec![
+///     (PathBuf::from("a.sql"), "-- name: x\nSELECT 1;".to_owned()),
+///     (PathBuf::from("b.sql"), "-- name: y\nSELECT 2;".to_owned()),
+/// ];
+/// let queries = rsyesql::parse_many(files).unwrap();
+/// assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["x", "y"]);
+/// ```
+pub fn parse_many<I, P, S>(files: I) -> Result<IndexMap<String, Query>, FileError>
+where
+    I: IntoIterator<Item = (P, S)>,
+    P: AsRef<Path>,
+    S: AsRef<str>,
+{
+    ParseOptions::new().parse_many(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseError;
+
+    #[test]
+    fn many_duplicate() {
+        let files = vec![
+            ("a.sql", "--name: x\n1;"),
+            ("b.sql", "--name: y\n2;\n\n--name: x\n3;"),
+        ];
+        assert_eq!(
+            parse_many(files).err(),
+            Some(FileError::DuplicateTag {
+                tag: "x".to_owned(),
+                file: PathBuf::from("b.sql"),
+                line: 4,
+                other_file: PathBuf::from("a.sql"),
+                other_line: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn named_error() {
+        assert_eq!(
+            parse_named("a.sql", "--name: x\n--name: x").err(),
+            Some(FileError::Parse {
+                file: PathBuf::from("a.sql"),
+                err: ParseError::TagOverwritten {
+                    line: 2,
+                    column: 9,
+                    offset: 18,
+                    tag: "x".to_owned()
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use indexmap::IndexMap;
+
+/// Query with normalized whitespace and literals, queries which differ only by constants have
+/// same fingerprint.
+///
+/// Outside of quoted identifiers runs of whitespace replaced by single space and text lowercased,
+/// string (`'...'`) and numeric literals replaced by `?`, terminating semicolon removed. Parameters
+/// (`:name`, `$1`) are kept as is.
+///
+/// ```
+/// let a = rsyesql::fingerprint("SELECT * FROM users WHERE age > 18 AND kind = 'admin';");
+/// let b = rsyesql::fingerprint("select *\n  from users where age > 21 and kind = 'user'");
+/// assert_eq!(a, b);
+/// assert_eq!(a, "select * from users where age > ? and kind = ?");
+/// ```
+pub fn fingerprint(query: &str) -> String {
+    let mut text = String::with_capacity(query.len());
+    let mut chars = query
+        .trim()
+        .trim_end_matches(';')
+        .trim_end()
+        .chars()
+        .peekable();
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // Doubled quote is escaped quote.
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.next_if_eq(&'\'').is_none() {
+                        break;
+                    }
+                }
+                text.push('?');
+            }
+            '"' => {
+                text.push(c);
+                for c in chars.by_ref() {
+                    text.push(c);
+                    if c == '"' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                text.push(' ');
+            }
+            c if c.is_ascii_digit() && !prev.is_some_and(is_word_char) => {
+                while chars.next_if(|&c| c.is_ascii_digit() || c == '.').is_some() {}
+                text.push('?');
+            }
+            c => text.extend(c.to_lowercase()),
+        }
+        prev = text.chars().last();
+    }
+
+    text
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '?')
+}
+
+/// Groups of tags with same [fingerprint], only groups with more than one tag returned.
+///
+/// Groups and tags in groups are in definition order.
+pub fn duplicates<I, T, Q>(queries: I) -> Vec<Vec<String>>
+where
+    I: IntoIterator<Item = (T, Q)>,
+    T: AsRef<str>,
+    Q: AsRef<str>,
+{
+    let mut groups: IndexMap<String, Vec<String>> = IndexMap::new();
+    for (tag, query) in queries {
+        groups
+            .entry(fingerprint(query.as_ref()))
+            .or_default()
+            .push(tag.as_ref().to_owned());
+    }
+    groups
+        .into_iter()
+        .map(|(_, tags)| tags)
+        .filter(|tags| tags.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_literals() {
+        assert_eq!(
+            fingerprint(r#"SELECT "Id", 'it''s', 1.5, t2.x, $1 FROM t2 LIMIT 10;"#),
+            r#"select "Id", ?, ?, t2.x, $1 from t2 limit ?"#
+        );
+    }
+
+    #[test]
+    fn duplicates_grouped() {
+        let queries = vec![
+            ("a", "SELECT * FROM t WHERE x = 1"),
+            ("b", "SELECT * FROM u"),
+            ("c", "select *  from t where x = 2;"),
+        ];
+        assert_eq!(duplicates(queries), vec![vec!["a", "c"]]);
+    }
+}
@@ -0,0 +1,111 @@
+use indexmap::IndexMap;
+use sqlformat::{Indent, QueryParams};
+
+/// Options for [format_queries].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    indent: u8,
+    tabs: bool,
+    uppercase: Option<bool>,
+    inline: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            tabs: false,
+            uppercase: None,
+            inline: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of spaces for indentation (default: `2`).
+    pub fn indent(mut self, spaces: u8) -> Self {
+        self.indent = spaces;
+        self
+    }
+
+    /// Indent with tabs instead of spaces (default: `false`).
+    pub fn tabs(mut self, yes: bool) -> Self {
+        self.tabs = yes;
+        self
+    }
+
+    /// Change case of reserved keywords: `true` for upper case, `false` for lower case (default:
+    /// case is not changed).
+    pub fn uppercase(mut self, yes: bool) -> Self {
+        self.uppercase = Some(yes);
+        self
+    }
+
+    /// Keep every query on single line (default: `false`).
+    pub fn inline(mut self, yes: bool) -> Self {
+        self.inline = yes;
+        self
+    }
+}
+
+/// Format query with [sqlformat](https://docs.rs/sqlformat/).
+///
+/// Placeholders (`:name`, `$1`, `?`) are kept as is.
+///
+/// ```
+/// use rsyesql::{format_query, FormatOptions};
+///
+/// let query = format_query("select id, name from users where id = :id;", &FormatOptions::new().uppercase(true));
+/// assert_eq!(query, "SELECT\n  id,\n  name\nFROM\n  users\nWHERE\n  id = :id;");
+/// ```
+pub fn format_query(query: &str, options: &FormatOptions) -> String {
+    let options = sqlformat::FormatOptions {
+        indent: if options.tabs {
+            Indent::Tabs
+        } else {
+            Indent::Spaces(options.indent)
+        },
+        uppercase: options.uppercase,
+        inline: options.inline,
+        ..Default::default()
+    };
+    sqlformat::format(query, &QueryParams::None, &options)
+}
+
+/// Format every query in place, see [format_query].
+pub fn format_queries(queries: &mut IndexMap<String, String>, options: FormatOptions) {
+    for query in queries.values_mut() {
+        *query = format_query(query, &options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_all() {
+        let mut queries =
+            crate::parse("--name: a\nselect 1;\n--name: b\nSELECT a FROM t;").unwrap();
+        format_queries(
+            &mut queries,
+            FormatOptions::new().uppercase(false).indent(4),
+        );
+        assert_eq!(queries["a"], "select\n    1;");
+        assert_eq!(queries["b"], "select\n    a\nfrom\n    t;");
+    }
+
+    #[test]
+    fn format_inline_tabs() {
+        let options = FormatOptions::new().tabs(true).inline(true);
+        assert_eq!(
+            format_query("SELECT  a,\n b FROM t", &options),
+            "SELECT a, b FROM t"
+        );
+    }
+}
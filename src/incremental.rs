@@ -0,0 +1,297 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::ops::Range;
+
+use crate::annotations;
+use crate::options::Sort;
+use crate::{
+    count_line_breaks, parse_line, split_lines, LineType, Map, ParseError, ParseOptions, Query,
+};
+
+/// Replacement of byte range in text, see [ParseOptions::reparse].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit<'a> {
+    /// Replaced byte range in text before edit.
+    pub range: Range<usize>,
+    /// New text of range.
+    pub text: &'a str,
+}
+
+impl ParseOptions {
+    /// Apply edit to `text` and update `queries` parsed from `text` with these options.
+    ///
+    /// Only queries with tags around edit are parsed again, positions of other queries are
+    /// shifted. Whole text is parsed if edit may affect other queries: text with BOM or
+    /// `/* ... */` comments, conditional or transaction blocks, queries without tag (see
+    /// [prelude](Self::prelude)), limit of tags or not definition order.
+    ///
+    /// On error `text` is edited and `queries` are not changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range is out of `text` or not on char boundary.
+    ///
+    /// ```
+    /// use rsyesql::{ParseOptions, TextEdit};
+    ///
+    /// let options = ParseOptions::new();
+    /// let mut text = "-- name: a\nSELECT 1;\n-- name: b\nSELECT 2;".to_owned();
+    /// let mut queries = options.parse_detailed(&text).unwrap();
+    /// let edit = TextEdit {
+    ///     range: 18..19,
+    ///     text: "42",
+    /// };
+    /// options.reparse(&mut text, &mut queries, edit).unwrap();
+    /// assert_eq!(queries["a"].text, "SELECT 42;");
+    /// assert_eq!(queries["b"].line, 3);
+    /// ```
+    pub fn reparse(
+        &self,
+        text: &mut String,
+        queries: &mut Map<Query>,
+        edit: TextEdit<'_>,
+    ) -> Result<(), ParseError> {
+        #[cfg(feature = "std")]
+        let file = queries.values().find_map(|query| query.file.clone());
+        let set_file = |queries: &mut Map<Query>| {
+            #[cfg(feature = "std")]
+            for query in queries.values_mut() {
+                query.file = file.clone();
+            }
+            #[cfg(not(feature = "std"))]
+            let _ = queries;
+        };
+
+        let bom = text.starts_with('\u{feff}');
+        // Region from not edited tag line before edit to tag line after edit.
+        let before = queries
+            .values()
+            .filter(|query| line_end(text, query.tag_pos) <= edit.range.start)
+            .count();
+        let first = before.saturating_sub(1);
+        let after = queries
+            .values()
+            .position(|query| line_start(text, query.tag_pos) > edit.range.end)
+            .unwrap_or(queries.len());
+        let (old_start, first_line) = match queries.get_index(first) {
+            Some((_, query)) if before > 0 => (line_start(text, query.tag_pos), query.line - 1),
+            _ => (0, 0),
+        };
+        let old_end = match queries.get_index(after) {
+            Some((_, query)) => line_start(text, query.tag_pos),
+            None => text.len(),
+        };
+        let old_lines = count_line_breaks(&text[old_start..old_end]);
+        // Queries defined by few tags with same name can not be parsed by regions.
+        let overlaps = queries
+            .values()
+            .take(after)
+            .enumerate()
+            .any(|(idx, query)| {
+                let last = query.lines.last().map_or(query.line, |&(_, line)| line);
+                let bound = if idx < first {
+                    first_line
+                } else {
+                    first_line + old_lines
+                };
+                last > bound
+            })
+            // Tag without query lines defined again in region.
+            || split_lines(&text[old_start..old_end])
+                .filter(|(line, _)| parse_line(line, self).0 == LineType::Tag)
+                .count()
+                != after - first;
+
+        // Edit which splits or joins `\r\n` changes line breaks outside of region.
+        let line_break = changes_line_break(text, &edit);
+
+        text.replace_range(edit.range.clone(), edit.text);
+        let end = old_end - edit.range.len() + edit.text.len();
+        let region = &text[old_start..end];
+        let new_lines = count_line_breaks(region);
+        // Tag without query before next tag is error in whole text.
+        if bom
+            || overlaps
+            || line_break
+            || !self.is_local(text)
+            || (after < queries.len() && ends_with_tag(region, self))
+        {
+            let mut parsed = self.parse_detailed(text.as_str())?;
+            set_file(&mut parsed);
+            *queries = parsed;
+            return Ok(());
+        }
+
+        let mut parsed = self
+            .parse_detailed(region)
+            .map_err(|err| err.shift(first_line, old_start))?;
+        if parsed.keys().any(|tag| {
+            queries
+                .get_index_of(tag)
+                .is_some_and(|idx| idx < first || idx >= after)
+        }) {
+            let mut parsed = self.parse_detailed(text.as_str())?;
+            set_file(&mut parsed);
+            *queries = parsed;
+            return Ok(());
+        }
+
+        for query in parsed.values_mut() {
+            query.line += first_line;
+            query.tag_pos += old_start;
+            for (_, line) in query.lines.iter_mut() {
+                *line += first_line;
+            }
+        }
+        set_file(&mut parsed);
+
+        let mut result = Map::default();
+        let old = core::mem::take(queries);
+        for (idx, (tag, mut query)) in old.into_iter().enumerate() {
+            if idx == first {
+                result.extend(core::mem::take(&mut parsed));
+            }
+            if idx < first {
+                result.insert(tag, query);
+            } else if idx >= after {
+                query.line = query.line - old_lines + new_lines;
+                query.tag_pos = query.tag_pos - old_end + end;
+                for (_, line) in query.lines.iter_mut() {
+                    *line = *line - old_lines + new_lines;
+                }
+                result.insert(tag, query);
+            }
+        }
+        result.extend(parsed);
+        *queries = result;
+        Ok(())
+    }
+
+    // Queries in text can be parsed independently.
+    fn is_local(&self, text: &str) -> bool {
+        matches!(self.order, Sort::Definition)
+            && self.prelude.is_none()
+            && self.max_tags.is_none()
+            && self.max_input_size.is_none()
+            && !text.starts_with('\u{feff}')
+            && matches!(self.language.remove_block_comments(text), Cow::Borrowed(_))
+            && !split_lines(text).any(|(line, _)| {
+                annotations::annotation(line, "if", &self.language).is_some()
+                    || annotations::is_endif(line, &self.language)
+                    || annotations::annotation(line, "tx", &self.language).is_some()
+            })
+    }
+}
+
+// Start of line with byte position.
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind(['\n', '\r']).map_or(0, |idx| idx + 1)
+}
+
+// Start of next line after byte position.
+fn line_end(text: &str, pos: usize) -> usize {
+    match text[pos..].find(['\n', '\r']).map(|idx| pos + idx) {
+        Some(idx) if text[idx..].starts_with("\r\n") => idx + 2,
+        Some(idx) => idx + 1,
+        None => text.len() + 1,
+    }
+}
+
+// Edit starts or ends between `\r` and `\n`, or forms `\r\n` with text around it.
+fn changes_line_break(text: &str, edit: &TextEdit<'_>) -> bool {
+    let bytes = text.as_bytes();
+    let (start, end) = (edit.range.start, edit.range.end);
+    let before = start.checked_sub(1).map(|idx| bytes[idx]);
+    let after = bytes.get(end).copied();
+    let first = edit.text.bytes().next().or(after);
+    let last = edit.text.bytes().last().or(before);
+    (before == Some(b'\r') && bytes.get(start) == Some(&b'\n'))
+        || (end > 0 && bytes[end - 1] == b'\r' && after == Some(b'\n'))
+        || (before == Some(b'\r') && first == Some(b'\n'))
+        || (last == Some(b'\r') && after == Some(b'\n'))
+}
+
+// Last not empty line is tag.
+fn ends_with_tag(text: &str, options: &ParseOptions) -> bool {
+    split_lines(text)
+        .map(|(line, _)| parse_line(line, options).0)
+        .filter(|ty| *ty != LineType::Empty)
+        .last()
+        == Some(LineType::Tag)
+}
+
+/// Apply edit to `text` and update `queries` parsed from `text` with default options, see
+/// [ParseOptions::reparse].
+pub fn reparse(
+    text: &mut String,
+    queries: &mut Map<Query>,
+    edit: TextEdit<'_>,
+) -> Result<(), ParseError> {
+    ParseOptions::new().reparse(text, queries, edit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_detailed;
+    use alloc::borrow::ToOwned;
+    use alloc::vec::Vec;
+
+    // Reparse result is same as parse of edited text.
+    fn check(text: &str, range: Range<usize>, replacement: &str) -> Map<Query> {
+        let mut text = text.to_owned();
+        let mut queries = parse_detailed(&text).unwrap();
+        let edit = TextEdit {
+            range,
+            text: replacement,
+        };
+        reparse(&mut text, &mut queries, edit).unwrap();
+        assert_eq!(queries, parse_detailed(&text).unwrap());
+        queries
+    }
+
+    const TEXT: &str = "-- name: a\nselect 1;\n\n-- name: b\nselect\n  2;\n-- name: c\nselect 3;";
+
+    #[test]
+    fn reparse_region() {
+        let queries = check(TEXT, 40..43, "20\n\n");
+        assert_eq!(queries["b"].text, "select 20 ;");
+        assert_eq!(queries["c"].line, 9);
+
+        let queries = check(TEXT, 0..22, "");
+        assert_eq!(queries.keys().collect::<Vec<_>>(), ["b", "c"]);
+        check(TEXT, 21..21, "-- name: x\nselect 0;\n");
+        check(TEXT, 0..0, "-- comment\n-- name: z\nselect 0;\n");
+        check(TEXT, 0..TEXT.len(), "");
+        check(TEXT, 65..65, "\n-- name: d\nselect 4;");
+        check(TEXT, 45..45, "select 22;\n");
+        check(
+            "-- name: a\nx;\n-- name: b\ny;\ny;\n-- name: a\n\r\n",
+            26..39,
+            "",
+        );
+        let queries = check(TEXT, 25..32, "name: c");
+        assert_eq!(queries.keys().collect::<Vec<_>>(), ["a", "c"]);
+
+        let text = "\r\n-- name: a\ny;-- name: b\n-- name: c\nx\n\r\n";
+        check(text, 40..40, "-- name: a\n");
+        check("-- name: a\nx\r\n-- name: b\ny\n", 14..14, "\n");
+        check("-- name: a\nx\n-- name: b\ny\r", 13..13, "\r");
+        check("-- name: a\nx\r-- name: b\ny\n-- name: c\nz", 14..25, "\n");
+    }
+
+    #[test]
+    fn reparse_error() {
+        let mut text = TEXT.to_owned();
+        let mut queries = parse_detailed(&text).unwrap();
+        let edit = TextEdit {
+            range: 31..31,
+            text: "\n-- name: x",
+        };
+        assert_eq!(
+            reparse(&mut text, &mut queries, edit).err(),
+            parse_detailed(&text).err()
+        );
+        assert_eq!(queries, parse_detailed(TEXT).unwrap());
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt::Write;
+
+use crate::split::dollar_quote_end;
+
+quick_error! {
+    /// The error type for [Interpolation::apply].
+    #[derive(Debug, PartialEq)]
+    pub enum InterpolationError {
+        /// Value is set, but query has no parameter with name.
+        UnusedValue(name: String) {
+            display(r#"Parameter "{}" is not used by query"#, name)
+        }
+        /// Value can not be quoted: contains NUL character, empty identifier or not finite
+        /// number.
+        InvalidValue(name: String) {
+            display(r#"Value of "{}" can not be quoted"#, name)
+        }
+    }
+}
+
+/// Quoting rules of database for [Interpolation].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quoting {
+    /// `"ident"` and `'text'` (PostgreSQL with `standard_conforming_strings`, SQLite).
+    Ansi,
+    /// `` `ident` `` and `'text'` with escaped backslashes (MySQL, MariaDB).
+    MySql,
+    /// `[ident]` and `N'text'` (SQL Server).
+    SqlServer,
+}
+
+/// Value substituted for named parameter, see [Interpolation].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    /// Quoted identifier (table, column, schema).
+    Ident(String),
+    /// Quoted string literal.
+    Text(String),
+    /// Integer literal.
+    Int(i64),
+    /// Float literal.
+    Float(f64),
+    /// `TRUE` or `FALSE`.
+    Bool(bool),
+    /// `NULL`.
+    Null,
+}
+
+/// Substitution of named parameters (`:name`) with quoted identifiers and literals, for
+/// positions where bind parameters are not allowed (DDL, identifiers, `SET` values).
+///
+/// Parameters without value are kept for binding, parameters in quoted strings and identifiers
+/// and casts (`::type`) are not replaced, same as [positional](crate::positional). Quotes are
+/// tracked by rules of [Quoting]: backslash escapes for [Quoting::MySql], dollar quotes
+/// (`$$...$$`) for [Quoting::Ansi], `[...]` identifiers for [Quoting::SqlServer]. Parameters in
+/// `--` and `/* */` comments are not replaced. Negative numbers are wrapped in parentheses, so `0-:n` is
+/// not turned into comment.
+///
+/// ```
+/// use rsyesql::{Interpolation, Quoting};
+///
+/// let query = Interpolation::new(Quoting::Ansi)
+///     .ident("table", "user \"accounts\"")
+///     .text("role", "app's")
+///     .apply("GRANT SELECT ON :table TO :role; SELECT * FROM :table WHERE id = :id;")
+///     .unwrap();
+/// assert_eq!(
+///     query,
+///     r#"GRANT SELECT ON "user ""accounts""" TO 'app''s'; SELECT * FROM "user ""accounts""" WHERE id = :id;"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpolation {
+    quoting: Quoting,
+    values: Vec<(String, SqlValue)>,
+}
+
+impl Interpolation {
+    /// Interpolation without values.
+    pub fn new(quoting: Quoting) -> Self {
+        Self {
+            quoting,
+            values: vec![],
+        }
+    }
+
+    /// Set value of parameter, replaces previous value with same name.
+    pub fn value<N: Into<String>>(mut self, name: N, value: SqlValue) -> Self {
+        let name = name.into();
+        self.values.retain(|(n, _)| *n != name);
+        self.values.push((name, value));
+        self
+    }
+
+    /// Set parameter to quoted identifier, see [SqlValue::Ident].
+    pub fn ident<N: Into<String>, V: Into<String>>(self, name: N, ident: V) -> Self {
+        self.value(name, SqlValue::Ident(ident.into()))
+    }
+
+    /// Set parameter to string literal, see [SqlValue::Text].
+    pub fn text<N: Into<String>, V: Into<String>>(self, name: N, text: V) -> Self {
+        self.value(name, SqlValue::Text(text.into()))
+    }
+
+    /// Substitute values in query. Every value should be used by query.
+    pub fn apply(&self, query: &str) -> Result<String, InterpolationError> {
+        let quoted = self
+            .values
+            .iter()
+            .map(|(name, value)| {
+                quote(value, self.quoting)
+                    .map(|value| (name.as_str(), value))
+                    .ok_or_else(|| InterpolationError::InvalidValue(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut used = vec![false; quoted.len()];
+
+        let mut text = String::with_capacity(query.len());
+        let mut quote_char: Option<char> = None;
+        let mut prev: Option<char> = None;
+        let mut pos = 0;
+        while let Some(c) = query[pos..].chars().next() {
+            let start = pos;
+            pos += c.len_utf8();
+            match quote_char {
+                // Backslash escapes next character in MySQL strings, not in identifiers.
+                Some(q) if c == '\\' && q != '`' && self.quoting == Quoting::MySql => {
+                    pos += query[pos..].chars().next().map_or(0, char::len_utf8);
+                }
+                // `]]` is escaped `]` in SQL Server identifier.
+                Some(']') if c == ']' && query[pos..].starts_with(']') => pos += 1,
+                Some(q) if c == q => quote_char = None,
+                Some(_) => {}
+                None if matches!(c, '\'' | '"' | '`') => quote_char = Some(c),
+                None if c == '[' && self.quoting == Quoting::SqlServer => quote_char = Some(']'),
+                None if c == '-' && query[pos..].starts_with('-') => {
+                    pos += query[pos..].find(['\n', '\r']).unwrap_or(query.len() - pos);
+                }
+                None if c == '/' && query[pos..].starts_with('*') => {
+                    pos += query[pos + 1..]
+                        .find("*/")
+                        .map_or(query.len() - pos, |idx| idx + 3);
+                }
+                None if c == '$'
+                    && self.quoting == Quoting::Ansi
+                    && !prev.is_some_and(|p| p.is_alphanumeric() || p == '_') =>
+                {
+                    pos = dollar_quote_end(query, start);
+                }
+                None if c == ':' && prev != Some(':') => {
+                    let name_len = query[pos..]
+                        .chars()
+                        .take_while(|&c| c.is_alphanumeric() || c == '_')
+                        .map(char::len_utf8)
+                        .sum::<usize>();
+                    let name = &query[pos..pos + name_len];
+                    if let Some(idx) = quoted.iter().position(|(n, _)| *n == name) {
+                        text.push_str(&quoted[idx].1);
+                        used[idx] = true;
+                        pos += name_len;
+                        prev = name.chars().last();
+                        continue;
+                    }
+                }
+                None => {}
+            }
+            text.push_str(&query[start..pos]);
+            prev = query[start..pos].chars().last();
+        }
+
+        match used.iter().position(|used| !used) {
+            Some(pos) => Err(InterpolationError::UnusedValue(quoted[pos].0.to_owned())),
+            None => Ok(text),
+        }
+    }
+}
+
+// SQL text of value, `None` if value can not be quoted.
+fn quote(value: &SqlValue, quoting: Quoting) -> Option<String> {
+    let mut text = String::new();
+    match value {
+        SqlValue::Ident(ident) if ident.is_empty() || ident.contains('\0') => return None,
+        SqlValue::Ident(ident) => {
+            let (open, close) = match quoting {
+                Quoting::Ansi => ('"', '"'),
+                Quoting::MySql => ('`', '`'),
+                Quoting::SqlServer => ('[', ']'),
+            };
+            text.push(open);
+            for c in ident.chars() {
+                if c == close {
+                    text.push(c);
+                }
+                text.push(c);
+            }
+            text.push(close);
+        }
+        SqlValue::Text(value) if value.contains('\0') => return None,
+        SqlValue::Text(value) => {
+            if quoting == Quoting::SqlServer {
+                text.push('N');
+            }
+            text.push('\'');
+            for c in value.chars() {
+                if c == '\'' || (c == '\\' && quoting == Quoting::MySql) {
+                    text.push(c);
+                }
+                text.push(c);
+            }
+            text.push('\'');
+        }
+        // Negative literal after `-` would start comment (`0--1`).
+        SqlValue::Int(value) if *value < 0 => write!(text, "({})", value).unwrap(),
+        SqlValue::Int(value) => write!(text, "{}", value).unwrap(),
+        SqlValue::Float(value) if !value.is_finite() => return None,
+        // Debug keeps `.0` of integral values, so literal stays float.
+        SqlValue::Float(value) if value.is_sign_negative() => {
+            write!(text, "({:?})", value).unwrap()
+        }
+        SqlValue::Float(value) => write!(text, "{:?}", value).unwrap(),
+        SqlValue::Bool(value) => text.push_str(if *value { "TRUE" } else { "FALSE" }),
+        SqlValue::Null => text.push_str("NULL"),
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_per_dialect() {
+        let interpolation = |quoting| {
+            Interpolation::new(quoting)
+                .ident("t", "a]b`c")
+                .text("v", r"it's \n")
+                .value("n", SqlValue::Float(1.0))
+                .value("x", SqlValue::Null)
+        };
+        let query = "SET x = :x; SELECT ':t', :n::float FROM :t WHERE v = :v AND b = :b";
+        assert_eq!(
+            interpolation(Quoting::Ansi).apply(query).unwrap(),
+            r#"SET x = NULL; SELECT ':t', 1.0::float FROM "a]b`c" WHERE v = 'it''s \n' AND b = :b"#
+        );
+        assert_eq!(
+            interpolation(Quoting::MySql).apply(query).unwrap(),
+            r"SET x = NULL; SELECT ':t', 1.0::float FROM `a]b``c` WHERE v = 'it''s \\n' AND b = :b"
+        );
+        assert_eq!(
+            interpolation(Quoting::SqlServer).apply(query).unwrap(),
+            r"SET x = NULL; SELECT ':t', 1.0::float FROM [a]]b`c] WHERE v = N'it''s \n' AND b = :b"
+        );
+
+        let interpolation = Interpolation::new(Quoting::Ansi).value("n", SqlValue::Int(-1));
+        assert_eq!(
+            interpolation
+                .apply("SELECT * FROM t WHERE balance > 0-:n AND owner = :owner")
+                .unwrap(),
+            "SELECT * FROM t WHERE balance > 0-(-1) AND owner = :owner"
+        );
+        assert_eq!(
+            Interpolation::new(Quoting::Ansi)
+                .value("f", SqlValue::Float(-0.5))
+                .apply("1-:f")
+                .unwrap(),
+            "1-(-0.5)"
+        );
+        assert_eq!(
+            interpolation.apply("LIMIT :m"),
+            Err(InterpolationError::UnusedValue("n".to_owned()))
+        );
+        let interpolation = Interpolation::new(Quoting::MySql).text("v", "x");
+        assert_eq!(
+            interpolation.apply(r"SELECT 'it\'s :v', :v").unwrap(),
+            r"SELECT 'it\'s :v', 'x'"
+        );
+        let interpolation = Interpolation::new(Quoting::Ansi).text("v", "x");
+        assert_eq!(
+            interpolation
+                .apply("SELECT $$it's :v$$, $a$ :v $a$, $1, :v")
+                .unwrap(),
+            "SELECT $$it's :v$$, $a$ :v $a$, $1, 'x'"
+        );
+        assert_eq!(
+            interpolation.apply(r"SELECT 'a\', :v").unwrap(),
+            r"SELECT 'a\', 'x'"
+        );
+
+        let interpolation = Interpolation::new(Quoting::Ansi).text("v", "x");
+        assert_eq!(
+            interpolation
+                .apply("SELECT :v -- :v\n/* :v */ FROM t /* :v")
+                .unwrap(),
+            "SELECT 'x' -- :v\n/* :v */ FROM t /* :v"
+        );
+        assert_eq!(
+            interpolation.apply("SELECT 1 -- :v"),
+            Err(InterpolationError::UnusedValue("v".to_owned()))
+        );
+        let interpolation = Interpolation::new(Quoting::SqlServer).text("x", "y");
+        assert_eq!(
+            interpolation.apply("SELECT [:x], [a]]:x], :x").unwrap(),
+            "SELECT [:x], [a]]:x], N'y'"
+        );
+
+        let interpolation = Interpolation::new(Quoting::Ansi).ident("t", "");
+        assert_eq!(
+            interpolation.apply(":t"),
+            Err(InterpolationError::InvalidValue("t".to_owned()))
+        );
+    }
+}
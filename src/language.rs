@@ -0,0 +1,204 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Comment and string syntax of query language, see [ParseOptions::language](crate::ParseOptions::language).
+///
+/// Tag lines and annotations start with line comment marker (`// name: x` for Cypher), block
+/// comments are removed, comment markers inside quotes are part of query.
+///
+/// ```
+/// use rsyesql::{Language, ParseOptions};
+///
+/// let text = "# name: names\nSELECT ?name WHERE { ?x foaf:name ?name } # all\n# name: hash\nSELECT \"#\";";
+/// let queries = ParseOptions::new().language(Language::sparql()).parse(text).unwrap();
+/// assert_eq!(queries["names"], "SELECT ?name WHERE { ?x foaf:name ?name }");
+/// assert_eq!(queries["hash"], "SELECT \"#\";");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Language {
+    line_comments: Vec<String>,
+    block_comment: Option<(String, String)>,
+    quotes: Vec<char>,
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self::sql()
+    }
+}
+
+impl Language {
+    /// Language without comments and quotes.
+    pub fn new() -> Self {
+        Self {
+            line_comments: vec![],
+            block_comment: None,
+            quotes: vec![],
+        }
+    }
+
+    /// SQL (default): `--` and `/* ... */` comments, quotes are not tracked.
+    pub fn sql() -> Self {
+        Self::new().line_comment("--").block_comment("/*", "*/")
+    }
+
+    /// Cypher: `//` and `/* ... */` comments, `'`, `"` and `` ` `` quotes.
+    pub fn cypher() -> Self {
+        Self::new()
+            .line_comment("//")
+            .block_comment("/*", "*/")
+            .quote('\'')
+            .quote('"')
+            .quote('`')
+    }
+
+    /// SPARQL: `#` comments, `'` and `"` quotes.
+    ///
+    /// IRIs in `<...>` are not quoted strings, `#` in IRI is comment.
+    pub fn sparql() -> Self {
+        Self::new().line_comment("#").quote('\'').quote('"')
+    }
+
+    /// CQL (Cassandra): `--`, `//` and `/* ... */` comments, `'` and `"` quotes.
+    pub fn cql() -> Self {
+        Self::new()
+            .line_comment("--")
+            .line_comment("//")
+            .block_comment("/*", "*/")
+            .quote('\'')
+            .quote('"')
+    }
+
+    /// Add line comment marker, tag lines and annotations are recognized after any marker.
+    pub fn line_comment<M: Into<String>>(mut self, marker: M) -> Self {
+        self.line_comments.push(marker.into());
+        self
+    }
+
+    /// Set block comment delimiters, comments can not be nested.
+    pub fn block_comment<O: Into<String>, C: Into<String>>(mut self, open: O, close: C) -> Self {
+        self.block_comment = Some((open.into(), close.into()));
+        self
+    }
+
+    /// Add string quote, comment markers between quotes are not comments. Doubled quote is
+    /// escaped quote.
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quotes.push(quote);
+        self
+    }
+
+    // Text after line comment marker at start of line.
+    pub(crate) fn comment<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let line = line.trim_start();
+        self.line_comments
+            .iter()
+            .filter(|marker| !marker.is_empty())
+            .find_map(|marker| line.strip_prefix(marker.as_str()))
+    }
+
+    // Byte position of line comment outside of quotes.
+    pub(crate) fn find_comment(&self, line: &str) -> Option<usize> {
+        let mut quote = None;
+        for (idx, c) in line.char_indices() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if self.quotes.contains(&c) => quote = Some(c),
+                None if self.comment_at(&line[idx..]) => return Some(idx),
+                None => {}
+            }
+        }
+        None
+    }
+
+    // Block comments replaced by spaces, line endings kept to keep positions of lines.
+    pub(crate) fn remove_block_comments<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let (open, close) = match &self.block_comment {
+            Some((open, close)) if !open.is_empty() && !close.is_empty() => (open, close),
+            _ => return Cow::Borrowed(text),
+        };
+        // Without quotes comments are found as in previous versions, regardless of line comments.
+        let tracked = !self.quotes.is_empty();
+        let mut result = Cow::Borrowed(text);
+        let mut quote = None;
+        let mut pos = 0;
+        while let Some(c) = text[pos..].chars().next() {
+            let rest = &text[pos..];
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if tracked && self.quotes.contains(&c) => quote = Some(c),
+                None if tracked && self.comment_at(rest) => {
+                    pos += rest.find(['\n', '\r']).unwrap_or(rest.len());
+                    continue;
+                }
+                None if rest.starts_with(open.as_str()) => {
+                    let end = match rest[open.len()..].find(close.as_str()) {
+                        Some(idx) => pos + open.len() + idx + close.len(),
+                        None => break,
+                    };
+                    // Every character replaced by same number of bytes, so positions are not
+                    // changed.
+                    let mut rep = String::with_capacity(end - pos);
+                    for c in text[pos..end].chars() {
+                        match c {
+                            '\r' | '\n' => rep.push(c),
+                            _ => (0..c.len_utf8()).for_each(|_| rep.push(' ')),
+                        }
+                    }
+                    result.to_mut().replace_range(pos..end, &rep);
+                    pos = end;
+                    continue;
+                }
+                None => {}
+            }
+            pos += c.len_utf8();
+        }
+        result
+    }
+
+    fn comment_at(&self, text: &str) -> bool {
+        self.line_comments
+            .iter()
+            .any(|marker| !marker.is_empty() && text.starts_with(marker.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_outside_quotes() {
+        let cypher = Language::cypher();
+        assert_eq!(cypher.find_comment("RETURN '//' // x"), Some(12));
+        assert_eq!(cypher.find_comment("RETURN 'it''s' + `a//b`"), None);
+        assert_eq!(cypher.comment("  // name: x"), Some(" name: x"));
+        assert_eq!(
+            cypher.remove_block_comments("RETURN '/*', /* a\n */ 1 // /*\n*/"),
+            "RETURN '/*',     \n    1 // /*\n*/"
+        );
+        assert_eq!(Language::sql().remove_block_comments("'/*' */"), "'      ");
+        assert_eq!(Language::sql().find_comment("SELECT '--' -- x"), Some(8));
+        assert_eq!(Language::new().find_comment("SELECT 1 -- x"), None);
+    }
+
+    #[test]
+    fn parse_cypher() {
+        let text = "// name: friends\n// returns: name:text\nMATCH (u)-[:FRIEND]->(f) /* all */\nRETURN f.name AS name // x\n-- name: y";
+        let queries = crate::ParseOptions::new()
+            .language(Language::cypher())
+            .parse_detailed(text)
+            .unwrap();
+        assert_eq!(queries.len(), 1);
+        let query = &queries["friends"];
+        assert_eq!(
+            query.text,
+            "MATCH (u)-[:FRIEND]->(f) RETURN f.name AS name -- name: y"
+        );
+        assert_eq!(query.returns.as_ref().unwrap()[0].name, "name");
+    }
+}
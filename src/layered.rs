@@ -0,0 +1,101 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Map, ParseError, ParseOptions, Query};
+
+/// Base query replaced by override, see [Layered].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overridden {
+    /// Query tag.
+    pub tag: String,
+    /// Line of tag in base text.
+    pub base_line: usize,
+    /// Line of tag in overrides text.
+    pub line: usize,
+}
+
+/// Base queries with overrides applied, see [parse_layered].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layered {
+    /// Queries from base with replaced queries in place, new queries from overrides at the end.
+    pub queries: Map<Query>,
+    /// Replaced queries in order of overrides.
+    pub overridden: Vec<Overridden>,
+}
+
+impl Layered {
+    /// Apply already parsed overrides to base queries.
+    ///
+    /// Useful with [parse_named](crate::parse_named) to know which file failed to parse.
+    pub fn new(base: Map<Query>, overrides: Map<Query>) -> Self {
+        let mut queries = base;
+        let mut overridden = Vec::new();
+        for (tag, query) in overrides {
+            if let Some(base) = queries.get(&tag) {
+                overridden.push(Overridden {
+                    tag: tag.clone(),
+                    base_line: base.line,
+                    line: query.line,
+                });
+            }
+            queries.insert(tag, query);
+        }
+        Self {
+            queries,
+            overridden,
+        }
+    }
+}
+
+/// Parse base SQL queries and overrides, queries in overrides replace same tags from base.
+///
+/// Replaced queries keep position of base query, new tags are appended. Replaced tags are listed
+/// in [Layered::overridden].
+///
+/// ```
+/// let base = "-- name: select\nSELECT * FROM users;\n-- name: delete\nDELETE FROM users;";
+/// let overrides = "-- name: select\nSELECT * FROM customer_users;\n-- name: count\nSELECT count(*) FROM users;";
+/// let layered = rsyesql::parse_layered(base, overrides).unwrap();
+/// assert_eq!(
+///     layered.queries.keys().collect::<Vec<_>>(),
+///     vec!["select", "delete", "count"]
+/// );
+/// assert_eq!(layered.queries["select"].text, "SELECT * FROM customer_users;");
+/// assert_eq!(layered.overridden[0].tag, "select");
+/// ```
+pub fn parse_layered<B: AsRef<str>, O: AsRef<str>>(
+    base: B,
+    overrides: O,
+) -> Result<Layered, ParseError> {
+    ParseOptions::new().parse_layered(base, overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::borrow::ToOwned;
+    use alloc::vec;
+
+    #[test]
+    fn overrides_in_place() {
+        let base = "--name: a\n1;\n--name: b\n2;\n--name: c\n3;";
+        let overrides = "--name: d\n4;\n\n--name: b\n22;";
+        let layered = parse_layered(base, overrides).unwrap();
+        assert_eq!(
+            layered
+                .queries
+                .iter()
+                .map(|(tag, query)| (tag.as_str(), query.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("a", "1;"), ("b", "22;"), ("c", "3;"), ("d", "4;")]
+        );
+        assert_eq!(
+            layered.overridden,
+            vec![Overridden {
+                tag: "b".to_owned(),
+                base_line: 3,
+                line: 4
+            }]
+        );
+    }
+}
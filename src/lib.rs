@@ -1,33 +1,460 @@
 //! Parse SQL queries as text to [IndexMap] (`tag` => `query`).
 //!
 //! Inspired by [github.com/krisajenkins/yesql](https://github.com/krisajenkins/yesql). This is Rust port with additional features.
+//!
+//! Without default `std` feature crate is `no_std` (requires `alloc`): only parser ([parse],
+//! [parse_detailed], [ParseOptions] without regex options) and [split_statements] are available.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+#[cfg(feature = "validate")]
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate quick_error;
 
-use std::borrow::Cow;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::path::Path;
 
 pub use indexmap;
+use indexmap::map::Entry;
 use indexmap::IndexMap;
-use regex::{Regex, RegexBuilder};
 
-quick_error! {
-    /// The error type for parse SQL queries as text
-    #[derive(Debug, PartialEq)]
-    pub enum ParseError {
-        /// Tag with same name already exists.
-        TagOverwritten { line: usize, tag: String } {
-            display(r#"Tag "{}" overwritten at line: {}"#, tag, line)
+mod annotations;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "deadpool")]
+pub mod deadpool;
+#[cfg(feature = "miette")]
+mod diagnostic;
+#[cfg(feature = "diesel")]
+pub mod diesel;
+#[cfg(feature = "encoding")]
+mod encoding;
+#[cfg(feature = "std")]
+mod executor;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod files;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "format")]
+mod format;
+mod incremental;
+#[cfg(feature = "std")]
+mod interpolate;
+mod language;
+mod layered;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "checksums")]
+mod lockfile;
+#[cfg(feature = "std")]
+mod migrations;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+mod options;
+#[cfg(feature = "std")]
+mod params;
+mod parser;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod queries;
+#[cfg(feature = "refinery")]
+pub mod refinery;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+mod rows;
+#[cfg(feature = "sea-orm")]
+pub mod sea_orm;
+mod split;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod tokens;
+mod transaction;
+#[cfg(feature = "validate")]
+mod validate;
+mod vars;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use annotations::{Column, Comparison, Expect, Fixture, Retry};
+#[cfg(feature = "miette")]
+pub use diagnostic::ParseDiagnostic;
+#[cfg(feature = "std")]
+pub use executor::QueryExecutor;
+#[cfg(feature = "std")]
+pub use files::{parse_many, parse_named, FileError};
+#[cfg(feature = "std")]
+pub use fingerprint::{duplicates, fingerprint};
+#[cfg(feature = "format")]
+pub use format::{format_queries, format_query, FormatOptions};
+pub use incremental::{reparse, TextEdit};
+#[cfg(feature = "std")]
+pub use interpolate::{Interpolation, InterpolationError, Quoting, SqlValue};
+pub use language::Language;
+pub use layered::{parse_layered, Layered, Overridden};
+#[cfg(feature = "checksums")]
+pub use lockfile::{Drift, Manifest, ManifestEntry, ManifestError};
+#[cfg(feature = "std")]
+pub use migrations::{Migration, Migrations};
+#[cfg(feature = "mmap")]
+pub use mmap::{parse_mmap, MmapError};
+pub use options::{
+    Limit, LineEnding, MetaCommands, Order, ParseOptions, Script, TagNames, Whitespace,
+};
+#[cfg(feature = "std")]
+pub use params::{
+    check_placeholders, check_unique_placeholders, placeholders, positional, Placeholder,
+    PlaceholderError, Placeholders, PositionalQuery,
+};
+pub use parser::{parse_into, Parser};
+#[cfg(feature = "std")]
+pub use queries::{Queries, QueriesError, TagLookup, TagPattern};
+#[cfg(feature = "std")]
+pub use registry::global;
+#[cfg(feature = "std")]
+pub use rows::{check_row_shape, RowError, RowShapeError};
+pub use split::{is_explainable, split_queries, split_statements};
+pub use tokens::{tokens, Token, TokenKind, Tokens};
+pub use transaction::{transactions, Transaction};
+#[cfg(feature = "validate")]
+pub use validate::{validate, Dialect, ValidationError};
+
+/// Map of parsed queries (`tag` => `V`).
+#[cfg(feature = "std")]
+type Map<V> = IndexMap<String, V>;
+/// Map of parsed queries (`tag` => `V`), without `std` there is no random state for hasher.
+#[cfg(not(feature = "std"))]
+type Map<V> = IndexMap<String, V, core::hash::BuildHasherDefault<FnvHasher>>;
+
+/// FNV-1a hasher of returned maps without `std`.
+#[cfg(not(feature = "std"))]
+pub struct FnvHasher(u64);
+
+#[cfg(not(feature = "std"))]
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::hash::Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
         }
-        /// Tag for query is not defined.
-        QueryWithoutTag{line: usize, query: String} {
-            display(r#"Query without tag (line: {}): "{}""#, line, query)
+    }
+}
+
+/// The error type for parse SQL queries as text
+///
+/// Every variant has position in source text: `line` (1-based), `column` (1-based, in
+/// characters) and byte `offset` (including BOM), see [line](Self::line),
+/// [column](Self::column) and [offset](Self::offset).
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// Tag with same name already exists.
+    TagOverwritten {
+        line: usize,
+        column: usize,
+        offset: usize,
+        tag: String,
+    },
+    /// Tag for query is not defined.
+    QueryWithoutTag {
+        line: usize,
+        column: usize,
+        offset: usize,
+        query: String,
+    },
+    /// Tag name does not satisfy [TagNames] rule.
+    InvalidTag {
+        line: usize,
+        column: usize,
+        offset: usize,
+        tag: String,
+    },
+    /// Different tags are same after [transform_tags](ParseOptions::transform_tags).
+    TagCollision {
+        line: usize,
+        column: usize,
+        offset: usize,
+        tag: String,
+        other: String,
+    },
+    /// Text contains control characters, most likely it's not a text file.
+    BinaryInput {
+        line: usize,
+        column: usize,
+        offset: usize,
+    },
+    /// Tag without query (strict mode).
+    EmptyQuery {
+        line: usize,
+        column: usize,
+        offset: usize,
+        tag: String,
+    },
+    /// Query contains more than one statement (strict mode).
+    MultipleStatements {
+        line: usize,
+        column: usize,
+        offset: usize,
+        tag: String,
+    },
+    /// Query is not terminated by semicolon (strict mode).
+    MissingSemicolon {
+        line: usize,
+        column: usize,
+        offset: usize,
+        tag: String,
+    },
+    /// Input exceeds one of limits set in [ParseOptions].
+    LimitExceeded {
+        line: usize,
+        column: usize,
+        offset: usize,
+        limit: Limit,
+        max: usize,
+    },
+    /// Annotation under tag (`-- returns:` in [strict](ParseOptions::strict) mode) can not be
+    /// parsed.
+    InvalidAnnotation {
+        line: usize,
+        column: usize,
+        offset: usize,
+        annotation: String,
+    },
+    /// `-- endif` without `-- if:` or `-- if:` without `-- endif`, see [ParseOptions::flag].
+    UnbalancedCondition {
+        line: usize,
+        column: usize,
+        offset: usize,
+    },
+    /// psql meta-command (`\set`, `\i`, ...) in query, see [ParseOptions::meta_commands].
+    MetaCommand {
+        line: usize,
+        column: usize,
+        offset: usize,
+        command: String,
+    },
+    /// Variable in query is not set, see [ParseOptions::substitute_vars].
+    UnsetVariable {
+        line: usize,
+        column: usize,
+        offset: usize,
+        name: String,
+    },
+}
+
+// Written by hand instead of `quick_error!`, which requires `std`.
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::TagOverwritten { line, tag, .. } => {
+                write!(f, r#"Tag "{}" overwritten at line: {}"#, tag, line)
+            }
+            ParseError::QueryWithoutTag { line, query, .. } => {
+                write!(f, r#"Query without tag (line: {}): "{}""#, line, query)
+            }
+            ParseError::InvalidTag { line, tag, .. } => {
+                write!(f, r#"Invalid tag "{}" at line: {}"#, tag, line)
+            }
+            ParseError::TagCollision {
+                line, tag, other, ..
+            } => write!(
+                f,
+                r#"Tag "{}" at line {} collides with "{}" after transformation"#,
+                tag, line, other
+            ),
+            ParseError::BinaryInput { line, .. } => {
+                write!(f, "Binary input, control character at line: {}", line)
+            }
+            ParseError::EmptyQuery { line, tag, .. } => {
+                write!(f, r#"Tag "{}" without query at line: {}"#, tag, line)
+            }
+            ParseError::MultipleStatements { line, tag, .. } => {
+                write!(
+                    f,
+                    r#"Query "{}" with multiple statements at line: {}"#,
+                    tag, line
+                )
+            }
+            ParseError::MissingSemicolon { line, tag, .. } => write!(
+                f,
+                r#"Query "{}" without terminating semicolon at line: {}"#,
+                tag, line
+            ),
+            ParseError::LimitExceeded {
+                line, limit, max, ..
+            } => write!(f, "{} limit ({}) exceeded at line: {}", limit, max, line),
+            ParseError::InvalidAnnotation {
+                line, annotation, ..
+            } => write!(
+                f,
+                r#"Invalid annotation "{}" at line: {}"#,
+                annotation, line
+            ),
+            ParseError::UnbalancedCondition { line, .. } => {
+                write!(f, "Unbalanced conditional block at line: {}", line)
+            }
+            ParseError::UnsetVariable { line, name, .. } => {
+                write!(f, r#"Variable "{}" is not set at line: {}"#, name, line)
+            }
+            ParseError::MetaCommand { line, command, .. } => {
+                write!(f, r#"Meta-command "{}" at line: {}"#, command, line)
+            }
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+// Position fields of every variant, by reference or mutable reference.
+macro_rules! position_fields {
+    ($err:expr) => {
+        match $err {
+            ParseError::TagOverwritten {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::QueryWithoutTag {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::InvalidTag {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::TagCollision {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::BinaryInput {
+                line,
+                column,
+                offset,
+            }
+            | ParseError::EmptyQuery {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::MultipleStatements {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::MissingSemicolon {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::LimitExceeded {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::InvalidAnnotation {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::UnbalancedCondition {
+                line,
+                column,
+                offset,
+            }
+            | ParseError::UnsetVariable {
+                line,
+                column,
+                offset,
+                ..
+            }
+            | ParseError::MetaCommand {
+                line,
+                column,
+                offset,
+                ..
+            } => (line, column, offset),
+        }
+    };
+}
+
+impl ParseError {
+    /// Line of error (1-based).
+    pub fn line(&self) -> usize {
+        self.position().0
+    }
+
+    /// Column of error (1-based, in characters).
+    pub fn column(&self) -> usize {
+        self.position().1
+    }
+
+    /// Byte offset of error in source text.
+    pub fn offset(&self) -> usize {
+        self.position().2
+    }
+
+    fn position(&self) -> (usize, usize, usize) {
+        let (line, column, offset) = position_fields!(self);
+        (*line, *column, *offset)
+    }
+
+    // Move error from parsed part of text to position in whole text, part starts at line start.
+    fn shift(mut self, lines: usize, offset: usize) -> Self {
+        let (line, _, pos) = position_fields!(&mut self);
+        *line += lines;
+        *pos += offset;
+        self
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum LineType {
     Empty,
@@ -59,92 +486,889 @@ enum LineType {
 /// println!("{}", queries.get("select").unwrap()); // SELECT * FROM users;
 /// println!("{}", queries.get("delete").unwrap()); // DELETE FROM users WHERE id = $1;
 /// ```
-pub fn parse<S: AsRef<str>>(text: S) -> Result<IndexMap<String, String>, ParseError> {
-    let mut queries = IndexMap::new();
+pub fn parse<S: AsRef<str>>(text: S) -> Result<Map<String>, ParseError> {
+    ParseOptions::new().parse(text)
+}
+
+/// Parse SQL queries as text to [IndexMap] with custom hasher.
+///
+/// Same as [parse], for faster lookups with large number of tags.
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::BuildHasherDefault;
+///
+/// type Hasher = BuildHasherDefault<DefaultHasher>;
+/// let queries = rsyesql::parse_with_hasher::<Hasher, _>("-- name: x\nSELECT 1;").unwrap();
+/// assert_eq!(queries["x"], "SELECT 1;");
+/// ```
+pub fn parse_with_hasher<H, S>(text: S) -> Result<IndexMap<String, String, H>, ParseError>
+where
+    H: core::hash::BuildHasher + Default,
+    S: AsRef<str>,
+{
+    ParseOptions::new().parse_with_hasher(text)
+}
+
+/// Parse SQL queries as text to [IndexMap] with [Arc] tags and queries.
+///
+/// Same as [parse], but cloned map or single query shares text instead of copying it.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// let queries = rsyesql::parse_shared("-- name: x\nSELECT 1;").unwrap();
+/// let query = Arc::clone(&queries["x"]);
+/// std::thread::spawn(move || assert_eq!(&*query, "SELECT 1;"))
+///     .join()
+///     .unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_shared<S: AsRef<str>>(text: S) -> Result<IndexMap<Arc<str>, Arc<str>>, ParseError> {
+    ParseOptions::new().parse_shared(text)
+}
+
+/// Parse static SQL queries (`include_str!`) to [IndexMap] with [Cow] tags and queries.
+///
+/// Same as [parse], but tags and queries which are same as in source text (single line query,
+/// or with [preserve_newlines](ParseOptions::preserve_newlines)) are borrowed instead of copied.
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// let queries = rsyesql::parse_static("-- name: x\nSELECT 1;\n-- name: y\nSELECT\n  2;").unwrap();
+/// assert!(matches!(queries["x"], Cow::Borrowed("SELECT 1;")));
+/// assert!(matches!(queries["y"], Cow::Owned(_)));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_static(
+    text: &'static str,
+) -> Result<IndexMap<Cow<'static, str>, Cow<'static, str>>, ParseError> {
+    ParseOptions::new().parse_static(text)
+}
+
+/// Query with information about position in source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Query text, same as returned by [parse].
+    pub text: String,
+    /// Line of tag (1-based).
+    pub line: usize,
+    /// Source file, set by [parse_named].
+    #[cfg(feature = "std")]
+    pub file: Option<Arc<Path>>,
+    /// Result columns from `-- returns: name:type, ...` annotation under tag line.
+    pub returns: Option<Vec<Column>>,
+    /// Source text from line after tag line to next tag line as is, with comments, empty lines
+    /// and line endings.
+    pub raw: String,
+    /// Batches of query separated by script separator lines (like `GO`), see
+    /// [ParseOptions::script]. Empty for [Script::Standard].
+    pub batches: Vec<String>,
+    /// `key=value` attributes after tag on tag line, like `-- name: select_users timeout=5s`, see
+    /// [ParseOptions::attributes].
+    pub attributes: Vec<(String, String)>,
+    /// psql meta-commands of query with [MetaCommands::Collect].
+    pub meta_commands: Vec<String>,
+    /// Index of `-- tx: begin <name>` ... `-- tx: end` block with tag in text and name of block,
+    /// see [transactions] and [ParseOptions::transactions].
+    pub transaction: Option<(usize, String)>,
+    /// Tests from `-- test:` lines under tag.
+    pub fixtures: Vec<Fixture>,
+    // Byte offset of tag in text without BOM.
+    tag_pos: usize,
+    // Byte offsets in `text` where source lines start, with line numbers.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Query {
+    /// Value of attribute from tag line, see [attributes](Self::attributes).
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Duration of `timeout` attribute (`timeout=100ms`, units: `ms`, `s`, `m`, `h`).
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let queries = rsyesql::ParseOptions::new()
+    ///     .attributes(true)
+    ///     .parse_detailed("-- name: report timeout=5s\nSELECT 1;")
+    ///     .unwrap();
+    /// assert_eq!(queries["report"].timeout(), Some(Duration::from_secs(5)));
+    /// ```
+    pub fn timeout(&self) -> Option<Duration> {
+        self.attribute("timeout")
+            .and_then(annotations::parse_duration)
+    }
+
+    /// Retry policy of `retries` and `backoff` attributes, `backoff` is zero if not set.
+    pub fn retry(&self) -> Option<Retry> {
+        let retries = self.attribute("retries")?.parse().ok()?;
+        let backoff = self
+            .attribute("backoff")
+            .and_then(annotations::parse_duration)
+            .unwrap_or_default();
+        Some(Retry { retries, backoff })
+    }
+
+    // Empty query, buffers of `spare` query are reused.
+    fn recycled(spare: Option<Query>) -> Self {
+        match spare {
+            Some(mut query) => {
+                query.text.clear();
+                query.raw.clear();
+                query.batches.clear();
+                query.lines.clear();
+                #[cfg(feature = "std")]
+                {
+                    query.file = None;
+                }
+                query
+            }
+            None => Query {
+                text: String::new(),
+                line: 0,
+                #[cfg(feature = "std")]
+                file: None,
+                returns: None,
+                raw: String::new(),
+                batches: vec![],
+                attributes: vec![],
+                meta_commands: vec![],
+                transaction: None,
+                fixtures: vec![],
+                tag_pos: 0,
+                lines: vec![],
+            },
+        }
+    }
+
+    /// Source line (1-based) of byte offset in query text.
+    pub fn line_at(&self, offset: usize) -> usize {
+        let idx = match self
+            .lines
+            .binary_search_by_key(&offset, |&(start, _)| start)
+        {
+            Ok(idx) => idx,
+            Err(idx) => idx.saturating_sub(1),
+        };
+        self.lines.get(idx).map_or(self.line, |&(_, line)| line)
+    }
+}
+
+/// Parse SQL queries as text to [IndexMap] with [Query] values.
+///
+/// Same as [parse], but every query keep information about source lines.
+pub fn parse_detailed<S: AsRef<str>>(text: S) -> Result<Map<Query>, ParseError> {
+    ParseOptions::new().parse_detailed(text)
+}
+
+/// Parse SQL queries as bytes to [IndexMap].
+///
+/// Encoding detected by BOM (UTF-8, UTF-16LE, UTF-16BE). Without BOM bytes decoded as UTF-8, or
+/// as windows-1252 (superset of latin-1) if bytes are not valid UTF-8.
+///
+/// ```ignore
+/// let queries = rsyesql::parse_bytes(std::fs::read("./queries.sql")?)?;
+/// ```
+#[cfg(feature = "encoding")]
+pub fn parse_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Map<String>, ParseError> {
+    ParseOptions::new().parse_bytes(bytes)
+}
+
+fn parse_text(text: &str, options: &ParseOptions) -> Result<Map<Query>, ParseError> {
+    let mut queries = Map::default();
+    parse_text_into(text, options, &mut queries, &mut vec![])?;
+    Ok(queries)
+}
+
+// Parse text to `queries`, buffers of previous queries are moved to `spare` and reused. On error
+// `queries` are empty.
+pub(crate) fn parse_text_into(
+    text: &str,
+    options: &ParseOptions,
+    queries: &mut Map<Query>,
+    spare: &mut Vec<Query>,
+) -> Result<(), ParseError> {
+    spare.extend(queries.drain(..).map(|(_, query)| query));
+    let result = parse_queries(text, options, queries, spare);
+    if result.is_err() {
+        spare.extend(queries.drain(..).map(|(_, query)| query));
+    }
+    result
+}
+
+fn parse_queries(
+    text: &str,
+    options: &ParseOptions,
+    queries: &mut Map<Query>,
+    spare: &mut Vec<Query>,
+) -> Result<(), ParseError> {
+    // UTF-8 BOM is not part of text.
+    let bom = if text.starts_with('\u{feff}') { 3 } else { 0 };
+    let text = &text[bom..];
+    let positions = Positions { text, bom };
+
+    if let Some(max) = options.max_input_size.filter(|&max| bom + text.len() > max) {
+        return Err(ParseError::LimitExceeded {
+            line: 1,
+            column: 1,
+            offset: 0,
+            limit: Limit::InputSize,
+            max,
+        });
+    }
+
+    if let Some(pos) = text.find(|c| c < ' ' && !matches!(c, '\t' | '\n' | '\r' | '\x0c')) {
+        let (column, offset) = positions.at(pos);
+        return Err(ParseError::BinaryInput {
+            line: count_line_breaks(&text[..pos]) + 1,
+            column,
+            offset,
+        });
+    }
 
     let mut last_type: Option<LineType> = None;
-    let mut last_tag: Option<&str> = None;
+    let mut last_tag: Option<(Cow<'_, str>, usize, usize)> = None;
+    let mut last_ending = "\n";
+    let mut last_comment = false;
+    // Script separator line after last query line.
+    let mut separated = false;
+    // Annotation of last tag, attached to query on first query line.
+    let mut returns: Option<Vec<Column>> = None;
+    // Attributes of last tag line, attached to query on first query line.
+    let mut attributes: Vec<(String, String)> = vec![];
+    // Collected meta-commands of last tag, attached to query on first query line.
+    let mut meta_commands: Vec<String> = vec![];
+    // Fixtures of last tag before first query line.
+    let mut fixtures: Vec<Fixture> = vec![];
+    // Transformed tags with original tags, for collision detection.
+    let mut origins: BTreeMap<String, String> = BTreeMap::new();
 
-    for (idx, line) in remove_multi_line_comments(text.as_ref())
-        .lines()
-        .enumerate()
-    {
+    // Open `-- tx: begin` block: name, annotation line, line number and position.
+    let mut transaction: Option<(String, &str, usize, usize)> = None;
+    // Number of `-- tx: begin` blocks.
+    let mut blocks = 0;
+    // Open `-- if:` blocks: line, position and whether lines are parsed.
+    let mut conditions: Vec<(usize, usize, bool)> = vec![];
+
+    // Line endings of empty lines after last query line, kept with `Whitespace::Preserve`.
+    let mut blank = String::new();
+    let preserve = options.whitespace == Some(Whitespace::Preserve);
+
+    // Start of raw text of last tag, appended to query on next tag and at the end.
+    let mut raw_start = 0;
+    let source = text;
+    let push_raw = |queries: &mut Map<Query>, tag: &Option<(Cow<'_, str>, _, _)>, start, end| {
+        if let Some(query) = tag
+            .as_ref()
+            .and_then(|(tag, _, _)| queries.get_mut(tag.as_ref()))
+        {
+            query.raw.push_str(&source[start..end]);
+        }
+    };
+
+    let text = options.language.remove_block_comments(text);
+    for (idx, (line, ending)) in split_lines(&text).enumerate() {
         if line.is_empty() {
+            if preserve {
+                blank.push_str(ending);
+            }
             continue;
         }
 
-        let (ty, value) = parse_line(line);
+        let active = conditions.last().is_none_or(|&(_, _, active)| active);
+        // Without flags `-- if:` and `-- endif` lines are usual comments.
+        let conditional = !options.flags.is_empty();
+        if let Some(condition) =
+            annotations::annotation(line, "if", &options.language).filter(|_| conditional)
+        {
+            let pos = line.as_ptr() as usize - text.as_ptr() as usize;
+            let matches = annotations::condition_matches(condition, &options.flags);
+            conditions.push((idx + 1, pos, active && matches));
+            continue;
+        }
+        if conditional && annotations::is_endif(line, &options.language) {
+            if conditions.pop().is_none() {
+                let (column, offset) =
+                    positions.at(line.as_ptr() as usize - text.as_ptr() as usize);
+                return Err(ParseError::UnbalancedCondition {
+                    line: idx + 1,
+                    column,
+                    offset,
+                });
+            }
+            continue;
+        }
+        if !active {
+            continue;
+        }
+        if let Some(value) =
+            annotations::annotation(line, "tx", &options.language).filter(|_| options.transactions)
+        {
+            let pos = line.as_ptr() as usize - text.as_ptr() as usize;
+            let (column, offset) = positions.at(pos);
+            let invalid = || ParseError::InvalidAnnotation {
+                line: idx + 1,
+                column,
+                offset,
+                annotation: line.trim().to_owned(),
+            };
+            match value.split_once(char::is_whitespace) {
+                Some(("begin", name)) if transaction.is_none() => {
+                    transaction = Some((name.trim().to_owned(), line, idx + 1, pos));
+                    blocks += 1;
+                }
+                None if value == "end" && transaction.is_some() => transaction = None,
+                _ => return Err(invalid()),
+            }
+            continue;
+        }
+
+        let (ty, value) = parse_line(line, options);
+        // Comments replaced by same number of bytes, so positions are same as in source text.
+        let pos = value.as_ptr() as usize - text.as_ptr() as usize;
+        let (column, offset) = positions.at(pos);
         match ty {
-            LineType::Empty => continue,
+            LineType::Empty => {
+                if preserve {
+                    blank.push_str(ending);
+                }
+                if last_type == Some(LineType::Tag) {
+                    if let Some(value) = annotations::annotation(line, "test", &options.language) {
+                        // Comment like `-- test: make sure it works` is documentation, error only
+                        // in strict mode.
+                        match annotations::parse_fixture(value, idx + 1) {
+                            Some(fixture) => fixtures.push(fixture),
+                            None if options.strict => {
+                                return Err(ParseError::InvalidAnnotation {
+                                    line: idx + 1,
+                                    column,
+                                    offset,
+                                    annotation: line.trim().to_owned(),
+                                });
+                            }
+                            None => {}
+                        }
+                    }
+                    if let Some(value) = annotations::annotation(line, "returns", &options.language)
+                    {
+                        // Comment like `-- returns: active users` is documentation, error only in
+                        // strict mode.
+                        match annotations::parse_returns(value) {
+                            Some(columns) => returns = Some(columns),
+                            None if options.strict => {
+                                let pos = value.as_ptr() as usize - text.as_ptr() as usize;
+                                let (column, offset) = positions.at(pos);
+                                return Err(ParseError::InvalidAnnotation {
+                                    line: idx + 1,
+                                    column,
+                                    offset,
+                                    annotation: line.trim().to_owned(),
+                                });
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                continue;
+            }
             LineType::Tag => {
+                returns = None;
+                meta_commands.clear();
+                fixtures.clear();
+                separated = false;
+                let (value, tag_attributes) = match options.attributes {
+                    true => annotations::split_attributes(value),
+                    false => (value, vec![]),
+                };
+                for (key, attribute) in &tag_attributes {
+                    if !annotations::is_valid_attribute(key, attribute) {
+                        let pos = key.as_ptr() as usize - text.as_ptr() as usize;
+                        let (column, offset) = positions.at(pos);
+                        return Err(ParseError::InvalidAnnotation {
+                            line: idx + 1,
+                            column,
+                            offset,
+                            annotation: format!("{}={}", key, attribute),
+                        });
+                    }
+                }
+                attributes = tag_attributes
+                    .into_iter()
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect();
                 if last_type.is_some() && last_type.as_ref().unwrap() == &LineType::Tag {
                     return Err(ParseError::TagOverwritten {
                         line: idx + 1,
+                        column,
+                        offset,
                         tag: value.to_owned(),
                     });
                 }
 
-                last_tag = Some(value);
+                let tag = match &options.transform_tags {
+                    Some(transform) => {
+                        let tag = (transform.0)(value);
+                        let origin = origins
+                            .entry(tag.clone())
+                            .or_insert_with(|| value.to_owned());
+                        if origin != value {
+                            return Err(ParseError::TagCollision {
+                                line: idx + 1,
+                                column,
+                                offset,
+                                tag: value.to_owned(),
+                                other: origin.clone(),
+                            });
+                        }
+                        Cow::Owned(tag)
+                    }
+                    None => Cow::Borrowed(value),
+                };
+
+                if !options.tag_names.is_valid(&tag) {
+                    return Err(ParseError::InvalidTag {
+                        line: idx + 1,
+                        column,
+                        offset,
+                        tag: tag.into_owned(),
+                    });
+                }
+
+                let line_start = line.as_ptr() as usize - text.as_ptr() as usize;
+                push_raw(queries, &last_tag, raw_start, line_start);
+                raw_start = line_start + line.len() + ending.len();
+                last_tag = Some((tag, idx + 1, pos));
+            }
+            LineType::Query if options.script.is_separator(value) => {
+                separated = true;
+                continue;
+            }
+            LineType::Query
+                if value.starts_with('\\') && options.meta_commands != MetaCommands::Query =>
+            {
+                match options.meta_commands {
+                    MetaCommands::Error => {
+                        return Err(ParseError::MetaCommand {
+                            line: idx + 1,
+                            column,
+                            offset,
+                            command: value.to_owned(),
+                        })
+                    }
+                    MetaCommands::Collect => {
+                        let query = last_tag
+                            .as_ref()
+                            .and_then(|(tag, _, _)| queries.get_mut(tag.as_ref()));
+                        match query {
+                            Some(query) => query.meta_commands.push(value.to_owned()),
+                            None if last_tag.is_some() => meta_commands.push(value.to_owned()),
+                            None => {}
+                        }
+                    }
+                    MetaCommands::Query | MetaCommands::Skip => {}
+                }
+                continue;
             }
             LineType::Query => {
+                if let (None, Some(key)) = (&last_tag, &options.prelude) {
+                    raw_start = line.as_ptr() as usize - text.as_ptr() as usize;
+                    last_tag = Some((Cow::Borrowed(key.as_str()), idx + 1, pos));
+                }
                 if last_tag.is_none() {
                     return Err(ParseError::QueryWithoutTag {
                         line: idx + 1,
+                        column,
+                        offset,
                         query: value.to_owned(),
                     });
                 }
 
-                queries
-                    .entry(last_tag.unwrap().to_owned())
-                    .and_modify(|x| {
-                        *x = format!("{} {}", *x, value);
-                    })
-                    .or_insert_with(|| value.to_owned());
+                let value = if preserve {
+                    // Line with indentation.
+                    let start = line.as_ptr() as usize - text.as_ptr() as usize;
+                    &line[..pos - start + value.len()]
+                } else {
+                    value
+                };
+                let value = match &options.variables {
+                    Some(vars) => vars::substitute(value, vars).map_err(|(start, name)| {
+                        let (column, offset) = positions.at(pos + start);
+                        ParseError::UnsetVariable {
+                            line: idx + 1,
+                            column,
+                            offset,
+                            name: name.to_owned(),
+                        }
+                    })?,
+                    None => Cow::Borrowed(value),
+                };
+
+                let value = match options.whitespace {
+                    Some(Whitespace::Minify | Whitespace::Normalize) => collapse_whitespace(value),
+                    _ => value,
+                };
+
+                if preserve {
+                    blank.insert_str(0, last_ending);
+                }
+                let newlines = match options.whitespace {
+                    Some(Whitespace::Minify) => false,
+                    Some(Whitespace::Preserve | Whitespace::Normalize) => true,
+                    None => options.preserve_newlines,
+                };
+                let separator = match (newlines, options.line_endings) {
+                    _ if separated => options.script.separator_line(),
+                    // Kept comment ends on line end.
+                    (false, _) if last_comment => "\n",
+                    (false, _) => " ",
+                    _ if preserve => blank.as_str(),
+                    (true, _) if options.whitespace.is_some() => "\n",
+                    (true, LineEnding::Normalize) => "\n",
+                    (true, LineEnding::Preserve) => last_ending,
+                };
+                last_ending = ending;
+                last_comment =
+                    options.keep_comments && options.language.find_comment(&value).is_some();
+                separated = false;
+
+                let (tag, tag_line, tag_pos) = last_tag.as_ref().unwrap();
+                let limit_exceeded =
+                    |line, (column, offset), limit, max| ParseError::LimitExceeded {
+                        line,
+                        column,
+                        offset,
+                        limit,
+                        max,
+                    };
+                match queries.entry(tag.as_ref().to_owned()) {
+                    Entry::Occupied(mut entry) => {
+                        let x = entry.get_mut();
+                        let len = x.text.len() + separator.len() + value.len();
+                        if let Some(max) = options.max_query_len.filter(|&max| len > max) {
+                            let position = (column, offset);
+                            return Err(limit_exceeded(idx + 1, position, Limit::QueryLength, max));
+                        }
+                        x.text.push_str(separator);
+                        x.lines.push((x.text.len(), idx + 1));
+                        x.text.push_str(&value);
+                        if returns.is_some() {
+                            x.returns = returns.take();
+                        }
+                        if !attributes.is_empty() {
+                            x.attributes = core::mem::take(&mut attributes);
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        let count = entry.index() + 1;
+                        if let Some(max) = options.max_tags.filter(|&max| count > max) {
+                            let position = positions.at(*tag_pos);
+                            return Err(limit_exceeded(*tag_line, position, Limit::Tags, max));
+                        }
+                        if let Some(max) = options.max_query_len.filter(|&max| value.len() > max) {
+                            let position = (column, offset);
+                            return Err(limit_exceeded(idx + 1, position, Limit::QueryLength, max));
+                        }
+                        let mut query = Query::recycled(spare.pop());
+                        query.text.push_str(&value);
+                        query.line = *tag_line;
+                        query.returns = returns.take();
+                        query.meta_commands = core::mem::take(&mut meta_commands);
+                        query.transaction = transaction
+                            .as_ref()
+                            .map(|(name, _, _, _)| (blocks - 1, name.clone()));
+                        query.attributes = core::mem::take(&mut attributes);
+                        query.fixtures = core::mem::take(&mut fixtures);
+                        query.tag_pos = *tag_pos;
+                        query.lines.push((0, idx + 1));
+                        entry.insert(query);
+                    }
+                }
             }
         };
 
+        blank.clear();
         last_type = Some(ty);
     }
+    push_raw(queries, &last_tag, raw_start, text.len());
 
-    Ok(queries)
-}
+    if let Some((_, annotation, line, pos)) = transaction {
+        let (column, offset) = positions.at(pos);
+        return Err(ParseError::InvalidAnnotation {
+            line,
+            column,
+            offset,
+            annotation: annotation.trim().to_owned(),
+        });
+    }
 
-// Inner comments are not allowed.
-// Preserve newlines for better error messages.
-fn remove_multi_line_comments(text: &str) -> Cow<'_, str> {
-    lazy_static! {
-        static ref RE: Regex = RegexBuilder::new(r#"(/\*.*?\*/)"#)
-            .multi_line(true)
-            .dot_matches_new_line(true)
-            .build()
-            .unwrap();
+    if let Some(&(line, pos, _)) = conditions.last() {
+        let (column, offset) = positions.at(pos);
+        return Err(ParseError::UnbalancedCondition {
+            line,
+            column,
+            offset,
+        });
     }
 
-    RE.replace_all(text, |caps: &regex::Captures| {
-        let mut rep = String::with_capacity(caps[1].len());
-        for c in caps[1].chars() {
-            let nc = match c {
-                '\r' => '\r',
-                '\n' => '\n',
-                _ => ' ',
+    if options.strict {
+        if let (Some(LineType::Tag), Some((tag, line, pos))) = (last_type, last_tag) {
+            let (column, offset) = positions.at(pos);
+            return Err(ParseError::EmptyQuery {
+                line,
+                column,
+                offset,
+                tag: tag.into_owned(),
+            });
+        }
+
+        for (tag, query) in queries.iter() {
+            check_strict(tag, query, &positions, options)?;
+        }
+    }
+
+    if options.strip_semicolons {
+        for query in queries.values_mut() {
+            let end = match options.keep_comments {
+                true => statements_end(&query.text, &options.language),
+                false => query.text.len(),
             };
-            rep.push(nc);
+            if query.text[..end].ends_with(';') {
+                let start = query.text[..end - 1].trim_end().len();
+                query.text.replace_range(start..end, "");
+                for (line_start, _) in query.lines.iter_mut() {
+                    if *line_start >= end {
+                        *line_start -= end - start;
+                    }
+                }
+            }
+        }
+    }
+
+    if options.tag_comments {
+        for (tag, query) in queries.iter_mut() {
+            let comment = format!("/* rsyesql: {} */ ", tag.replace("*/", "* /"));
+            for (start, _) in query.lines.iter_mut() {
+                *start += comment.len();
+            }
+            query.text.insert_str(0, &comment);
+        }
+    }
+
+    if let Some(transform) = &options.transform_queries {
+        for (tag, query) in queries.iter_mut() {
+            query.text = (transform.0)(tag, core::mem::take(&mut query.text));
+        }
+    }
+
+    if options.script.separator().is_some() {
+        for query in queries.values_mut() {
+            query.batches = split_batches(&query.text, options.script);
+        }
+    }
+
+    options.order.apply(queries);
+    Ok(())
+}
+
+// Runs of whitespace outside of quotes replaced by single space.
+fn collapse_whitespace(line: Cow<'_, str>) -> Cow<'_, str> {
+    let mut quote = None;
+    let mut space = false;
+    let collapsed = line.chars().all(|c| {
+        let ok = quote.is_some() || !c.is_whitespace() || (c == ' ' && !space);
+        match c {
+            '\'' | '"' if quote.is_none() => quote = Some(c),
+            c if quote == Some(c) => quote = None,
+            _ => {}
+        }
+        space = quote.is_none() && c.is_whitespace();
+        ok
+    });
+    if collapsed {
+        return line;
+    }
+
+    let mut text = String::with_capacity(line.len());
+    let mut quote = None;
+    for c in line.chars() {
+        match c {
+            c if quote.is_none() && c.is_whitespace() => {
+                if !text.ends_with(' ') {
+                    text.push(' ');
+                }
+                continue;
+            }
+            '\'' | '"' if quote.is_none() => quote = Some(c),
+            c if quote == Some(c) => quote = None,
+            _ => {}
+        }
+        text.push(c);
+    }
+    Cow::Owned(text)
+}
+
+// Column and offset in source text by byte position in text without BOM.
+struct Positions<'a> {
+    text: &'a str,
+    bom: usize,
+}
+
+impl Positions<'_> {
+    fn at(&self, pos: usize) -> (usize, usize) {
+        let start = self.text[..pos]
+            .rfind(['\n', '\r'])
+            .map_or(0, |idx| idx + 1);
+        (self.text[start..pos].chars().count() + 1, self.bom + pos)
+    }
+}
+
+fn check_strict(
+    tag: &str,
+    query: &Query,
+    positions: &Positions<'_>,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
+    let (column, offset) = positions.at(query.tag_pos);
+    // Kept comments are not part of statements.
+    let text = match options.keep_comments {
+        true => Cow::Owned(
+            split_lines(&query.text)
+                .map(|(line, _)| {
+                    options
+                        .language
+                        .find_comment(line)
+                        .map_or(line, |idx| &line[..idx])
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim_end()
+                .to_owned(),
+        ),
+        false => Cow::Borrowed(query.text.as_str()),
+    };
+    let statements = split_statements(&text);
+    if statements.len() > 1 {
+        return Err(ParseError::MultipleStatements {
+            line: query.line,
+            column,
+            offset,
+            tag: tag.to_owned(),
+        });
+    }
+    if statements.is_empty() {
+        return Err(ParseError::EmptyQuery {
+            line: query.line,
+            column,
+            offset,
+            tag: tag.to_owned(),
+        });
+    }
+    if !text.ends_with(';') {
+        return Err(ParseError::MissingSemicolon {
+            line: query.line,
+            column,
+            offset,
+            tag: tag.to_owned(),
+        });
+    }
+    Ok(())
+}
+
+// Split query by separator lines of script.
+// End of statements before kept trailing comments.
+fn statements_end(text: &str, language: &Language) -> usize {
+    let mut end = text.trim_end().len();
+    while end > 0 {
+        let start = text[..end].rfind(['\n', '\r']).map_or(0, |idx| idx + 1);
+        match language.find_comment(&text[start..end]) {
+            Some(idx) if text[start..start + idx].trim().is_empty() => {
+                end = text[..start].trim_end().len();
+            }
+            Some(idx) => return text[..start + idx].trim_end().len(),
+            None => return end,
         }
-        rep
+    }
+    end
+}
+
+fn split_batches(text: &str, script: Script) -> Vec<String> {
+    let mut batches = vec![];
+    let mut batch = String::new();
+    for (line, ending) in split_lines(text) {
+        if script.is_separator(line.trim()) {
+            if !batch.trim().is_empty() {
+                batches.push(batch.trim().to_owned());
+            }
+            batch.clear();
+        } else {
+            batch.push_str(line);
+            batch.push_str(ending);
+        }
+    }
+    if !batch.trim().is_empty() {
+        batches.push(batch.trim().to_owned());
+    }
+    batches
+}
+
+// Split text to lines with line endings, `\n`, `\r\n` and `\r` are recognized.
+fn split_lines(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = text;
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let (line, ending) = match rest.find(['\n', '\r']) {
+            Some(idx) if rest[idx..].starts_with("\r\n") => (&rest[..idx], &rest[idx..idx + 2]),
+            Some(idx) => (&rest[..idx], &rest[idx..=idx]),
+            None => (rest, ""),
+        };
+        rest = &rest[line.len() + ending.len()..];
+        Some((line, ending))
     })
 }
 
-// Remove single-line comment and trim string
-fn parse_line(mut line: &str) -> (LineType, &str) {
-    lazy_static! {
-        static ref RE_TAG: Regex = Regex::new(r#"^\s*--\s*name\s*:\s*(.*?)\s*$"#).unwrap();
+fn count_line_breaks(text: &str) -> usize {
+    text.matches('\n').count() + text.matches('\r').count() - text.matches("\r\n").count()
+}
+
+// Tag from line matched by `-- name: tag` (with any whitespaces) or by custom tag marker.
+fn find_tag<'a>(line: &'a str, options: &ParseOptions) -> Option<&'a str> {
+    #[cfg(feature = "std")]
+    {
+        if let Some(re) = &options.tag_marker {
+            return re
+                .captures(line)
+                .and_then(|caps| caps.name("tag").or_else(|| caps.get(1)))
+                .map(|tag| tag.as_str().trim());
+        }
     }
+    #[cfg(not(feature = "std"))]
+    let _ = options;
+
+    annotations::annotation(line, "name", &options.language)
+}
 
-    match RE_TAG.captures(line) {
-        Some(caps) => (LineType::Tag, caps.get(1).unwrap().as_str()),
+// Remove single-line comment and trim string
+fn parse_line<'a>(mut line: &'a str, options: &ParseOptions) -> (LineType, &'a str) {
+    match find_tag(line, options) {
+        Some(tag) => (LineType::Tag, tag),
+        None if options.script.is_comment(line) => (LineType::Empty, &line[..0]),
         None => {
-            if let Some(idx) = line.find("--") {
-                line = line.get(0..idx).unwrap();
+            if let Some(idx) = options.language.find_comment(line) {
+                // Comment after query is kept, comment line is empty.
+                let query = line.get(0..idx).unwrap();
+                if !options.keep_comments || query.trim().is_empty() {
+                    line = query;
+                }
             };
 
             line = line.trim();
@@ -162,6 +1386,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::unnecessary_to_owned)]
     fn accept_str_string() {
         let text = "--name: x\nquery";
         assert!(parse(text).is_ok());
@@ -175,6 +1400,8 @@ mod tests {
             parse(text).err(),
             Some(ParseError::TagOverwritten {
                 line: 2,
+                column: 9,
+                offset: 18,
                 tag: "x".to_owned()
             })
         );
@@ -187,65 +1414,730 @@ mod tests {
             parse(text).err(),
             Some(ParseError::QueryWithoutTag {
                 line: 1,
+                column: 1,
+                offset: 0,
                 query: "SELECT 1;".to_owned()
             })
         );
     }
 
+    #[test]
+    fn prelude() {
+        let options = ParseOptions::new().prelude("_prelude").strict(true);
+        let text = "SET a = 1;\n\nBEGIN;\n--name: x\nselect 1;\n--name: y\nselect 2;";
+        assert_eq!(
+            options.parse(text).err(),
+            Some(ParseError::MultipleStatements {
+                line: 1,
+                column: 1,
+                offset: 0,
+                tag: "_prelude".to_owned()
+            })
+        );
+
+        let queries = ParseOptions::new().prelude("_prelude").parse(text).unwrap();
+        assert_eq!(
+            queries.keys().collect::<Vec<_>>(),
+            vec!["_prelude", "x", "y"]
+        );
+        assert_eq!(queries["_prelude"], "SET a = 1; BEGIN;");
+    }
+
+    #[test]
+    fn conditional_blocks() {
+        let text = "--name: a\nselect 1;\n-- if: env=test\n--name: b\nselect 2;\n-- if: db\n--name: c\nselect 3;\n-- endif\n-- endif\n--name: d\nselect 4;";
+        let options = ParseOptions::new().flag("env", "test");
+        let queries = options.parse(text).unwrap();
+        assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["a", "b", "d"]);
+        let queries = options.flag("db", "pg").parse(text).unwrap();
+        assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["a", "b", "c", "d"]);
+        let queries = ParseOptions::new().flag("db", "pg").parse(text).unwrap();
+        assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["a", "d"]);
+
+        // Without flags directives are comments.
+        assert_eq!(parse(text).unwrap().len(), 4);
+        assert!(parse("--name: a\nselect 1;\n -- endif").is_ok());
+
+        let options = ParseOptions::new().flag("env", "test");
+        assert_eq!(
+            options.parse("--name: a\nselect 1;\n -- endif").err(),
+            Some(ParseError::UnbalancedCondition {
+                line: 3,
+                column: 1,
+                offset: 20
+            })
+        );
+        assert_eq!(
+            options.parse("-- if: x\n--name: a\nselect 1;").err(),
+            Some(ParseError::UnbalancedCondition {
+                line: 1,
+                column: 1,
+                offset: 0
+            })
+        );
+    }
+
+    #[test]
+    fn raw_text() {
+        let text = "-- name: a\r\n-- about a\r\nselect 1 /* x */;\r\n\r\n--name: b\nselect\n  2;\n--name: a\nselect 3;";
+        let queries = ParseOptions::new()
+            .prelude("p")
+            .parse_detailed(text)
+            .unwrap();
+        assert_eq!(queries["a"].text, "select 1        ; select 3;");
+        assert_eq!(
+            queries["a"].raw,
+            "-- about a\r\nselect 1 /* x */;\r\n\r\nselect 3;"
+        );
+        assert_eq!(queries["b"].raw, "select\n  2;\n");
+
+        let queries = ParseOptions::new()
+            .prelude("p")
+            .parse_detailed("set x;\n--name: a\nb;")
+            .unwrap();
+        assert_eq!(queries["p"].raw, "set x;\n");
+    }
+
+    #[test]
+    fn keep_comments() {
+        let text = "--name: a\n-- about a\nselect 1, -- first\n  2; -- hint\n--name: b\nselect 3;";
+        let options = ParseOptions::new().keep_comments(true);
+        let queries = options.parse(text).unwrap();
+        assert_eq!(queries["a"], "select 1, -- first\n2; -- hint");
+        assert_eq!(queries["b"], "select 3;");
+        assert!(options.strict(true).parse(text).is_ok());
+        assert_eq!(parse(text).unwrap()["a"], "select 1, 2;");
+    }
+
+    #[test]
+    fn meta_commands() {
+        let text = "\\set ON_ERROR_STOP on\n--name: a\n\\set x 1\nselect :x;\n  \\i other.sql\n--name: b\nselect 2;";
+        let queries = parse_detailed(text);
+        assert_eq!(
+            queries.err(),
+            Some(ParseError::QueryWithoutTag {
+                line: 1,
+                column: 1,
+                offset: 0,
+                query: "\\set ON_ERROR_STOP on".to_owned()
+            })
+        );
+
+        let options = ParseOptions::new().meta_commands(MetaCommands::Skip);
+        assert_eq!(options.parse(text).unwrap()["a"], "select :x;");
+
+        let options = ParseOptions::new().meta_commands(MetaCommands::Collect);
+        let queries = options.parse_detailed(text).unwrap();
+        assert_eq!(queries["a"].text, "select :x;");
+        assert_eq!(queries["a"].meta_commands, ["\\set x 1", "\\i other.sql"]);
+        assert!(queries["b"].meta_commands.is_empty());
+
+        let options = ParseOptions::new().meta_commands(MetaCommands::Error);
+        assert_eq!(
+            options.parse(&text[22..]).err(),
+            Some(ParseError::MetaCommand {
+                line: 2,
+                column: 1,
+                offset: 10,
+                command: "\\set x 1".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn script_batches() {
+        let text = "--name: a\nCREATE TABLE t (id int)\ngo\nINSERT INTO t VALUES (1) -- x\n  GO -- end\nGO\n--name: b\nGO\nSELECT 1";
+        let options = ParseOptions::new().script(Script::SqlServer);
+        let queries = options.parse_detailed(text).unwrap();
+        assert_eq!(
+            queries["a"].text,
+            "CREATE TABLE t (id int)\nGO\nINSERT INTO t VALUES (1)"
+        );
+        assert_eq!(
+            queries["a"].batches,
+            ["CREATE TABLE t (id int)", "INSERT INTO t VALUES (1)"]
+        );
+        assert_eq!(queries["b"].batches, ["SELECT 1"]);
+        assert!(parse_detailed(text).unwrap()["b"].batches.is_empty());
+    }
+
+    #[test]
+    fn script_sqlplus() {
+        let text = "--name: a\nREM create procedure\nCREATE PROCEDURE p AS\nBEGIN\n  NULL;\nEND;\n/\nremark grant\nGRANT EXECUTE ON p TO u;\nREMOVE 1;";
+        let options = ParseOptions::new().script(Script::SqlPlus);
+        let queries = options.parse_detailed(text).unwrap();
+        assert_eq!(
+            queries["a"].batches,
+            [
+                "CREATE PROCEDURE p AS BEGIN NULL; END;",
+                "GRANT EXECUTE ON p TO u; REMOVE 1;"
+            ]
+        );
+        assert!(parse(text).unwrap()["a"].starts_with("REM create"));
+    }
+
+    #[test]
+    fn tag_attributes() {
+        let text = "--name: a timeout=100ms retries=2\nselect 1;\n--name: b\nselect 2;";
+        let options = ParseOptions::new().attributes(true);
+        let queries = options
+            .clone()
+            .tag_names(TagNames::RustIdent)
+            .parse_detailed(text)
+            .unwrap();
+        assert_eq!(queries.keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(queries["a"].timeout(), Some(Duration::from_millis(100)));
+        assert_eq!(queries["a"].attribute("retries"), Some("2"));
+        assert_eq!(queries["b"].timeout(), None);
+        let retry = Retry {
+            retries: 2,
+            backoff: Duration::ZERO,
+        };
+        assert_eq!(queries["a"].retry(), Some(retry));
+        assert_eq!(queries["b"].retry(), None);
+
+        assert_eq!(
+            options.parse("--name: a timeout=5\nselect 1;").err(),
+            Some(ParseError::InvalidAnnotation {
+                line: 1,
+                column: 11,
+                offset: 10,
+                annotation: "timeout=5".to_owned()
+            })
+        );
+        assert_eq!(
+            options.parse("--name: a retries=x\nselect 1;").err(),
+            Some(ParseError::InvalidAnnotation {
+                line: 1,
+                column: 11,
+                offset: 10,
+                annotation: "retries=x".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn transaction_blocks() {
+        let text = "-- name: a\nselect 1;\n-- tx: begin init\n-- name: b\nselect 2;\n-- tx: end\n-- name: c\nselect 3;";
+        let options = ParseOptions::new().transactions(true);
+        let queries = options.parse_detailed(text).unwrap();
+        assert_eq!(queries["a"].transaction, None);
+        assert_eq!(queries["b"].transaction, Some((0, "init".to_owned())));
+        assert_eq!(queries["c"].transaction, None);
+        // Without option `-- tx:` lines are comments.
+        assert_eq!(parse_detailed(text).unwrap()["b"].transaction, None);
+        assert!(parse("-- tx: begins\n-- name: a\nselect 1;").is_ok());
+
+        let invalid = |line, column, offset, annotation: &str| {
+            Some(ParseError::InvalidAnnotation {
+                line,
+                column,
+                offset,
+                annotation: annotation.to_owned(),
+            })
+        };
+        assert_eq!(
+            options.parse("-- tx: begin\n-- name: a\nselect 1;").err(),
+            invalid(1, 1, 0, "-- tx: begin")
+        );
+        assert_eq!(
+            options
+                .parse("-- tx: begin x\n-- tx: begin y\n-- name: a\nselect 1;")
+                .err(),
+            invalid(2, 1, 15, "-- tx: begin y")
+        );
+        assert_eq!(
+            options.parse("-- name: a\nselect 1;\n-- tx: end").err(),
+            invalid(3, 1, 21, "-- tx: end")
+        );
+        assert_eq!(
+            options.parse("-- tx: begin x\n-- name: a\nselect 1;").err(),
+            invalid(1, 1, 0, "-- tx: begin x")
+        );
+    }
+
+    #[test]
+    fn custom_hasher() {
+        #[derive(Default)]
+        struct XorHasher(u64);
+
+        impl core::hash::Hasher for XorHasher {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+                }
+            }
+        }
+
+        type Hasher = core::hash::BuildHasherDefault<XorHasher>;
+        let queries = parse_with_hasher::<Hasher, _>("--name: a\n1;\n--name: b\n2;").unwrap();
+        assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(queries["b"], "2;");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn shared_strings() {
+        let queries = parse_shared("--name: a\n1;").unwrap();
+        let clone = queries.clone();
+        assert!(Arc::ptr_eq(&queries["a"], &clone["a"]));
+        assert_eq!(
+            clone.get_key_value("a").map(|(k, v)| (&**k, &**v)),
+            Some(("a", "1;"))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn static_strings() {
+        let text = "\u{feff}--name: a\n1;\n\n--name: b\n2\n3;\n--name: a\n4;";
+        let queries = ParseOptions::new()
+            .preserve_newlines(true)
+            .parse_static(text)
+            .unwrap();
+        let borrowed = |value: &Cow<'_, str>| matches!(value, Cow::Borrowed(_));
+        assert!(queries.keys().all(borrowed));
+        assert_eq!(queries.len(), 2);
+        assert!(borrowed(&queries["b"]));
+        assert_eq!(queries["b"], "2\n3;");
+        assert!(!borrowed(&queries["a"]));
+
+        let queries = ParseOptions::new()
+            .transform_tags(|tag| tag.to_uppercase())
+            .parse_static("--name: a\n1;")
+            .unwrap();
+        assert!(!borrowed(queries.get_index(0).unwrap().0));
+    }
+
+    #[test]
+    fn test_fixtures() {
+        let text =
+            "-- name: a\n-- test: id=1\nselect 1;\n-- test: expect_error\n-- name: b\nselect 2;";
+        let queries = parse_detailed(text).unwrap();
+        let lines = queries["a"]
+            .fixtures
+            .iter()
+            .map(|f| f.line)
+            .collect::<Vec<_>>();
+        // Comment after query text is not an annotation.
+        assert_eq!(lines, [2]);
+        assert!(queries["b"].fixtures.is_empty());
+
+        let text = "-- name: a\n-- test: expect_rows\nselect 1;";
+        assert!(parse_detailed(text).unwrap()["a"].fixtures.is_empty());
+        assert_eq!(
+            ParseOptions::new().strict(true).parse(text).err(),
+            Some(ParseError::InvalidAnnotation {
+                line: 2,
+                column: 1,
+                offset: 11,
+                annotation: "-- test: expect_rows".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn whitespace_modes() {
+        let text = "--name: a\r\n  select  'a  b',\t\"x  y\" -- c\r\n\r\n\t from t\r\n\r\n--name: b\nselect 1;";
+        let parse = |mode| {
+            ParseOptions::new()
+                .whitespace(mode)
+                .parse_detailed(text)
+                .unwrap()
+        };
+        let queries = parse(Whitespace::Minify);
+        assert_eq!(queries["a"].text, "select 'a  b', \"x  y\" from t");
+        assert_eq!(queries["a"].line_at(22), 4);
+        assert_eq!(
+            parse(Whitespace::Normalize)["a"].text,
+            "select 'a  b', \"x  y\"\nfrom t"
+        );
+        assert_eq!(
+            parse(Whitespace::Preserve)["a"].text,
+            "  select  'a  b',\t\"x  y\"\r\n\r\n\t from t"
+        );
+        assert_eq!(parse(Whitespace::Preserve)["b"].text, "select 1;");
+        assert_eq!(
+            collapse_whitespace(Cow::Borrowed("a b 'c  d'")),
+            Cow::<str>::Borrowed("a b 'c  d'")
+        );
+    }
+
+    #[test]
+    fn substitute_vars() {
+        let text = "--name: a\nselect * from ${SCHEMA}.t\n  where x = '${X';";
+        let options = ParseOptions::new().substitute_vars(vec![("SCHEMA", "app")]);
+        let queries = options.parse(text).unwrap();
+        assert_eq!(queries["a"], "select * from app.t where x = '${X';");
+        assert_eq!(
+            ParseOptions::new().parse(text).unwrap()["a"],
+            "select * from ${SCHEMA}.t where x = '${X';"
+        );
+
+        let options = ParseOptions::new().substitute_vars(vec![("X", "1")]);
+        assert_eq!(
+            options.parse(text).err(),
+            Some(ParseError::UnsetVariable {
+                line: 2,
+                column: 15,
+                offset: 24,
+                name: "SCHEMA".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn returns_annotation() {
+        let text =
+            "--name: x\n-- returns: id:i64\n\nselect 1;\n--name: y\n-- returns: a\nselect 2;";
+        assert_eq!(parse_detailed(text).unwrap()["y"].returns, None);
+        assert_eq!(
+            ParseOptions::new().strict(true).parse_detailed(text).err(),
+            Some(ParseError::InvalidAnnotation {
+                line: 6,
+                column: 13,
+                offset: 62,
+                annotation: "-- returns: a".to_owned()
+            })
+        );
+
+        let text =
+            "--name: x\n-- returns: id:i64\n\nselect 1;\n-- returns: a\n--name: y\nselect 2;";
+        let queries = parse_detailed(text).unwrap();
+        let column = Column {
+            name: "id".to_owned(),
+            ty: "i64".to_owned(),
+        };
+        assert_eq!(queries["x"].returns, Some(vec![column]));
+        assert_eq!(queries["y"].returns, None);
+    }
+
+    #[test]
+    fn error_limit_exceeded() {
+        let text = "--name: x\nselect 1;\n--name: y\nselect\n2;";
+        assert!(ParseOptions::new()
+            .max_input_size(text.len())
+            .parse(text)
+            .is_ok());
+        assert_eq!(
+            ParseOptions::new().max_input_size(10).parse(text).err(),
+            Some(ParseError::LimitExceeded {
+                line: 1,
+                column: 1,
+                offset: 0,
+                limit: Limit::InputSize,
+                max: 10
+            })
+        );
+        assert_eq!(
+            ParseOptions::new().max_tags(1).parse(text).err(),
+            Some(ParseError::LimitExceeded {
+                line: 3,
+                column: 9,
+                offset: 28,
+                limit: Limit::Tags,
+                max: 1
+            })
+        );
+        assert!(ParseOptions::new().max_query_len(9).parse(text).is_ok());
+        assert_eq!(
+            ParseOptions::new().max_query_len(8).parse(text).err(),
+            Some(ParseError::LimitExceeded {
+                line: 2,
+                column: 1,
+                offset: 10,
+                limit: Limit::QueryLength,
+                max: 8
+            })
+        );
+        assert_eq!(
+            ParseOptions::new()
+                .max_query_len(8)
+                .parse("--name: y\nselect\n2;")
+                .err()
+                .map(|err| err.line()),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn error_binary_input() {
+        let text = "--name: x\nselect 1;\n\u{0}\u{1}";
+        assert_eq!(
+            parse(text).err(),
+            Some(ParseError::BinaryInput {
+                line: 3,
+                column: 1,
+                offset: 20
+            })
+        );
+        assert!(parse("--name: x\r\n\tselect 1;\x0c").is_ok());
+    }
+
+    #[test]
+    fn error_invalid_tag() {
+        let options = ParseOptions::new().tag_names(TagNames::NoWhitespace);
+        assert_eq!(
+            options
+                .parse("--name: x\nselect 1;\n-- name: start end")
+                .err(),
+            Some(ParseError::InvalidTag {
+                line: 3,
+                column: 10,
+                offset: 29,
+                tag: "start end".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn transform_tags() {
+        let options = ParseOptions::new().transform_tags(|tag| tag.replace('-', "_"));
+        let queries = options.parse("--name: select-users\nselect 1;").unwrap();
+        assert_eq!(queries.get("select_users").unwrap(), "select 1;");
+
+        let text = "--name: select-users\nselect 1;\n--name: select_users\nselect 2;";
+        assert_eq!(
+            options.parse(text).err(),
+            Some(ParseError::TagCollision {
+                line: 3,
+                column: 9,
+                offset: 39,
+                tag: "select_users".to_owned(),
+                other: "select-users".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn transform_queries() {
+        let options = ParseOptions::new()
+            .strip_semicolons(true)
+            .transform_queries(|tag, query| format!("{} LIMIT 10; -- {}", query, tag));
+        let queries = options.parse("--name: x\nselect 1;").unwrap();
+        assert_eq!(queries.get("x").unwrap(), "select 1 LIMIT 10; -- x");
+    }
+
+    #[test]
+    fn tag_comments() {
+        let options = ParseOptions::new().tag_comments(true);
+        let queries = options.parse_detailed("--name: x*/\nselect 1;").unwrap();
+        let query = queries.get("x*/").unwrap();
+        assert_eq!(query.text, "/* rsyesql: x* / */ select 1;");
+        assert_eq!(query.line_at(0), 2);
+    }
+
     #[test]
     fn parse_text() {
         let text = "-- just comment\n--name: x\nselect 2;";
-        let mut queries = IndexMap::new();
+        let mut queries = Map::default();
+        queries.insert("x".to_owned(), "select 2;".to_owned());
+        assert_eq!(parse(text).ok(), Some(queries));
+    }
+
+    #[test]
+    fn parse_text_with_bom() {
+        let text = "\u{feff}--name: x\nselect 2;";
+        let mut queries = Map::default();
         queries.insert("x".to_owned(), "select 2;".to_owned());
         assert_eq!(parse(text).ok(), Some(queries));
     }
 
+    #[test]
+    fn parse_text_detailed() {
+        let text = "--name: x\nselect 1,\n\n  2;\n--name: y\nselect 3;";
+        let queries = parse_detailed(text).unwrap();
+
+        let x = queries.get("x").unwrap();
+        assert_eq!(x.text, "select 1, 2;");
+        assert_eq!(x.line, 1);
+        assert_eq!(x.line_at(0), 2);
+        assert_eq!(x.line_at(9), 2);
+        assert_eq!(x.line_at(10), 4);
+        assert_eq!(x.line_at(100), 4);
+
+        let y = queries.get("y").unwrap();
+        assert_eq!(y.text, "select 3;");
+        assert_eq!(y.line, 5);
+        assert_eq!(y.line_at(0), 6);
+    }
+
+    #[test]
+    fn strict_valid() {
+        let options = ParseOptions::new().strict(true);
+        let text = "--name: x\nselect 1\n, ';';\n--name: y\nselect 2;";
+        assert!(options.parse(text).is_ok());
+    }
+
+    #[test]
+    fn strict_errors() {
+        let options = ParseOptions::new().strict(true);
+        assert_eq!(
+            options.parse("--name: x\nselect 1;\n--name: y").err(),
+            Some(ParseError::EmptyQuery {
+                line: 3,
+                column: 9,
+                offset: 28,
+                tag: "y".to_owned()
+            })
+        );
+        assert_eq!(
+            options.parse("--name: x\n;").err(),
+            Some(ParseError::EmptyQuery {
+                line: 1,
+                column: 9,
+                offset: 8,
+                tag: "x".to_owned()
+            })
+        );
+        assert_eq!(
+            options.parse("--name: x\nselect 1; select 2;").err(),
+            Some(ParseError::MultipleStatements {
+                line: 1,
+                column: 9,
+                offset: 8,
+                tag: "x".to_owned()
+            })
+        );
+        assert_eq!(
+            options.parse("--name: x\nselect 1").err(),
+            Some(ParseError::MissingSemicolon {
+                line: 1,
+                column: 9,
+                offset: 8,
+                tag: "x".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn strip_semicolons() {
+        let options = ParseOptions::new().strip_semicolons(true);
+        let text = "--name: x\nselect ';' ;\n--name: y\nselect 1; select 2;\n--name: z\nselect 3";
+        let queries = options.parse(text).unwrap();
+        assert_eq!(queries.get("x").unwrap(), "select ';'");
+        assert_eq!(queries.get("y").unwrap(), "select 1; select 2");
+        assert_eq!(queries.get("z").unwrap(), "select 3");
+
+        let options = options.keep_comments(true);
+        let text = "--name: x\nselect 1, -- a\n  2 ; -- hint\n--name: y\nselect ';' -- ;\n";
+        let queries = options.parse(text).unwrap();
+        assert_eq!(queries["x"], "select 1, -- a\n2 -- hint");
+        assert_eq!(queries["y"], "select ';' -- ;");
+    }
+
+    #[test]
+    fn line_numbers_with_crlf() {
+        let text = "--name: x\r\nselect 1;\r\n\r\n--name: x\r--name: y";
+        assert_eq!(
+            parse(text).err(),
+            Some(ParseError::TagOverwritten {
+                line: 5,
+                column: 9,
+                offset: 42,
+                tag: "y".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn error_position_with_bom_and_comment() {
+        let err = parse("\u{feff}--name: x\n/* é */ --name: y").unwrap_err();
+        assert_eq!((err.line(), err.column(), err.offset()), (2, 17, 30));
+    }
+
+    #[test]
+    fn preserve_newlines() {
+        let text = "--name: x\r\nselect 1\r\n, 2\n, 3;\r\n";
+        let options = ParseOptions::new().preserve_newlines(true);
+        assert_eq!(
+            options.parse(text).unwrap().get("x").unwrap(),
+            "select 1\n, 2\n, 3;"
+        );
+
+        let options = options.line_endings(LineEnding::Preserve);
+        assert_eq!(
+            options.parse(text).unwrap().get("x").unwrap(),
+            "select 1\r\n, 2\n, 3;"
+        );
+    }
+
+    #[test]
+    fn split_lines_endings() {
+        let lines = split_lines("a\nb\r\nc\rd").collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![("a", "\n"), ("b", "\r\n"), ("c", "\r"), ("d", "")]
+        );
+        assert_eq!(count_line_breaks("a\nb\r\nc\rd"), 3);
+    }
+
     #[test]
     fn remove_zero_comments() {
         let text = "123\nabc";
         let result = "123\nabc";
-        assert_eq!(remove_multi_line_comments(text), result);
+        assert_eq!(Language::sql().remove_block_comments(text), result);
     }
 
     #[test]
     fn remove_line_comment() {
         let text = "123/*qqq*/ /*123**/ 321";
         let result = "123                 321";
-        assert_eq!(remove_multi_line_comments(text), result);
+        assert_eq!(Language::sql().remove_block_comments(text), result);
     }
 
     #[test]
     fn remove_multi_line_comment() {
         let text = "123/*9\nqqq\nz*/321";
         let result = "123   \n   \n   321";
-        assert_eq!(remove_multi_line_comments(text), result);
+        assert_eq!(Language::sql().remove_block_comments(text), result);
     }
 
     #[test]
     fn parse_line_with_comment() {
         let line = "33 -- 123";
         let result = (LineType::Query, "33");
-        assert_eq!(parse_line(line), result);
+        assert_eq!(parse_line(line, &ParseOptions::new()), result);
     }
 
     #[test]
     fn parse_line_invalid_tag() {
         let line = "0 -- name: start";
         let result = (LineType::Query, "0");
-        assert_eq!(parse_line(line), result);
+        assert_eq!(parse_line(line, &ParseOptions::new()), result);
     }
 
     #[test]
     fn parse_line_tag() {
         let line = " --  name:start";
         let result = (LineType::Tag, "start");
-        assert_eq!(parse_line(line), result);
+        assert_eq!(parse_line(line, &ParseOptions::new()), result);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parse_line_custom_marker() {
+        let re = regex::Regex::new(r#"^--\s*:name\s+(?P<tag>\S+)"#).unwrap();
+        let options = ParseOptions::new().tag_marker(re);
+        assert_eq!(
+            parse_line("-- :name foo :? :*", &options),
+            (LineType::Tag, "foo")
+        );
+        assert_eq!(parse_line("-- name: foo", &options), (LineType::Empty, ""));
+
+        let re = regex::Regex::new(r#"^#\s*name:(.*)"#).unwrap();
+        let options = ParseOptions::new().tag_marker(re);
+        assert_eq!(parse_line("# name: foo", &options), (LineType::Tag, "foo"));
     }
 
     #[test]
     fn parse_line_tag_with_space() {
         let line = "-- name: start end ";
         let result = (LineType::Tag, "start end");
-        assert_eq!(parse_line(line), result);
+        assert_eq!(parse_line(line, &ParseOptions::new()), result);
     }
 }
@@ -0,0 +1,310 @@
+//! Configurable lint rules for parsed queries, see [Linter].
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use regex::Regex;
+
+use crate::{Query, TagNames};
+
+/// Query passed to [Rule::check].
+#[derive(Debug, Clone, Copy)]
+pub struct LintQuery<'a> {
+    /// Query tag.
+    pub tag: &'a str,
+    /// Parsed query.
+    pub query: &'a Query,
+    /// Comment lines directly above tag line, without `--`, joined by `\n`.
+    pub doc: Option<&'a str>,
+}
+
+/// Lint rule checked against every query, see [Linter].
+pub trait Rule {
+    /// Rule name in findings.
+    fn name(&self) -> &str;
+
+    /// Violations of rule as `(line, message)`.
+    fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)>;
+}
+
+/// Rule violation found by [Linter].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Name of violated rule.
+    pub rule: String,
+    /// Query tag.
+    pub tag: String,
+    /// Source line (1-based).
+    pub line: usize,
+    /// Description of violation.
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"line {}: {} ("{}"): {}"#,
+            self.line, self.rule, self.tag, self.message
+        )
+    }
+}
+
+/// Set of [Rule]s run over parsed queries.
+///
+/// ```
+/// use rsyesql::lint::{Linter, NoSelectStar, TrailingSemicolon};
+///
+/// let text = "-- name: users\nSELECT * FROM users";
+/// let queries = rsyesql::parse_detailed(text).unwrap();
+/// let findings = Linter::new()
+///     .rule(TrailingSemicolon)
+///     .rule(NoSelectStar::default())
+///     .check(text, &queries);
+/// assert_eq!(findings.len(), 2);
+/// assert_eq!(findings[0].rule, "trailing-semicolon");
+/// ```
+#[derive(Default)]
+pub struct Linter {
+    rules: Vec<Box<dyn Rule + Send + Sync>>,
+}
+
+impl fmt::Debug for Linter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.rules.iter().map(|rule| rule.name()))
+            .finish()
+    }
+}
+
+impl Linter {
+    /// Linter without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add rule.
+    pub fn rule<R: Rule + Send + Sync + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Check queries parsed from `text`, findings ordered by query and rule.
+    pub fn check(&self, text: &str, queries: &IndexMap<String, Query>) -> Vec<Finding> {
+        let lines = text.lines().collect::<Vec<_>>();
+        let mut findings = vec![];
+        for (tag, query) in queries {
+            let doc = doc_comment(&lines, query.line);
+            let lint_query = LintQuery {
+                tag,
+                query,
+                doc: doc.as_deref(),
+            };
+            for rule in &self.rules {
+                for (line, message) in rule.check(&lint_query) {
+                    findings.push(Finding {
+                        rule: rule.name().to_owned(),
+                        tag: tag.clone(),
+                        line,
+                        message,
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+// Comment lines above tag line, stops on empty line or tag.
+pub(crate) fn doc_comment(lines: &[&str], tag_line: usize) -> Option<String> {
+    let mut doc = lines[..tag_line.saturating_sub(1).min(lines.len())]
+        .iter()
+        .rev()
+        .map_while(|line| line.trim().strip_prefix("--"))
+        .take_while(|comment| !comment.trim_start().starts_with("name"))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    if doc.iter().all(|line| line.is_empty()) {
+        return None;
+    }
+    doc.reverse();
+    Some(doc.join("\n"))
+}
+
+/// Query ends with `;`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrailingSemicolon;
+
+impl Rule for TrailingSemicolon {
+    fn name(&self) -> &str {
+        "trailing-semicolon"
+    }
+
+    fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)> {
+        let text = query.query.text.trim_end();
+        if text.ends_with(';') {
+            return vec![];
+        }
+        let line = query.query.line_at(text.len().saturating_sub(1));
+        vec![(line, "Query should end with `;`".to_owned())]
+    }
+}
+
+/// Query does not contain `SELECT *`.
+#[derive(Debug, Clone)]
+pub struct NoSelectStar {
+    re: Regex,
+}
+
+impl Default for NoSelectStar {
+    fn default() -> Self {
+        Self {
+            re: Regex::new(r"(?i)\bselect\s+(?:distinct\s+)?\*").unwrap(),
+        }
+    }
+}
+
+impl Rule for NoSelectStar {
+    fn name(&self) -> &str {
+        "no-select-star"
+    }
+
+    fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)> {
+        self.re
+            .find_iter(&query.query.text)
+            .map(|m| {
+                let line = query.query.line_at(m.start());
+                (line, "Columns should be listed instead of `*`".to_owned())
+            })
+            .collect()
+    }
+}
+
+/// Tag satisfies [TagNames] rule.
+#[derive(Debug, Clone)]
+pub struct TagNaming(pub TagNames);
+
+impl Rule for TagNaming {
+    fn name(&self) -> &str {
+        "tag-naming"
+    }
+
+    fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)> {
+        if self.0.is_valid(query.tag) {
+            return vec![];
+        }
+        let message = format!("Tag does not satisfy {:?} rule", self.0);
+        vec![(query.query.line, message)]
+    }
+}
+
+/// Tag line preceded by comment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequireDoc;
+
+impl Rule for RequireDoc {
+    fn name(&self) -> &str {
+        "require-doc"
+    }
+
+    fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)> {
+        if query.doc.is_some() {
+            return vec![];
+        }
+        let message = "Query should be documented by comment above tag".to_owned();
+        vec![(query.query.line, message)]
+    }
+}
+
+/// Query text is not longer than number of bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxLength(pub usize);
+
+impl Rule for MaxLength {
+    fn name(&self) -> &str {
+        "max-length"
+    }
+
+    fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)> {
+        let len = query.query.text.len();
+        if len <= self.0 {
+            return vec![];
+        }
+        let message = format!("Query length {} exceeds {}", len, self.0);
+        vec![(query.query.line, message)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_detailed;
+
+    const TEXT: &str = "-- Select users.\n--\n-- Without deleted.\n-- name: select-users\nSELECT id\nFROM users\nWHERE NOT deleted;\n\n-- name: select_all\nselect\n  DISTINCT * from t";
+
+    fn findings(linter: Linter) -> Vec<(String, usize)> {
+        let queries = parse_detailed(TEXT).unwrap();
+        linter
+            .check(TEXT, &queries)
+            .into_iter()
+            .map(|f| (f.rule, f.line))
+            .collect()
+    }
+
+    #[test]
+    fn doc_comments() {
+        let lines = TEXT.lines().collect::<Vec<_>>();
+        assert_eq!(
+            doc_comment(&lines, 4).as_deref(),
+            Some("Select users.\n\nWithout deleted.")
+        );
+        assert_eq!(doc_comment(&lines, 9), None);
+        assert_eq!(doc_comment(&["--name: a", "x;"], 1), None);
+    }
+
+    #[test]
+    fn builtin_rules() {
+        let linter = Linter::new()
+            .rule(TrailingSemicolon)
+            .rule(NoSelectStar::default())
+            .rule(TagNaming(TagNames::RustIdent))
+            .rule(RequireDoc)
+            .rule(MaxLength(30));
+        assert_eq!(
+            findings(linter),
+            vec![
+                ("tag-naming".to_owned(), 4),
+                ("max-length".to_owned(), 4),
+                ("trailing-semicolon".to_owned(), 11),
+                ("no-select-star".to_owned(), 10),
+                ("require-doc".to_owned(), 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_rule() {
+        struct NoDelete;
+
+        impl Rule for NoDelete {
+            fn name(&self) -> &str {
+                "no-delete"
+            }
+
+            fn check(&self, query: &LintQuery<'_>) -> Vec<(usize, String)> {
+                match query.query.text.find("DELETE") {
+                    Some(pos) => vec![(query.query.line_at(pos), "DELETE".to_owned())],
+                    None => vec![],
+                }
+            }
+        }
+
+        let text = "-- name: x\nDELETE FROM t;";
+        let queries = parse_detailed(text).unwrap();
+        let findings = Linter::new().rule(NoDelete).check(text, &queries);
+        assert_eq!(
+            findings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![r#"line 2: no-delete ("x"): DELETE"#]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt::{self, Write};
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+use sha2::{Digest, Sha256};
+
+use crate::split::dollar_quote_end;
+use crate::Query;
+
+const HEADER: &str = "# rsyesql queries.lock v2";
+
+quick_error! {
+    /// The error type for [Manifest::parse].
+    #[derive(Debug, PartialEq)]
+    pub enum ManifestError {
+        /// First line is not `# rsyesql queries.lock v2`.
+        Header {
+            display("Missing manifest header: {}", HEADER)
+        }
+        /// Line is not `<tag> <checksum> [file]`, or escape in tag or file is invalid.
+        InvalidLine(line: usize) {
+            display("Invalid manifest line {}", line)
+        }
+    }
+}
+
+/// Locked query, line of `queries.lock`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    /// Query tag.
+    pub tag: String,
+    /// SHA-256 of query as lowercase hex, see [Queries::checksums](crate::Queries::checksums).
+    pub checksum: String,
+    /// Source file of query, see [Query::file].
+    pub file: Option<PathBuf>,
+}
+
+/// Difference between locked and loaded queries, see [Manifest::verify].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Drift {
+    /// Query text is different.
+    Changed {
+        /// Query tag.
+        tag: String,
+        /// Locked checksum.
+        locked: String,
+        /// Checksum of loaded query.
+        actual: String,
+    },
+    /// Query is locked, but not loaded.
+    Missing(String),
+    /// Query is loaded, but not locked.
+    Unlocked(String),
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::Changed {
+                tag,
+                locked,
+                actual,
+            } => write!(
+                f,
+                r#"Query "{}" changed: locked {}, actual {}"#,
+                tag, locked, actual
+            ),
+            Drift::Missing(tag) => write!(f, r#"Locked query "{}" is not loaded"#, tag),
+            Drift::Unlocked(tag) => write!(f, r#"Query "{}" is not locked"#, tag),
+        }
+    }
+}
+
+/// Manifest of reviewed queries (`queries.lock`): tag, checksum and source file of every query.
+///
+/// Written on review ([Display](fmt::Display)), queries loaded at runtime are compared with
+/// [verify](Self::verify) to detect drift of deployed SQL. In tag and file `\`, space and tab
+/// are escaped as `\\`, `\s` and `\t`.
+///
+/// ```
+/// use rsyesql::Manifest;
+///
+/// let queries = rsyesql::parse_named("users.sql", "-- name: select\nSELECT 1;").unwrap();
+/// let lock = Manifest::from_queries(&queries).to_string();
+/// assert!(lock.ends_with("select 17db4fd369edb9244b9f91d9aeed145c3d04ad8ba6e95d06247f07a63527d11a users.sql\n"));
+///
+/// let manifest = Manifest::parse(&lock).unwrap();
+/// assert!(manifest.verify(&queries).is_empty());
+/// let changed = rsyesql::parse_named("users.sql", "-- name: select\nSELECT 2;").unwrap();
+/// assert_eq!(manifest.verify(&changed).len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    /// Entries sorted by tag.
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Manifest of queries.
+    pub fn from_queries(queries: &IndexMap<String, Query>) -> Self {
+        let mut entries = queries
+            .iter()
+            .map(|(tag, query)| ManifestEntry {
+                tag: tag.clone(),
+                checksum: checksum(&query.text),
+                file: query.file.as_deref().map(PathBuf::from),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.tag.cmp(&b.tag));
+        Self { entries }
+    }
+
+    /// Parse `queries.lock` text.
+    pub fn parse(text: &str) -> Result<Self, ManifestError> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim_end()) != Some(HEADER) {
+            return Err(ManifestError::Header);
+        }
+        let mut entries = vec![];
+        for (idx, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || ManifestError::InvalidLine(idx + 1);
+            let mut parts = line.split(' ');
+            let (tag, checksum) = match (parts.next(), parts.next()) {
+                (Some(tag), Some(checksum))
+                    if !tag.is_empty()
+                        && checksum.len() == 64
+                        && checksum.bytes().all(|b| b.is_ascii_hexdigit()) =>
+                {
+                    (unescape(tag).ok_or_else(invalid)?, checksum)
+                }
+                _ => return Err(invalid()),
+            };
+            let file = match (parts.next(), parts.next()) {
+                (None, _) => None,
+                (Some(file), None) => Some(PathBuf::from(unescape(file).ok_or_else(invalid)?)),
+                (Some(_), Some(_)) => return Err(invalid()),
+            };
+            entries.push(ManifestEntry {
+                tag,
+                checksum: checksum.to_ascii_lowercase(),
+                file,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Compare loaded queries with manifest, returns changed and missing queries in order of
+    /// manifest, then not locked queries sorted by tag.
+    ///
+    /// Source files are not compared, queries can be loaded from different paths.
+    pub fn verify(&self, queries: &IndexMap<String, Query>) -> Vec<Drift> {
+        let mut drift = vec![];
+        for entry in &self.entries {
+            match queries.get(&entry.tag) {
+                Some(query) => {
+                    let actual = checksum(&query.text);
+                    if actual != entry.checksum {
+                        drift.push(Drift::Changed {
+                            tag: entry.tag.clone(),
+                            locked: entry.checksum.clone(),
+                            actual,
+                        });
+                    }
+                }
+                None => drift.push(Drift::Missing(entry.tag.clone())),
+            }
+        }
+        let mut unlocked = queries
+            .keys()
+            .filter(|tag| !self.entries.iter().any(|entry| entry.tag == **tag))
+            .collect::<Vec<_>>();
+        unlocked.sort();
+        drift.extend(unlocked.into_iter().cloned().map(Drift::Unlocked));
+        drift
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for entry in &self.entries {
+            write!(f, "{} {}", escape(&entry.tag), entry.checksum)?;
+            if let Some(file) = &entry.file {
+                write!(f, " {}", escape(&file.display().to_string()))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Field of manifest line without spaces.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                's' => ' ',
+                't' => '\t',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(unescaped)
+}
+
+// SHA-256 of query as lowercase hex, whitespace normalized outside of quoted strings and
+// identifiers (`'...'`, `"..."`, `$$...$$`).
+pub(crate) fn checksum(query: &str) -> String {
+    let query = query.trim();
+    let mut hasher = Sha256::new();
+    let mut space = false;
+    let mut prev: Option<char> = None;
+    let mut pos = 0;
+    while let Some(c) = query[pos..].chars().next() {
+        let end = match c {
+            '\'' | '"' => query[pos + 1..]
+                .find(c)
+                .map_or(query.len(), |idx| pos + 1 + idx + 1),
+            '$' if !prev.is_some_and(|p| p.is_alphanumeric() || p == '_') => {
+                dollar_quote_end(query, pos)
+            }
+            _ => pos + c.len_utf8(),
+        };
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                hasher.update(b" ");
+                space = false;
+            }
+            hasher.update(&query.as_bytes()[pos..end]);
+        }
+        prev = query[pos..end].chars().last();
+        pos = end;
+    }
+    let mut hex = String::with_capacity(64);
+    for byte in hasher.finalize().iter() {
+        write!(hex, "{:02x}", byte).unwrap();
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_detailed;
+
+    #[test]
+    fn verify_drift() {
+        let queries = parse_detailed("-- name: b\nSELECT 2;\n-- name: a\nSELECT 1;").unwrap();
+        let manifest = Manifest::from_queries(&queries);
+        let text = manifest.to_string();
+        assert_eq!(text.lines().nth(1).unwrap().split(' ').next(), Some("a"));
+        assert_eq!(Manifest::parse(&text).unwrap(), manifest);
+
+        let loaded = parse_detailed("-- name: a\nSELECT\n  1;\n-- name: c\nSELECT 3;").unwrap();
+        let drift = manifest
+            .verify(&loaded)
+            .iter()
+            .map(|drift| drift.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            drift,
+            [
+                r#"Locked query "b" is not loaded"#,
+                r#"Query "c" is not locked"#
+            ]
+        );
+
+        assert_eq!(checksum("SELECT  'x y'"), checksum("SELECT\n'x y' "));
+        assert_ne!(checksum("SELECT 'x  y'"), checksum("SELECT 'x y'"));
+        assert_ne!(checksum("SELECT $$x  y$$"), checksum("SELECT $$x y$$"));
+
+        let queries =
+            crate::parse_named("my sql/a\\b.sql", "-- name: select users\nSELECT 1;").unwrap();
+        let manifest = Manifest::from_queries(&queries);
+        let text = manifest.to_string();
+        assert!(text.contains("select\\susers "));
+        assert!(text.ends_with(" my\\ssql/a\\\\b.sql\n"));
+        assert_eq!(Manifest::parse(&text).unwrap(), manifest);
+        assert_eq!(
+            Manifest::parse(&format!("{}\nx\\q {}", HEADER, "0".repeat(64))),
+            Err(ManifestError::InvalidLine(2))
+        );
+
+        assert_eq!(Manifest::parse("b 1"), Err(ManifestError::Header));
+        assert_eq!(
+            Manifest::parse(&format!("{}\n\nb 1", HEADER)),
+            Err(ManifestError::InvalidLine(3))
+        );
+    }
+}
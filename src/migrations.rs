@@ -0,0 +1,146 @@
+use indexmap::IndexMap;
+
+use crate::QueriesError;
+
+/// Migration from pair of queries with tags `name.up` and `name.down`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Migration<'a> {
+    /// Tag without `.up` / `.down` suffix.
+    pub name: &'a str,
+    /// Query which applies migration.
+    pub up: &'a str,
+    /// Query which reverts migration, if defined.
+    pub down: Option<&'a str>,
+}
+
+/// Migrations from queries with tags `name.up` and `name.down`, other queries are ignored.
+///
+/// Migrations ordered by definition of `up` query.
+///
+/// ```
+/// use rsyesql::Queries;
+///
+/// let text = "-- name: 001_users.up
+/// CREATE TABLE users (id INT);
+/// -- name: 001_users.down
+/// DROP TABLE users;
+/// -- name: 002_posts.up
+/// CREATE TABLE posts (id INT);";
+/// let queries = Queries::parse(text).unwrap();
+/// let migrations = queries.migrations().unwrap();
+/// assert_eq!(
+///     migrations.up().map(|(name, _)| name).collect::<Vec<_>>(),
+///     vec!["001_users", "002_posts"]
+/// );
+/// assert_eq!(
+///     migrations.down().collect::<Vec<_>>(),
+///     vec![("001_users", "DROP TABLE users;")]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Migrations<'a> {
+    migrations: Vec<Migration<'a>>,
+}
+
+impl<'a> Migrations<'a> {
+    /// Collect migrations from queries.
+    ///
+    /// Returns [QueriesError::MigrationWithoutUp] if `name.down` is defined without `name.up`.
+    pub fn new(queries: &'a IndexMap<String, String>) -> Result<Self, QueriesError> {
+        let mut migrations: IndexMap<&str, Migration<'a>> = IndexMap::new();
+        for (tag, query) in queries {
+            if let Some(name) = tag.strip_suffix(".up") {
+                migrations.insert(
+                    name,
+                    Migration {
+                        name,
+                        up: query,
+                        down: None,
+                    },
+                );
+            }
+        }
+        for (tag, query) in queries {
+            if let Some(name) = tag.strip_suffix(".down") {
+                match migrations.get_mut(name) {
+                    Some(migration) => migration.down = Some(query),
+                    None => {
+                        return Err(QueriesError::MigrationWithoutUp { tag: tag.clone() });
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            migrations: migrations.into_iter().map(|(_, m)| m).collect(),
+        })
+    }
+
+    /// Migration by name (tag without suffix).
+    pub fn get(&self, name: &str) -> Option<&Migration<'a>> {
+        self.migrations.iter().find(|m| m.name == name)
+    }
+
+    /// Iterate over migrations in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Migration<'a>> {
+        self.migrations.iter()
+    }
+
+    /// `(name, up)` queries in order of application.
+    pub fn up(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        self.migrations.iter().map(|m| (m.name, m.up))
+    }
+
+    /// `(name, down)` queries in order of reverting (reversed), migrations without `down` query
+    /// are skipped.
+    pub fn down(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        self.migrations
+            .iter()
+            .rev()
+            .filter_map(|m| m.down.map(|down| (m.name, down)))
+    }
+
+    /// Number of migrations.
+    pub fn len(&self) -> usize {
+        self.migrations.len()
+    }
+
+    /// Returns `true` if there are no migrations.
+    pub fn is_empty(&self) -> bool {
+        self.migrations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn pairs_in_order() {
+        let text = "--name: 002.down\nd2;\n--name: 001.up\nu1;\n--name: select\ns;\n--name: 002.up\nu2;\n--name: 001.down\nd1;";
+        let queries = parse(text).unwrap();
+        let migrations = Migrations::new(&queries).unwrap();
+        assert_eq!(migrations.len(), 2);
+        assert_eq!(
+            migrations.up().collect::<Vec<_>>(),
+            vec![("001", "u1;"), ("002", "u2;")]
+        );
+        assert_eq!(
+            migrations.down().collect::<Vec<_>>(),
+            vec![("002", "d2;"), ("001", "d1;")]
+        );
+        assert_eq!(migrations.get("001").and_then(|m| m.down), Some("d1;"));
+    }
+
+    #[test]
+    fn down_without_up() {
+        let queries = parse("--name: 001.down\nd1;").unwrap();
+        assert_eq!(
+            Migrations::new(&queries).err(),
+            Some(QueriesError::MigrationWithoutUp {
+                tag: "001.down".to_owned()
+            })
+        );
+    }
+}
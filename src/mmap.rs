@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str;
+
+use memmap2::Mmap;
+
+use crate::{FileError, Map, ParseOptions, Query};
+
+quick_error! {
+    /// The error type for [parse_mmap].
+    #[derive(Debug)]
+    pub enum MmapError {
+        /// Failed to open or map file, or file is not UTF-8.
+        Io { file: PathBuf, err: io::Error } {
+            display("{}: {}", file.display(), err)
+            cause(err)
+        }
+        /// Failed to parse file.
+        File(err: FileError) {
+            display("{}", err)
+            cause(err)
+            from()
+        }
+    }
+}
+
+/// Parse SQL queries from memory-mapped file, see [parse_named](crate::parse_named).
+///
+/// File is not read to heap before parsing, only queries are copied. File should not be modified
+/// while parsing, otherwise result is undefined.
+///
+/// ```ignore
+/// let queries = rsyesql::parse_mmap("./seeds.sql")?;
+/// ```
+pub fn parse_mmap<P: AsRef<Path>>(path: P) -> Result<Map<Query>, MmapError> {
+    ParseOptions::new().parse_mmap(path)
+}
+
+impl ParseOptions {
+    /// Parse SQL queries from memory-mapped file with these options, see [parse_mmap].
+    pub fn parse_mmap<P: AsRef<Path>>(&self, path: P) -> Result<Map<Query>, MmapError> {
+        let path = path.as_ref();
+        let io_err = |err| MmapError::Io {
+            file: path.to_path_buf(),
+            err,
+        };
+
+        let file = File::open(path).map_err(io_err)?;
+        // Safety: mapped file is only read while parsing, see function docs.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(io_err)?;
+        let text = str::from_utf8(&mmap)
+            .map_err(|err| io_err(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+        Ok(self.parse_named(path, text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn mapped_file() {
+        let path = env::temp_dir().join(format!("rsyesql-mmap-{}.sql", std::process::id()));
+        fs::write(&path, "--name: a\nselect 1;").unwrap();
+        let queries = parse_mmap(&path);
+        fs::write(&path, b"--name: a\nselect '\xff';").unwrap();
+        let invalid = parse_mmap(&path);
+        fs::remove_file(&path).unwrap();
+
+        let queries = queries.unwrap();
+        assert_eq!(queries["a"].text, "select 1;");
+        assert_eq!(queries["a"].file.as_deref(), Some(path.as_path()));
+        assert!(
+            matches!(invalid, Err(MmapError::Io { err, .. }) if err.kind() == io::ErrorKind::InvalidData)
+        );
+        assert!(matches!(parse_mmap(&path), Err(MmapError::Io { .. })));
+    }
+}
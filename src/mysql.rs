@@ -0,0 +1,469 @@
+//! [mysql_async](https://docs.rs/mysql_async/) integration.
+//!
+//! Named parameters (`:name`) translated to `?` placeholders, [Params::Named] converted to
+//! positional parameters in order of placeholders.
+//!
+//! ```ignore
+//! use mysql_async::params;
+//!
+//! let queries = rsyesql::parse(include_str!("./queries.sql"))?;
+//! let queries = rsyesql::mysql::MysqlQueries::new(&queries);
+//! let users: Vec<(u64, String)> = queries
+//!     .query_all(&mut conn, "select_user", params! { "id" => 42 })
+//!     .await?;
+//! ```
+
+use indexmap::IndexMap;
+use mysql_async::prelude::{FromRow, Queryable};
+use mysql_async::{Conn, Params, Pool, Row, Statement, TxOpts, Value};
+
+use crate::{
+    is_explainable, positional, transactions, Fixture, Placeholder, PositionalQuery, Query,
+    QueryExecutor, Transaction,
+};
+
+quick_error! {
+    /// The error type for [MysqlQueries].
+    #[derive(Debug)]
+    pub enum Error {
+        /// Query with tag is not defined.
+        UnknownTag(tag: String) {
+            display(r#"Unknown tag: "{}""#, tag)
+        }
+        /// Named parameter used in query is not provided.
+        MissingParam { tag: String, name: String } {
+            display(r#"Missing parameter "{}" for "{}""#, name, tag)
+        }
+        /// Query preparation or execution failed.
+        Mysql { tag: String, err: mysql_async::Error } {
+            display(r#"Failed to execute "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// `EXPLAIN` of query failed in [explain_all].
+        Explain { tag: String, err: mysql_async::Error } {
+            display(r#"Failed to explain "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Query execution failed in [execute_all] or [execute_transactions].
+        ExecuteAll { tag: String, line: usize, err: mysql_async::Error } {
+            display(r#"Failed to execute "{}" (line: {}): {}"#, tag, line, err)
+            cause(err)
+        }
+        /// Transaction begin or commit failed.
+        Transaction(err: mysql_async::Error) {
+            display("Transaction failed: {}", err)
+            cause(err)
+        }
+        /// Test of [Query::fixtures] failed in [run_fixtures].
+        Fixture { tag: String, line: usize, message: String } {
+            display(r#"Test of "{}" (line: {}) failed: {}"#, tag, line, message)
+        }
+    }
+}
+
+/// Queries with named parameters translated to `?` placeholders.
+#[derive(Debug, Clone)]
+pub struct MysqlQueries {
+    queries: IndexMap<String, PositionalQuery>,
+}
+
+impl MysqlQueries {
+    /// Translate queries.
+    pub fn new<I, T, Q>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let queries = queries
+            .into_iter()
+            .map(|(tag, query)| {
+                let query = positional(query.as_ref(), Placeholder::Question);
+                (tag.as_ref().to_owned(), query)
+            })
+            .collect();
+        Self { queries }
+    }
+
+    /// Translated query by tag.
+    pub fn query(&self, tag: &str) -> Result<&PositionalQuery, Error> {
+        self.queries
+            .get(tag)
+            .ok_or_else(|| Error::UnknownTag(tag.to_owned()))
+    }
+
+    /// Prepare query, statement cached by connection.
+    pub async fn prepare<C: Queryable>(&self, conn: &mut C, tag: &str) -> Result<Statement, Error> {
+        let query = self.query(tag)?;
+        conn.prep(query.text.as_str())
+            .await
+            .map_err(|err| mysql_error(tag, err))
+    }
+
+    /// Execute query, result is dropped.
+    pub async fn execute<C, P>(&self, conn: &mut C, tag: &str, params: P) -> Result<(), Error>
+    where
+        C: Queryable,
+        P: Into<Params>,
+    {
+        let params = self.params(tag, params.into())?;
+        let statement = self.prepare(conn, tag).await?;
+        conn.exec_drop(statement, params)
+            .await
+            .map_err(|err| mysql_error(tag, err))
+    }
+
+    /// Execute query, returns all rows.
+    pub async fn query_all<T, C, P>(
+        &self,
+        conn: &mut C,
+        tag: &str,
+        params: P,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: FromRow + Send + 'static,
+        C: Queryable,
+        P: Into<Params>,
+    {
+        let params = self.params(tag, params.into())?;
+        let statement = self.prepare(conn, tag).await?;
+        conn.exec(statement, params)
+            .await
+            .map_err(|err| mysql_error(tag, err))
+    }
+
+    /// Execute query, returns first row.
+    pub async fn query_first<T, C, P>(
+        &self,
+        conn: &mut C,
+        tag: &str,
+        params: P,
+    ) -> Result<Option<T>, Error>
+    where
+        T: FromRow + Send + 'static,
+        C: Queryable,
+        P: Into<Params>,
+    {
+        let params = self.params(tag, params.into())?;
+        let statement = self.prepare(conn, tag).await?;
+        conn.exec_first(statement, params)
+            .await
+            .map_err(|err| mysql_error(tag, err))
+    }
+
+    // Named parameters to positional, in order of placeholders.
+    fn params(&self, tag: &str, params: Params) -> Result<Params, Error> {
+        let query = self.query(tag)?;
+        match params {
+            Params::Named(values) => query
+                .params
+                .iter()
+                .map(|name| match values.get(name.as_bytes()) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(Error::MissingParam {
+                        tag: tag.to_owned(),
+                        name: name.clone(),
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Params::Positional),
+            Params::Empty if !query.params.is_empty() => Err(Error::MissingParam {
+                tag: tag.to_owned(),
+                name: query.params[0].clone(),
+            }),
+            params => Ok(params),
+        }
+    }
+}
+
+/// Pool with queries executed by tag, every call takes connection from pool.
+#[derive(Debug, Clone)]
+pub struct PooledQueries {
+    pool: Pool,
+    queries: MysqlQueries,
+}
+
+impl PooledQueries {
+    /// Pair pool with queries.
+    pub fn new(pool: Pool, queries: MysqlQueries) -> Self {
+        Self { pool, queries }
+    }
+
+    /// Underlying pool.
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    /// Translated queries.
+    pub fn queries(&self) -> &MysqlQueries {
+        &self.queries
+    }
+
+    async fn conn(&self, tag: &str) -> Result<Conn, Error> {
+        self.pool
+            .get_conn()
+            .await
+            .map_err(|err| mysql_error(tag, err))
+    }
+}
+
+impl QueryExecutor for PooledQueries {
+    type Params<'p> = Params;
+    type Row = Row;
+    type Error = Error;
+
+    async fn execute(&self, tag: &str, params: Self::Params<'_>) -> Result<u64, Error> {
+        let mut conn = self.conn(tag).await?;
+        self.queries.execute(&mut conn, tag, params).await?;
+        Ok(conn.affected_rows())
+    }
+
+    async fn query_many(&self, tag: &str, params: Self::Params<'_>) -> Result<Vec<Row>, Error> {
+        let mut conn = self.conn(tag).await?;
+        self.queries.query_all(&mut conn, tag, params).await
+    }
+
+    async fn query_one(&self, tag: &str, params: Self::Params<'_>) -> Result<Option<Row>, Error> {
+        let mut conn = self.conn(tag).await?;
+        self.queries.query_first(&mut conn, tag, params).await
+    }
+}
+
+/// Execute every query in definition order inside transaction, for example for database scheme
+/// creation.
+///
+/// Every query can contain multiple statements. Execution stops on first failed query, transaction
+/// is rolled back. Note that MySQL commits DDL statements (`CREATE TABLE`, ...) implicitly.
+pub async fn execute_all(conn: &mut Conn, queries: &IndexMap<String, Query>) -> Result<(), Error> {
+    let mut tx = conn
+        .start_transaction(TxOpts::default())
+        .await
+        .map_err(Error::Transaction)?;
+    for (tag, query) in queries {
+        tx.query_drop(query.text.as_str())
+            .await
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.clone(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().await.map_err(Error::Transaction)
+}
+
+/// Execute queries in definition order, every group of [transactions] inside own transaction.
+///
+/// Queries of `-- tx: begin <name>` block are committed together, every query outside of block is
+/// committed alone. Execution stops on first failed query, only transaction of failed query is
+/// rolled back. Note that MySQL commits DDL statements implicitly.
+///
+/// On deadlock or lock wait timeout group is executed again by [Transaction::retry].
+pub async fn execute_transactions(
+    conn: &mut Conn,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error> {
+    for group in transactions(queries) {
+        let mut attempt = 0;
+        loop {
+            match (execute_group(conn, &group).await, group.retry()) {
+                (Err(err), Some(retry)) if attempt < retry.retries && is_transient(&err) => {
+                    tokio::time::sleep(retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                (result, _) => break result?,
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn execute_group(conn: &mut Conn, group: &Transaction<'_>) -> Result<(), Error> {
+    let mut tx = conn
+        .start_transaction(TxOpts::default())
+        .await
+        .map_err(Error::Transaction)?;
+    for &(tag, query) in &group.queries {
+        tx.query_drop(query.text.as_str())
+            .await
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.to_owned(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().await.map_err(Error::Transaction)
+}
+
+// Deadlock (`ER_LOCK_DEADLOCK`) or lock wait timeout (`ER_LOCK_WAIT_TIMEOUT`).
+fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::ExecuteAll { err, .. } | Error::Transaction(err) => matches!(
+            err,
+            mysql_async::Error::Server(err) if err.code == 1213 || err.code == 1205
+        ),
+        _ => false,
+    }
+}
+
+/// `EXPLAIN` of every [is_explainable] query, for example to check queries against schema
+/// without data. Queries are not executed, named parameters translated to `?` and bound to
+/// `NULL`.
+///
+/// Plan is rows with columns separated by `\t` joined by `\n`, queries which are not
+/// explainable are skipped.
+pub async fn explain_all(
+    conn: &mut Conn,
+    queries: &IndexMap<String, Query>,
+) -> IndexMap<String, Result<String, Error>> {
+    let mut plans = IndexMap::new();
+    for (tag, query) in queries {
+        if !is_explainable(&query.text) {
+            continue;
+        }
+        let plan = explain(conn, &query.text)
+            .await
+            .map_err(|err| Error::Explain {
+                tag: tag.clone(),
+                err,
+            });
+        plans.insert(tag.clone(), plan);
+    }
+    plans
+}
+
+async fn explain(conn: &mut Conn, query: &str) -> Result<String, mysql_async::Error> {
+    let query = positional(query, Placeholder::Question);
+    let statement = conn.prep(format!("EXPLAIN {}", query.text)).await?;
+    let params = vec![Value::NULL; usize::from(statement.num_params())];
+    let rows: Vec<mysql_async::Row> = conn.exec(&statement, params).await?;
+    let plan = rows
+        .into_iter()
+        .map(|row| {
+            row.unwrap()
+                .into_iter()
+                .map(|value| match value {
+                    Value::NULL => "NULL".to_owned(),
+                    Value::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                    value => value.as_sql(true),
+                })
+                .collect::<Vec<_>>()
+                .join("\t")
+        })
+        .collect::<Vec<_>>();
+    Ok(plan.join("\n"))
+}
+
+/// Run [Query::fixtures] against test database, returns failed tests.
+///
+/// Every test is executed in own transaction, which is rolled back. Named parameters are bound by
+/// [positional], numbers are bound as numbers, other values as text (`null` is `NULL`, quotes
+/// `'...'` are removed).
+///
+/// ```ignore
+/// let failures = rsyesql::mysql::run_fixtures(&mut conn, &queries).await;
+/// assert!(failures.is_empty(), "{:?}", failures);
+/// ```
+pub async fn run_fixtures(conn: &mut Conn, queries: &IndexMap<String, Query>) -> Vec<Error> {
+    let mut failures = vec![];
+    for (tag, query) in queries {
+        for fixture in &query.fixtures {
+            let result = count_rows(conn, &query.text, fixture).await;
+            if let Err(message) = fixture.check(result) {
+                failures.push(Error::Fixture {
+                    tag: tag.clone(),
+                    line: fixture.line,
+                    message,
+                });
+            }
+        }
+    }
+    failures
+}
+
+// Number of returned rows, or changed rows for query without columns.
+async fn count_rows(
+    conn: &mut Conn,
+    query: &str,
+    fixture: &Fixture,
+) -> Result<usize, mysql_async::Error> {
+    let query = positional(query, Placeholder::Question);
+    let mut tx = conn.start_transaction(TxOpts::default()).await?;
+    let statement = tx.prep(&query.text).await?;
+    let params = (0..usize::from(statement.num_params()))
+        .map(|idx| {
+            let number = (idx + 1).to_string();
+            let value = fixture.params.iter().find(|(name, _)| {
+                *name == number || query.params.get(idx).is_some_and(|param| param == name)
+            });
+            fixture_value(value.map(|(_, value)| value.as_str()))
+        })
+        .collect::<Vec<_>>();
+    let rows = if statement.columns().is_empty() {
+        tx.exec_drop(&statement, params).await?;
+        tx.affected_rows() as usize
+    } else {
+        tx.exec::<mysql_async::Row, _, _>(&statement, params)
+            .await?
+            .len()
+    };
+    tx.rollback().await?;
+    Ok(rows)
+}
+
+// Fixture value, missing value is `NULL`.
+fn fixture_value(value: Option<&str>) -> Value {
+    match value {
+        None => Value::NULL,
+        Some(value) if value.eq_ignore_ascii_case("null") => Value::NULL,
+        Some(value) => {
+            if let Ok(value) = value.parse() {
+                Value::Int(value)
+            } else if let Ok(value) = value.parse() {
+                Value::Double(value)
+            } else {
+                let value = value
+                    .strip_prefix('\'')
+                    .and_then(|value| value.strip_suffix('\''))
+                    .unwrap_or(value);
+                Value::Bytes(value.as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+fn mysql_error(tag: &str, err: mysql_async::Error) -> Error {
+    Error::Mysql {
+        tag: tag.to_owned(),
+        err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mysql_async::{params, Value};
+
+    #[test]
+    fn named_to_positional() {
+        let queries = MysqlQueries::new(vec![("x", "SELECT :a, :b, :a")]);
+        assert_eq!(queries.query("x").unwrap().text, "SELECT ?, ?, ?");
+
+        let params = queries.params("x", params! { "a" => 1, "b" => "s" });
+        assert_eq!(
+            params.unwrap(),
+            Params::Positional(vec![Value::Int(1), Value::from("s"), Value::Int(1)])
+        );
+
+        let params = queries.params("x", params! { "a" => 1 });
+        assert!(matches!(params, Err(Error::MissingParam { name, .. }) if name == "b"));
+    }
+
+    #[test]
+    fn fixture_values() {
+        assert_eq!(fixture_value(None), Value::NULL);
+        assert_eq!(fixture_value(Some("NULL")), Value::NULL);
+        assert_eq!(fixture_value(Some("-1")), Value::Int(-1));
+        assert_eq!(fixture_value(Some("1.5")), Value::Double(1.5));
+        assert_eq!(fixture_value(Some("'a b'")), Value::from("a b"));
+    }
+}
@@ -0,0 +1,774 @@
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::BuildHasher;
+#[cfg(feature = "std")]
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use indexmap::IndexMap;
+#[cfg(feature = "std")]
+use regex::Regex;
+
+use crate::vars::Variables;
+#[cfg(feature = "std")]
+use crate::FileError;
+use crate::{parse_text, Language, Layered, Map, ParseError, Query};
+
+/// Options for parsing SQL queries as text.
+///
+/// ```
+/// let queries = rsyesql::ParseOptions::new()
+///     .strict(true)
+///     .parse("-- name: x\nSELECT 1;")
+///     .unwrap();
+/// assert_eq!(queries.get("x").unwrap(), "SELECT 1;");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) strict: bool,
+    pub(crate) strip_semicolons: bool,
+    pub(crate) preserve_newlines: bool,
+    pub(crate) line_endings: LineEnding,
+    pub(crate) whitespace: Option<Whitespace>,
+    pub(crate) tag_names: TagNames,
+    #[cfg(feature = "std")]
+    pub(crate) tag_marker: Option<Regex>,
+    pub(crate) transform_tags: Option<Hook<TransformTag>>,
+    pub(crate) tag_comments: bool,
+    pub(crate) attributes: bool,
+    pub(crate) transactions: bool,
+    pub(crate) keep_comments: bool,
+    pub(crate) meta_commands: MetaCommands,
+    pub(crate) script: Script,
+    pub(crate) language: Language,
+    pub(crate) transform_queries: Option<Hook<TransformQuery>>,
+    pub(crate) prelude: Option<String>,
+    pub(crate) flags: Vec<(String, String)>,
+    pub(crate) variables: Option<Variables>,
+    pub(crate) max_input_size: Option<usize>,
+    pub(crate) max_tags: Option<usize>,
+    pub(crate) max_query_len: Option<usize>,
+    pub(crate) order: Sort,
+}
+
+type TransformTag = dyn Fn(&str) -> String + Send + Sync;
+type TransformQuery = dyn Fn(&str, String) -> String + Send + Sync;
+type CompareTags = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+// Order of returned queries.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Sort {
+    #[default]
+    Definition,
+    Order(Order),
+    Custom(Hook<CompareTags>),
+}
+
+// Closure in options, shared between clones.
+pub(crate) struct Hook<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Hook(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> PartialEq for Hook<F> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+/// Handling of lines starting with `\` (psql meta-commands), see [ParseOptions::meta_commands].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MetaCommands {
+    /// Lines are part of query.
+    #[default]
+    Query,
+    /// Lines are removed.
+    Skip,
+    /// Lines are removed and collected to [Query::meta_commands], lines before first tag are
+    /// removed.
+    Collect,
+    /// Line is [ParseError::MetaCommand].
+    Error,
+}
+
+/// Conventions of SQL client scripts, see [ParseOptions::script].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Script {
+    /// Without client specific lines.
+    #[default]
+    Standard,
+    /// SQL Server (`sqlcmd`, SSMS): line `GO` separates batches in [Query::batches], in query
+    /// text batches are separated by `\nGO\n`.
+    SqlServer,
+    /// Oracle SQL*Plus: line `/` terminates statement (PL/SQL block) in [Query::batches], in
+    /// query text statements are separated by `\n/\n`. Lines starting with `REM` or `REMARK`
+    /// are comments.
+    SqlPlus,
+}
+
+impl Script {
+    pub(crate) fn separator(&self) -> Option<&'static str> {
+        match self {
+            Script::Standard => None,
+            Script::SqlServer => Some("GO"),
+            Script::SqlPlus => Some("/"),
+        }
+    }
+
+    // Separator with line endings, as in query text.
+    pub(crate) fn separator_line(&self) -> &'static str {
+        match self {
+            Script::Standard => " ",
+            Script::SqlServer => "\nGO\n",
+            Script::SqlPlus => "\n/\n",
+        }
+    }
+
+    // Line is script specific comment.
+    pub(crate) fn is_comment(&self, line: &str) -> bool {
+        if *self != Script::SqlPlus {
+            return false;
+        }
+        let line = line.trim_start();
+        let word = line
+            .find(char::is_whitespace)
+            .map_or(line, |idx| &line[..idx]);
+        word.eq_ignore_ascii_case("rem") || word.eq_ignore_ascii_case("remark")
+    }
+
+    // Line without comment and whitespaces is separator.
+    pub(crate) fn is_separator(&self, line: &str) -> bool {
+        self.separator()
+            .is_some_and(|separator| line.eq_ignore_ascii_case(separator))
+    }
+}
+
+/// Order of returned queries, see [ParseOptions::order].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Order {
+    /// Order of definition in source text.
+    #[default]
+    Definition,
+    /// Sorted by tag.
+    Alphabetical,
+    /// Grouped by namespace (tag part before first `.`), namespaces sorted, queries in namespace
+    /// in definition order. Tags without `.` are first.
+    Namespace,
+}
+
+impl Sort {
+    pub(crate) fn apply<V>(&self, queries: &mut Map<V>) {
+        fn namespace(tag: &str) -> &str {
+            tag.find('.').map_or("", |idx| &tag[..idx])
+        }
+
+        match self {
+            Sort::Definition | Sort::Order(Order::Definition) => {}
+            Sort::Order(Order::Alphabetical) => queries.sort_keys(),
+            Sort::Order(Order::Namespace) => {
+                queries.sort_by(|a, _, b, _| namespace(a).cmp(namespace(b)))
+            }
+            Sort::Custom(Hook(f)) => queries.sort_by(|a, _, b, _| f(a, b)),
+        }
+    }
+}
+
+/// Line endings in queries with [preserve_newlines](ParseOptions::preserve_newlines).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LineEnding {
+    /// Every line ending replaced by `\n`.
+    #[default]
+    Normalize,
+    /// Line endings (`\n`, `\r\n` or `\r`) kept as in source text.
+    Preserve,
+}
+
+/// Whitespace of queries, see [ParseOptions::whitespace].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Whitespace {
+    /// Lines joined by spaces, runs of whitespace outside of quotes replaced by single space.
+    Minify,
+    /// Lines with indentation, empty lines between query lines and line endings as in source
+    /// text.
+    Preserve,
+    /// Runs of whitespace outside of quotes replaced by single space, lines joined by `\n`.
+    Normalize,
+}
+
+impl ParseOptions {
+    /// Create options with default values, same as used by [parse](crate::parse).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enforce one statement terminated by semicolon in every tag (default: `false`).
+    ///
+    /// On violation [ParseError::EmptyQuery], [ParseError::MultipleStatements] or
+    /// [ParseError::MissingSemicolon] is returned. `-- returns:` comment under tag which is not
+    /// `name:type, ...` or `-- test:` comment which is not valid test returns
+    /// [ParseError::InvalidAnnotation], otherwise they are ignored.
+    pub fn strict(mut self, yes: bool) -> Self {
+        self.strict = yes;
+        self
+    }
+
+    /// Remove terminating semicolon from every query (default: `false`).
+    ///
+    /// Semicolons inside query are not changed, with [keep_comments](Self::keep_comments) semicolon
+    /// before trailing comment is removed. Applied after [strict](Self::strict) checks.
+    pub fn strip_semicolons(mut self, yes: bool) -> Self {
+        self.strip_semicolons = yes;
+        self
+    }
+
+    /// Join query lines with line endings instead of spaces (default: `false`).
+    pub fn preserve_newlines(mut self, yes: bool) -> Self {
+        self.preserve_newlines = yes;
+        self
+    }
+
+    /// Line endings used with [preserve_newlines](Self::preserve_newlines) (default:
+    /// [LineEnding::Normalize]).
+    pub fn line_endings(mut self, line_endings: LineEnding) -> Self {
+        self.line_endings = line_endings;
+        self
+    }
+
+    /// Whitespace mode of queries, overrides [preserve_newlines](Self::preserve_newlines) and
+    /// [line_endings](Self::line_endings) (default: not set, lines are trimmed).
+    ///
+    /// ```
+    /// use rsyesql::{ParseOptions, Whitespace};
+    ///
+    /// let text = "-- name: x\nSELECT  a,\n    b\n\nFROM t;";
+    /// let parse = |mode| ParseOptions::new().whitespace(mode).parse(text).unwrap();
+    /// assert_eq!(parse(Whitespace::Minify)["x"], "SELECT a, b FROM t;");
+    /// assert_eq!(parse(Whitespace::Normalize)["x"], "SELECT a,\nb\nFROM t;");
+    /// assert_eq!(parse(Whitespace::Preserve)["x"], "SELECT  a,\n    b\n\nFROM t;");
+    /// ```
+    pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.whitespace = Some(whitespace);
+        self
+    }
+
+    /// Rule for tag names (default: [TagNames::Any]).
+    ///
+    /// Tag which does not satisfy rule returns [ParseError::InvalidTag].
+    pub fn tag_names(mut self, tag_names: TagNames) -> Self {
+        self.tag_names = tag_names;
+        self
+    }
+
+    /// Regex for tag lines (default: `^\s*--\s*name\s*:\s*(.*?)\s*$`).
+    ///
+    /// Regex matched against every line, tag is captured by group named `tag` or by first group.
+    /// For example: `^--\s*:name\s+(?P<tag>\S+)` for `-- :name foo` or `^--sql\s+name=(\S+)` for
+    /// `--sql name=foo`.
+    #[cfg(feature = "std")]
+    pub fn tag_marker(mut self, re: Regex) -> Self {
+        self.tag_marker = Some(re);
+        self
+    }
+
+    /// Rewrite every tag while parsing, e.g. `kebab-case` to `snake_case` (default: none).
+    ///
+    /// If different tags are same after transformation [ParseError::TagCollision] is returned.
+    /// [tag_names](Self::tag_names) rule checked against transformed tags.
+    pub fn transform_tags<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.transform_tags = Some(Hook(Arc::new(f)));
+        self
+    }
+
+    /// Prepend `/* rsyesql: tag */` to every query (default: `false`).
+    ///
+    /// With comment tools like `pg_stat_statements`, slow query logs and APM can attribute SQL to
+    /// tag.
+    pub fn tag_comments(mut self, yes: bool) -> Self {
+        self.tag_comments = yes;
+        self
+    }
+
+    /// Parse `key=value` words after tag as [Query::attributes](crate::Query::attributes)
+    /// (default: `false`).
+    ///
+    /// `timeout`, `retries` and `backoff` values are validated, invalid value returns
+    /// [ParseError::InvalidAnnotation]. Without option words are part of tag.
+    ///
+    /// ```
+    /// let text = "-- name: report timeout=5s\nSELECT 1;";
+    /// let queries = rsyesql::ParseOptions::new().attributes(true).parse_detailed(text).unwrap();
+    /// assert_eq!(queries["report"].attribute("timeout"), Some("5s"));
+    /// assert!(rsyesql::parse(text).unwrap().contains_key("report timeout=5s"));
+    /// ```
+    pub fn attributes(mut self, yes: bool) -> Self {
+        self.attributes = yes;
+        self
+    }
+
+    /// Parse `-- tx: begin <name>` ... `-- tx: end` blocks as
+    /// [Query::transaction](crate::Query::transaction) (default: `false`).
+    ///
+    /// Invalid or unbalanced `-- tx:` line returns [ParseError::InvalidAnnotation]. Without option
+    /// `-- tx:` lines are comments.
+    pub fn transactions(mut self, yes: bool) -> Self {
+        self.transactions = yes;
+        self
+    }
+
+    /// Keep `-- ...` comments after query on same line in query text (default: `false`).
+    ///
+    /// Comment lines are removed as before, tag lines are recognized as before. Line with comment
+    /// is followed by `\n` instead of space. Comments are ignored by [strict](Self::strict) checks.
+    pub fn keep_comments(mut self, yes: bool) -> Self {
+        self.keep_comments = yes;
+        self
+    }
+
+    /// Handling of psql meta-command lines (`\set`, `\copy`, `\i`, ...), see [MetaCommands]
+    /// (default: [MetaCommands::Query]).
+    pub fn meta_commands(mut self, meta_commands: MetaCommands) -> Self {
+        self.meta_commands = meta_commands;
+        self
+    }
+
+    /// Conventions of SQL client scripts, see [Script] (default: [Script::Standard]).
+    ///
+    /// ```
+    /// use rsyesql::{ParseOptions, Script};
+    ///
+    /// let queries = ParseOptions::new()
+    ///     .script(Script::SqlServer)
+    ///     .parse_detailed("-- name: x\nCREATE TABLE t (id int)\nGO\nSELECT 1")
+    ///     .unwrap();
+    /// assert_eq!(queries["x"].batches, ["CREATE TABLE t (id int)", "SELECT 1"]);
+    /// ```
+    pub fn script(mut self, script: Script) -> Self {
+        self.script = script;
+        self
+    }
+
+    /// Comment and string syntax of queries (default: [Language::sql]), for query languages
+    /// other than SQL: tag lines are `// name: x` with [Language::cypher].
+    ///
+    /// Custom tag marker is used instead of line comment marker for tags.
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Collect queries before first tag under `key` instead of [ParseError::QueryWithoutTag]
+    /// (default: none).
+    ///
+    /// Useful for dumps which start with `SET` / `BEGIN` boilerplate.
+    ///
+    /// ```
+    /// let queries = rsyesql::ParseOptions::new()
+    ///     .prelude("_prelude")
+    ///     .parse("SET search_path = app;\n-- name: x\nSELECT 1;")
+    ///     .unwrap();
+    /// assert_eq!(queries.get("_prelude").unwrap(), "SET search_path = app;");
+    /// ```
+    pub fn prelude<K: Into<String>>(mut self, key: K) -> Self {
+        self.prelude = Some(key.into());
+        self
+    }
+
+    /// Set flag for conditional blocks (default: none).
+    ///
+    /// Lines between `-- if: key=value` and `-- endif` are parsed only if flag `key` is set to
+    /// `value`, `-- if: key` requires flag with any value. Blocks can be nested, unbalanced block
+    /// returns [ParseError::UnbalancedCondition]. Without flags `-- if:` and `-- endif` lines are
+    /// comments.
+    ///
+    /// ```
+    /// let text = "-- if: env=test\n-- name: seed\nINSERT INTO users VALUES (1);\n-- endif\n-- name: x\nSELECT 1;";
+    /// let queries = rsyesql::ParseOptions::new().flag("env", "test").parse(text).unwrap();
+    /// assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["seed", "x"]);
+    /// let queries = rsyesql::ParseOptions::new().flag("env", "prod").parse(text).unwrap();
+    /// assert_eq!(queries.keys().collect::<Vec<_>>(), vec!["x"]);
+    /// ```
+    pub fn flag<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.flags.push((key.into(), value.into()));
+        self
+    }
+
+    /// Replace `${NAME}` in queries with values of environment variables (default: `false`).
+    ///
+    /// Unset variable returns [ParseError::UnsetVariable], see
+    /// [substitute_vars](Self::substitute_vars).
+    #[cfg(feature = "std")]
+    pub fn substitute_env(mut self, yes: bool) -> Self {
+        self.variables = if yes { Some(Variables::Env) } else { None };
+        self
+    }
+
+    /// Replace `${NAME}` in queries with values from `vars` (default: none).
+    ///
+    /// `NAME` consists of ASCII letters, digits and `_`, and does not start with digit. Unset
+    /// variable returns [ParseError::UnsetVariable].
+    ///
+    /// ```
+    /// let queries = rsyesql::ParseOptions::new()
+    ///     .substitute_vars(vec![("SCHEMA", "app")])
+    ///     .parse("-- name: x\nSELECT * FROM ${SCHEMA}.users;")
+    ///     .unwrap();
+    /// assert_eq!(queries.get("x").unwrap(), "SELECT * FROM app.users;");
+    /// ```
+    pub fn substitute_vars<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = vars
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self.variables = Some(Variables::Map(vars));
+        self
+    }
+
+    /// Maximum size of input text in bytes (default: none).
+    ///
+    /// Limits protect against hostile or broken input from untrusted sources, on violation
+    /// [ParseError::LimitExceeded] is returned.
+    pub fn max_input_size(mut self, max: usize) -> Self {
+        self.max_input_size = Some(max);
+        self
+    }
+
+    /// Maximum number of tags (default: none), see [max_input_size](Self::max_input_size).
+    pub fn max_tags(mut self, max: usize) -> Self {
+        self.max_tags = Some(max);
+        self
+    }
+
+    /// Maximum length of query in bytes (default: none), see
+    /// [max_input_size](Self::max_input_size).
+    pub fn max_query_len(mut self, max: usize) -> Self {
+        self.max_query_len = Some(max);
+        self
+    }
+
+    /// Rewrite every query after parsing, closure receives tag and query (default: none).
+    ///
+    /// Called last, after all other options are applied. Useful for setting `search_path`,
+    /// rewriting table prefixes or appending `LIMIT` guards.
+    ///
+    /// ```
+    /// let queries = rsyesql::ParseOptions::new()
+    ///     .transform_queries(|_tag, query| query.replace("{prefix}", "tenant1_"))
+    ///     .parse("-- name: x\nSELECT * FROM {prefix}users;")
+    ///     .unwrap();
+    /// assert_eq!(queries.get("x").unwrap(), "SELECT * FROM tenant1_users;");
+    /// ```
+    pub fn transform_queries<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, String) -> String + Send + Sync + 'static,
+    {
+        self.transform_queries = Some(Hook(Arc::new(f)));
+        self
+    }
+
+    /// Order of returned queries (default: [Order::Definition]).
+    ///
+    /// ```
+    /// use rsyesql::Order;
+    ///
+    /// let queries = rsyesql::ParseOptions::new()
+    ///     .order(Order::Namespace)
+    ///     .parse("-- name: users.b\n1;\n-- name: posts.a\n2;\n-- name: users.a\n3;")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     queries.keys().collect::<Vec<_>>(),
+    ///     vec!["posts.a", "users.b", "users.a"]
+    /// );
+    /// ```
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = Sort::Order(order);
+        self
+    }
+
+    /// Sort returned queries by tags with comparator, replaces [order](Self::order).
+    ///
+    /// Sort is stable, equal tags keep definition order.
+    pub fn sort_by<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &str) -> Ordering + Send + Sync + 'static,
+    {
+        self.order = Sort::Custom(Hook(Arc::new(f)));
+        self
+    }
+
+    /// Parse SQL queries as text with these options, see [parse](crate::parse).
+    pub fn parse<S: AsRef<str>>(&self, text: S) -> Result<Map<String>, ParseError> {
+        self.parse_detailed(text).map(|queries| {
+            queries
+                .into_iter()
+                .map(|(tag, query)| (tag, query.text))
+                .collect()
+        })
+    }
+
+    /// Parse SQL queries as text with these options to map with hasher `H`, see
+    /// [parse_with_hasher](crate::parse_with_hasher).
+    pub fn parse_with_hasher<H, S>(
+        &self,
+        text: S,
+    ) -> Result<IndexMap<String, String, H>, ParseError>
+    where
+        H: BuildHasher + Default,
+        S: AsRef<str>,
+    {
+        self.parse_detailed(text).map(|queries| {
+            queries
+                .into_iter()
+                .map(|(tag, query)| (tag, query.text))
+                .collect()
+        })
+    }
+
+    /// Parse SQL queries as text with these options to shared strings, see
+    /// [parse_shared](crate::parse_shared).
+    #[cfg(feature = "std")]
+    pub fn parse_shared<S: AsRef<str>>(
+        &self,
+        text: S,
+    ) -> Result<IndexMap<Arc<str>, Arc<str>>, ParseError> {
+        self.parse_detailed(text).map(|queries| {
+            queries
+                .into_iter()
+                .map(|(tag, query)| (tag.into(), query.text.into()))
+                .collect()
+        })
+    }
+
+    /// Parse static SQL queries with these options, see [parse_static](crate::parse_static).
+    #[cfg(feature = "std")]
+    pub fn parse_static(
+        &self,
+        text: &'static str,
+    ) -> Result<IndexMap<Cow<'static, str>, Cow<'static, str>>, ParseError> {
+        let source = text.strip_prefix('\u{feff}').unwrap_or(text);
+        self.parse_detailed(text).map(|queries| {
+            queries
+                .into_iter()
+                .map(|(tag, query)| {
+                    let start = query.tag_pos;
+                    let tag = static_str(source, start..start + tag.len(), tag);
+                    let end = start + source[start..].find('\n').unwrap_or(0) + 1 + query.raw.len();
+                    (tag, static_str(source, start..end, query.text))
+                })
+                .collect()
+        })
+    }
+
+    /// Parse SQL queries as text with these options, see [parse_detailed](crate::parse_detailed).
+    pub fn parse_detailed<S: AsRef<str>>(&self, text: S) -> Result<Map<Query>, ParseError> {
+        parse_text(text.as_ref(), self)
+    }
+
+    /// Parse base SQL queries and overrides with these options, see
+    /// [parse_layered](crate::parse_layered).
+    pub fn parse_layered<B: AsRef<str>, O: AsRef<str>>(
+        &self,
+        base: B,
+        overrides: O,
+    ) -> Result<Layered, ParseError> {
+        let base = self.parse_detailed(base)?;
+        let overrides = self.parse_detailed(overrides)?;
+        Ok(Layered::new(base, overrides))
+    }
+
+    /// Parse SQL queries from file with these options, see [parse_named](crate::parse_named).
+    #[cfg(feature = "std")]
+    pub fn parse_named<P: AsRef<Path>, S: AsRef<str>>(
+        &self,
+        file: P,
+        text: S,
+    ) -> Result<Map<Query>, FileError> {
+        let file: Arc<Path> = file.as_ref().into();
+        let mut queries = self.parse_detailed(text).map_err(|err| FileError::Parse {
+            file: file.to_path_buf(),
+            err,
+        })?;
+        for query in queries.values_mut() {
+            query.file = Some(Arc::clone(&file));
+        }
+        Ok(queries)
+    }
+
+    /// Parse SQL queries from many files with these options, see [parse_many](crate::parse_many).
+    #[cfg(feature = "std")]
+    pub fn parse_many<I, P, S>(&self, files: I) -> Result<Map<Query>, FileError>
+    where
+        I: IntoIterator<Item = (P, S)>,
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        let mut queries: Map<Query> = Map::new();
+        for (file, text) in files {
+            for (tag, query) in self.parse_named(file, text)? {
+                if let Some(other) = queries.get(&tag) {
+                    return Err(FileError::DuplicateTag {
+                        tag,
+                        file: query.file.as_deref().unwrap().to_path_buf(),
+                        line: query.line,
+                        other_file: other.file.as_deref().unwrap().to_path_buf(),
+                        other_line: other.line,
+                    });
+                }
+                queries.insert(tag, query);
+            }
+        }
+        Ok(queries)
+    }
+
+    /// Parse SQL queries as bytes with these options, see [parse_bytes](crate::parse_bytes).
+    #[cfg(feature = "encoding")]
+    pub fn parse_bytes<B: AsRef<[u8]>>(&self, bytes: B) -> Result<Map<String>, ParseError> {
+        self.parse(crate::encoding::decode(bytes.as_ref()))
+    }
+}
+
+/// Limit in [ParseError::LimitExceeded].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    /// [ParseOptions::max_input_size].
+    InputSize,
+    /// [ParseOptions::max_tags].
+    Tags,
+    /// [ParseOptions::max_query_len].
+    QueryLength,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::InputSize => "Input size",
+            Limit::Tags => "Tags",
+            Limit::QueryLength => "Query length",
+        })
+    }
+}
+
+/// Rule for tag names, see [ParseOptions::tag_names].
+#[derive(Debug, Clone, Default)]
+pub enum TagNames {
+    /// Any tag is valid, including empty and with whitespaces.
+    #[default]
+    Any,
+    /// Tag is not empty and does not contain whitespaces.
+    NoWhitespace,
+    /// Tag is valid Rust identifier (not keyword), useful for code generation.
+    RustIdent,
+    /// Tag matches regex.
+    #[cfg(feature = "std")]
+    Pattern(Regex),
+}
+
+impl TagNames {
+    /// Check tag against rule.
+    pub fn is_valid(&self, tag: &str) -> bool {
+        match self {
+            TagNames::Any => true,
+            TagNames::NoWhitespace => !tag.is_empty() && !tag.contains(char::is_whitespace),
+            TagNames::RustIdent => is_rust_ident(tag),
+            #[cfg(feature = "std")]
+            TagNames::Pattern(re) => re.is_match(tag),
+        }
+    }
+}
+
+fn is_rust_ident(tag: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ];
+
+    let mut chars = tag.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_') && tag != "_" && !KEYWORDS.contains(&tag)
+}
+
+// Slice of `source` range equal to `text`, or owned `text`.
+#[cfg(feature = "std")]
+fn static_str(source: &'static str, range: Range<usize>, text: String) -> Cow<'static, str> {
+    let region = source
+        .get(range.start..range.end.min(source.len()))
+        .unwrap_or(&source[range.start..]);
+    match region.find(text.as_str()) {
+        Some(idx) => Cow::Borrowed(&region[idx..idx + text.len()]),
+        None => Cow::Owned(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_names() {
+        assert!(TagNames::Any.is_valid(""));
+        assert!(TagNames::NoWhitespace.is_valid("select-users.1"));
+        assert!(!TagNames::NoWhitespace.is_valid("start end"));
+        assert!(!TagNames::NoWhitespace.is_valid(""));
+        assert!(TagNames::RustIdent.is_valid("select_users"));
+        assert!(!TagNames::RustIdent.is_valid("select-users"));
+        assert!(!TagNames::RustIdent.is_valid("1select"));
+        assert!(!TagNames::RustIdent.is_valid("type"));
+    }
+
+    #[test]
+    fn order_queries() {
+        let text = "--name: b.x\n1;\n--name: c\n2;\n--name: a\n3;\n--name: b.a\n4;";
+        let tags = |options: ParseOptions| {
+            let queries = options.parse(text).unwrap();
+            queries.keys().cloned().collect::<Vec<_>>()
+        };
+        assert_eq!(tags(ParseOptions::new()), ["b.x", "c", "a", "b.a"]);
+        assert_eq!(
+            tags(ParseOptions::new().order(Order::Alphabetical)),
+            ["a", "b.a", "b.x", "c"]
+        );
+        assert_eq!(
+            tags(ParseOptions::new().order(Order::Namespace)),
+            ["c", "a", "b.x", "b.a"]
+        );
+        assert_eq!(
+            tags(ParseOptions::new().sort_by(|a, b| a.len().cmp(&b.len()))),
+            ["c", "a", "b.x", "b.a"]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tag_names_pattern() {
+        let re = Regex::new("^[a-z]+$").unwrap();
+        assert!(TagNames::Pattern(re.clone()).is_valid("ab"));
+        assert!(!TagNames::Pattern(re).is_valid("aB"));
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::BTreeSet;
+
+/// Placeholder style for positional parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placeholder {
+    /// `?`, every occurrence of named parameter is separate positional parameter (MySQL, SQLite).
+    Question,
+    /// `$1`, `$2`, ... occurrences of same named parameter use same number (PostgreSQL).
+    Dollar,
+}
+
+/// Query with named parameters replaced by positional placeholders, see [positional].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionalQuery {
+    /// Query text with positional placeholders.
+    pub text: String,
+    /// Names of parameters in order of placeholders.
+    pub params: Vec<String>,
+}
+
+/// Replace named parameters (`:name`) with positional placeholders.
+///
+/// Parameters in quoted strings and identifiers, comments and casts (`::type`) are not replaced.
+///
+/// ```
+/// use rsyesql::{positional, Placeholder};
+///
+/// let query = positional("SELECT * FROM users WHERE id = :id OR parent = :id", Placeholder::Dollar);
+/// assert_eq!(query.text, "SELECT * FROM users WHERE id = $1 OR parent = $1");
+/// assert_eq!(query.params, vec!["id"]);
+/// ```
+pub fn positional(query: &str, style: Placeholder) -> PositionalQuery {
+    let mut text = String::with_capacity(query.len());
+    let mut params: Vec<String> = vec![];
+    let mut quote: Option<char> = None;
+    let mut prev = None;
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '\'' | '"' | '`') => quote = Some(c),
+            None if (c == '-' && query[idx + 1..].starts_with('-'))
+                || (c == '/' && query[idx + 1..].starts_with('*')) =>
+            {
+                let end = match c {
+                    '-' => query[idx..].find('\n').map_or(query.len(), |n| idx + n),
+                    _ => query[idx + 2..]
+                        .find("*/")
+                        .map_or(query.len(), |n| idx + n + 4),
+                };
+                while chars.next_if(|&(idx, _)| idx < end).is_some() {}
+                text.push_str(&query[idx..end]);
+                prev = query[idx..end].chars().last();
+                continue;
+            }
+            None if c == ':' && prev != Some(':') => {
+                let name_len = query[idx + 1..]
+                    .chars()
+                    .take_while(|&c| c.is_alphanumeric() || c == '_')
+                    .map(char::len_utf8)
+                    .sum::<usize>();
+                let name = &query[idx + 1..idx + 1 + name_len];
+                if name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                    match style {
+                        Placeholder::Question => {
+                            params.push(name.to_owned());
+                            text.push('?');
+                        }
+                        Placeholder::Dollar => {
+                            let pos = match params.iter().position(|p| p == name) {
+                                Some(pos) => pos,
+                                None => {
+                                    params.push(name.to_owned());
+                                    params.len() - 1
+                                }
+                            };
+                            text.push('$');
+                            text.push_str(&(pos + 1).to_string());
+                        }
+                    }
+                    for _ in name.chars() {
+                        chars.next();
+                    }
+                    prev = name.chars().last();
+                    continue;
+                }
+            }
+            None => {}
+        }
+        text.push(c);
+        prev = Some(c);
+    }
+
+    PositionalQuery { text, params }
+}
+
+quick_error! {
+    /// The error type for [check_placeholders].
+    #[derive(Debug, PartialEq)]
+    pub enum PlaceholderError {
+        /// `$n` is not used, but greater numbers are.
+        Gap { missing: usize } {
+            display("Placeholder ${} is not used, but greater numbers are", missing)
+        }
+        /// `$n` is used more than once, returned by [check_unique_placeholders].
+        Duplicate { number: usize } {
+            display("Placeholder ${} is used more than once", number)
+        }
+        /// Query uses both `$n` and `?` placeholders.
+        Mixed {
+            display("Query mixes $n and ? placeholders")
+        }
+        /// Number of bind values is not same as number of placeholders.
+        Count { expected: usize, actual: usize } {
+            display("Query expects {} parameters, got {}", expected, actual)
+        }
+    }
+}
+
+/// Positional placeholders used in query, see [placeholders].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Placeholders {
+    /// Numbers of `$n` placeholders, sorted and without duplicates (same number can be used
+    /// multiple times).
+    pub dollar: BTreeSet<usize>,
+    /// Numbers of `$n` placeholders used more than once.
+    pub repeated: BTreeSet<usize>,
+    /// Number of `?` placeholders.
+    pub question: usize,
+}
+
+impl Placeholders {
+    /// Number of bind values expected by query: greatest `$n` or number of `?`.
+    pub fn count(&self) -> usize {
+        self.dollar.iter().next_back().copied().unwrap_or(0) + self.question
+    }
+}
+
+/// Collect positional placeholders (`$1`, `?`) from query.
+///
+/// Placeholders in quoted strings, identifiers and comments are ignored.
+///
+/// ```
+/// let placeholders = rsyesql::placeholders("SELECT * FROM t WHERE a = $2 OR b = $1 OR c = $2");
+/// assert_eq!(placeholders.dollar.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+/// ```
+pub fn placeholders(query: &str) -> Placeholders {
+    let mut placeholders = Placeholders::default();
+    let mut chars = query.char_indices().peekable();
+    let mut prev = None;
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                for (_, n) in chars.by_ref() {
+                    if n == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.next_if(|&(_, c)| c == '-').is_some() => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '/' if chars.next_if(|&(_, c)| c == '*').is_some() => {
+                let mut star = false;
+                for (_, c) in chars.by_ref() {
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+            }
+            '?' => placeholders.question += 1,
+            '$' if !prev.is_some_and(|c: char| c.is_alphanumeric() || c == '_') => {
+                let digits = query[idx + 1..]
+                    .chars()
+                    .take_while(char::is_ascii_digit)
+                    .count();
+                if let Ok(n) = query[idx + 1..idx + 1 + digits].parse() {
+                    if !placeholders.dollar.insert(n) {
+                        placeholders.repeated.insert(n);
+                    }
+                    for _ in 0..digits {
+                        chars.next();
+                    }
+                }
+            }
+            _ => {}
+        }
+        prev = Some(c);
+    }
+
+    placeholders
+}
+
+/// Check that placeholders are consistent and match number of bind values.
+///
+/// `$n` numbers should be `$1..$n` without gaps, `$n` and `?` can not be mixed. Same `$n` can be
+/// used multiple times (same value bound in PostgreSQL), see [check_unique_placeholders]. Returns
+/// number of placeholders.
+///
+/// ```
+/// use rsyesql::{check_placeholders, PlaceholderError};
+///
+/// let query = "SELECT * FROM t WHERE a = $1 AND b = $3";
+/// assert_eq!(check_placeholders(query, 2), Err(PlaceholderError::Gap { missing: 2 }));
+/// ```
+pub fn check_placeholders(query: &str, params: usize) -> Result<usize, PlaceholderError> {
+    let placeholders = placeholders(query);
+    if !placeholders.dollar.is_empty() && placeholders.question > 0 {
+        return Err(PlaceholderError::Mixed);
+    }
+    if let Some(missing) = (1..)
+        .zip(&placeholders.dollar)
+        .find(|&(n, &used)| n != used)
+    {
+        return Err(PlaceholderError::Gap { missing: missing.0 });
+    }
+    let expected = placeholders.count();
+    if expected != params {
+        return Err(PlaceholderError::Count {
+            expected,
+            actual: params,
+        });
+    }
+    Ok(expected)
+}
+
+/// Same as [check_placeholders], but every `$n` is used only once.
+///
+/// ```
+/// use rsyesql::{check_unique_placeholders, PlaceholderError};
+///
+/// let query = "SELECT * FROM t WHERE a = $1 OR b = $1";
+/// assert_eq!(
+///     check_unique_placeholders(query, 1),
+///     Err(PlaceholderError::Duplicate { number: 1 })
+/// );
+/// ```
+pub fn check_unique_placeholders(query: &str, params: usize) -> Result<usize, PlaceholderError> {
+    let expected = check_placeholders(query, params)?;
+    match placeholders(query).repeated.into_iter().next() {
+        Some(number) => Err(PlaceholderError::Duplicate { number }),
+        None => Ok(expected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positional_question() {
+        let query = positional(
+            "SELECT ':x', \"a:b\" FROM t WHERE a = :a AND b = :b_1 OR a = :a",
+            Placeholder::Question,
+        );
+        assert_eq!(
+            query.text,
+            "SELECT ':x', \"a:b\" FROM t WHERE a = ? AND b = ? OR a = ?"
+        );
+        assert_eq!(query.params, vec!["a", "b_1", "a"]);
+    }
+
+    #[test]
+    fn positional_dollar() {
+        let query = positional("SELECT :a::text, :b, :a, x::int", Placeholder::Dollar);
+        assert_eq!(query.text, "SELECT $1::text, $2, $1, x::int");
+        assert_eq!(query.params, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn positional_skips_comments() {
+        let query = positional(
+            "SELECT :a -- :b\n/* :c */ FROM t WHERE x = :d /* :e",
+            Placeholder::Question,
+        );
+        assert_eq!(
+            query.text,
+            "SELECT ? -- :b\n/* :c */ FROM t WHERE x = ? /* :e"
+        );
+        assert_eq!(query.params, vec!["a", "d"]);
+    }
+
+    #[test]
+    fn positional_not_param() {
+        let query = positional("SELECT a :1, b: c, :", Placeholder::Question);
+        assert_eq!(query.text, "SELECT a :1, b: c, :");
+        assert!(query.params.is_empty());
+    }
+
+    #[test]
+    fn placeholders_ignored_in_strings_and_comments() {
+        let query =
+            "SELECT '$3 ?', \"a?\", x$1, $$ -- $4 ?\n/* $5 */ FROM t WHERE a = $2 AND b = $10";
+        let placeholders = placeholders(query);
+        assert_eq!(
+            placeholders.dollar.into_iter().collect::<Vec<_>>(),
+            vec![2, 10]
+        );
+        assert_eq!(placeholders.question, 0);
+        assert_eq!(super::placeholders("a = ? AND b = ?").count(), 2);
+    }
+
+    #[test]
+    fn check_placeholders_errors() {
+        assert_eq!(check_placeholders("a = $1 OR b = $2 OR c = $1", 2), Ok(2));
+        assert_eq!(
+            check_placeholders("a = $2", 2),
+            Err(PlaceholderError::Gap { missing: 1 })
+        );
+        assert_eq!(
+            check_placeholders("a = $1 AND b = ?", 2),
+            Err(PlaceholderError::Mixed)
+        );
+        assert_eq!(
+            check_placeholders("a = $1 AND b = $2 AND c = $3", 2),
+            Err(PlaceholderError::Count {
+                expected: 3,
+                actual: 2
+            })
+        );
+        assert_eq!(check_unique_placeholders("a = $2 OR b = $1", 2), Ok(2));
+        assert_eq!(
+            check_unique_placeholders("a = $1 OR b = $2 OR c = $1", 2),
+            Err(PlaceholderError::Duplicate { number: 1 })
+        );
+    }
+}
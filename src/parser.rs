@@ -0,0 +1,126 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{parse_text_into, Map, ParseError, ParseOptions, Query};
+
+/// Parser with buffers reused between parses, for frequent parse of same files (watch mode).
+///
+/// ```
+/// let mut parser = rsyesql::Parser::default();
+/// let mut queries = rsyesql::indexmap::IndexMap::new();
+/// parser.parse_into("-- name: a\nSELECT 1;", &mut queries).unwrap();
+/// parser.parse_into("-- name: a\nSELECT 2;", &mut queries).unwrap();
+/// assert_eq!(queries["a"], "SELECT 2;");
+/// ```
+#[derive(Debug, Default)]
+pub struct Parser {
+    options: ParseOptions,
+    queries: Map<Query>,
+    spare: Vec<Query>,
+    texts: Vec<String>,
+}
+
+impl Parser {
+    /// Parser with options.
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Options of parser.
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Parse SQL queries as text, see [ParseOptions::parse_detailed]. Returned queries are valid
+    /// until next parse.
+    pub fn parse_detailed<S: AsRef<str>>(&mut self, text: S) -> Result<&Map<Query>, ParseError> {
+        parse_text_into(
+            text.as_ref(),
+            &self.options,
+            &mut self.queries,
+            &mut self.spare,
+        )?;
+        Ok(&self.queries)
+    }
+
+    /// Parse SQL queries as text to `queries`, see [ParseOptions::parse_into]. Previous query
+    /// strings from `queries` are reused by next parses.
+    pub fn parse_into<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        queries: &mut Map<String>,
+    ) -> Result<(), ParseError> {
+        self.texts.extend(queries.drain(..).map(|(_, text)| text));
+        parse_text_into(
+            text.as_ref(),
+            &self.options,
+            &mut self.queries,
+            &mut self.spare,
+        )?;
+        for (tag, query) in self.queries.iter_mut() {
+            // Text moved to result, spare buffer is used by next parse.
+            let mut text = self.texts.pop().unwrap_or_default();
+            text.clear();
+            core::mem::swap(&mut text, &mut query.text);
+            queries.insert(tag.clone(), text);
+        }
+        Ok(())
+    }
+}
+
+impl ParseOptions {
+    /// Parse SQL queries as text with these options to `queries`, previous content is removed and
+    /// map allocation is reused. On error `queries` are empty.
+    ///
+    /// See [Parser] to reuse query buffers too.
+    pub fn parse_into<S: AsRef<str>>(
+        &self,
+        text: S,
+        queries: &mut Map<String>,
+    ) -> Result<(), ParseError> {
+        queries.clear();
+        let parsed = self.parse_detailed(text)?;
+        queries.extend(parsed.into_iter().map(|(tag, query)| (tag, query.text)));
+        Ok(())
+    }
+}
+
+/// Parse SQL queries as text to `queries` with default options, see [ParseOptions::parse_into].
+pub fn parse_into<S: AsRef<str>>(text: S, queries: &mut Map<String>) -> Result<(), ParseError> {
+    ParseOptions::new().parse_into(text, queries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_detailed;
+
+    #[test]
+    fn reuse_buffers() {
+        let mut parser = Parser::new(ParseOptions::new().strip_semicolons(true));
+        let mut queries = Map::default();
+        parser
+            .parse_into("-- name: a\nSELECT 1;\n-- name: b\nSELECT 2;", &mut queries)
+            .unwrap();
+        assert_eq!(queries.keys().collect::<Vec<_>>(), ["a", "b"]);
+        parser
+            .parse_into("-- name: c\nSELECT 3;", &mut queries)
+            .unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries["c"], "SELECT 3");
+
+        let text = "-- name: x\nSELECT 1;\n-- name: y\nSELECT\n  2;";
+        let mut parser = Parser::default();
+        parser.parse_detailed("-- name: z\nSELECT 0;").unwrap();
+        assert_eq!(
+            parser.parse_detailed(text).unwrap(),
+            &parse_detailed(text).unwrap()
+        );
+        assert!(parser.parse_detailed("-- name: x\n-- name: y").is_err());
+        assert!(parser.parse_into("-- name: a", &mut queries).is_ok());
+        assert!(queries.is_empty());
+    }
+}
@@ -0,0 +1,528 @@
+//! [tokio-postgres](https://docs.rs/tokio-postgres/) integration.
+//!
+//! ```ignore
+//! let queries = rsyesql::parse(include_str!("./queries.sql"))?;
+//! let prepared = rsyesql::postgres::PreparedQueries::prepare(client, &queries).await?;
+//! let rows = prepared.query("select_user", &[&user_id]).await?;
+//! ```
+
+use std::future::Future;
+
+use bytes::BytesMut;
+use indexmap::IndexMap;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::{to_sql_checked, Format, IsNull, ToSql, Type};
+use tokio_postgres::{Client, Row, Statement};
+
+use crate::{
+    is_explainable, positional, transactions, Fixture, Placeholder, Query, QueryExecutor,
+    Transaction,
+};
+
+quick_error! {
+    /// The error type for [PreparedQueries].
+    #[derive(Debug)]
+    pub enum Error {
+        /// Query with tag is not defined.
+        UnknownTag(tag: String) {
+            display(r#"Unknown tag: "{}""#, tag)
+        }
+        /// Query preparation failed.
+        Prepare { tag: String, err: tokio_postgres::Error } {
+            display(r#"Failed to prepare "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Query execution failed.
+        Query { tag: String, err: tokio_postgres::Error } {
+            display(r#"Failed to execute "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// `EXPLAIN` of query failed in [explain_all].
+        Explain { tag: String, err: tokio_postgres::Error } {
+            display(r#"Failed to explain "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Test from `-- test:` line failed in [run_fixtures].
+        Fixture { tag: String, line: usize, message: String } {
+            display(r#"Test of "{}" (line: {}) failed: {}"#, tag, line, message)
+        }
+        /// Query execution failed in [execute_all] or [execute_transactions].
+        ExecuteAll { tag: String, line: usize, err: tokio_postgres::Error } {
+            display(r#"Failed to execute "{}" (line: {}): {}"#, tag, line, err)
+            cause(err)
+        }
+        /// Transaction begin or commit failed.
+        Transaction(err: tokio_postgres::Error) {
+            display("Transaction failed: {}", err)
+            cause(err)
+        }
+    }
+}
+
+/// Client with prepared statement for every query.
+#[derive(Debug)]
+pub struct PreparedQueries {
+    client: Client,
+    statements: IndexMap<String, Statement>,
+}
+
+impl PreparedQueries {
+    /// Prepare every query, in definition order.
+    pub async fn prepare<I, T, Q>(client: Client, queries: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let mut statements = IndexMap::new();
+        for (tag, query) in queries {
+            let tag = tag.as_ref();
+            let statement = client
+                .prepare(query.as_ref())
+                .await
+                .map_err(|err| Error::Prepare {
+                    tag: tag.to_owned(),
+                    err,
+                })?;
+            statements.insert(tag.to_owned(), statement);
+        }
+
+        Ok(Self { client, statements })
+    }
+
+    /// Prepared statement by tag.
+    pub fn statement(&self, tag: &str) -> Result<&Statement, Error> {
+        self.statements
+            .get(tag)
+            .ok_or_else(|| Error::UnknownTag(tag.to_owned()))
+    }
+
+    /// Tags of prepared statements in definition order.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.statements.keys().map(String::as_str)
+    }
+
+    /// Execute statement, returns number of modified rows.
+    pub async fn execute(&self, tag: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        let statement = self.statement(tag)?;
+        self.client
+            .execute(statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Execute statement, returns resulting rows.
+    pub async fn query(
+        &self,
+        tag: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        let statement = self.statement(tag)?;
+        self.client
+            .query(statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Execute statement, returns exactly one row.
+    pub async fn query_one(&self, tag: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error> {
+        let statement = self.statement(tag)?;
+        self.client
+            .query_one(statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Execute statement, returns at most one row.
+    pub async fn query_opt(
+        &self,
+        tag: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        let statement = self.statement(tag)?;
+        self.client
+            .query_opt(statement, params)
+            .await
+            .map_err(|err| query_error(tag, err))
+    }
+
+    /// Client used for preparation.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Mutable client, for example for transactions.
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    /// Take client back.
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+}
+
+impl QueryExecutor for PreparedQueries {
+    type Params<'p> = &'p [&'p (dyn ToSql + Sync)];
+    type Row = Row;
+    type Error = Error;
+
+    fn execute(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<u64, Error>> {
+        PreparedQueries::execute(self, tag, params)
+    }
+
+    fn query_many(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Vec<Row>, Error>> {
+        self.query(tag, params)
+    }
+
+    fn query_one(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Option<Row>, Error>> {
+        self.query_opt(tag, params)
+    }
+}
+
+/// Execute every query in definition order inside transaction, for example for database scheme
+/// creation.
+///
+/// Every query can contain multiple statements. Queries with [Query::timeout] are executed with
+/// [statement_timeout]. Execution stops on first failed query, transaction is rolled back.
+pub async fn execute_all(
+    client: &mut Client,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error> {
+    let tx = client.transaction().await.map_err(Error::Transaction)?;
+    for (tag, query) in queries {
+        tx.batch_execute(&with_timeout(query))
+            .await
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.clone(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().await.map_err(Error::Transaction)
+}
+
+/// Execute queries in definition order, every group of [transactions] inside own transaction.
+///
+/// Queries of `-- tx: begin <name>` block are committed together, every query outside of block is
+/// committed alone. Execution stops on first failed query, only transaction of failed query is
+/// rolled back.
+///
+/// On serialization failure or deadlock group is executed again by [Transaction::retry].
+pub async fn execute_transactions(
+    client: &mut Client,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error> {
+    for group in transactions(queries) {
+        let mut attempt = 0;
+        loop {
+            match (execute_group(client, &group).await, group.retry()) {
+                (Err(err), Some(retry)) if attempt < retry.retries && is_transient(&err) => {
+                    tokio::time::sleep(retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                (result, _) => break result?,
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn execute_group(client: &mut Client, group: &Transaction<'_>) -> Result<(), Error> {
+    let tx = client.transaction().await.map_err(Error::Transaction)?;
+    for &(tag, query) in &group.queries {
+        tx.batch_execute(&with_timeout(query))
+            .await
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.to_owned(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().await.map_err(Error::Transaction)
+}
+
+// Serialization failure or deadlock, transaction can be executed again.
+fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::ExecuteAll { err, .. } | Error::Transaction(err) => is_transient_code(err.code()),
+        _ => false,
+    }
+}
+
+fn is_transient_code(code: Option<&SqlState>) -> bool {
+    matches!(
+        code,
+        Some(&SqlState::T_R_SERIALIZATION_FAILURE | &SqlState::T_R_DEADLOCK_DETECTED)
+    )
+}
+
+// Query text with `statement_timeout` set before and reset after query.
+fn with_timeout(query: &Query) -> String {
+    match statement_timeout(query) {
+        Some(timeout) => format!(
+            "{};\n{}\n;SET LOCAL statement_timeout = DEFAULT;",
+            timeout, query.text
+        ),
+        None => query.text.clone(),
+    }
+}
+
+/// `SET LOCAL statement_timeout` statement for query with [Query::timeout], should be executed in
+/// transaction before query.
+///
+/// ```
+/// let queries = rsyesql::ParseOptions::new()
+///     .attributes(true)
+///     .parse_detailed("-- name: report timeout=5s\nSELECT 1;")
+///     .unwrap();
+/// assert_eq!(
+///     rsyesql::postgres::statement_timeout(&queries["report"]).as_deref(),
+///     Some("SET LOCAL statement_timeout = 5000")
+/// );
+/// ```
+pub fn statement_timeout(query: &Query) -> Option<String> {
+    query
+        .timeout()
+        .map(|timeout| format!("SET LOCAL statement_timeout = {}", timeout.as_millis()))
+}
+
+/// `EXPLAIN` of every [is_explainable] query, for example to check queries against schema
+/// without data. Queries are not executed, parameters are bound to `NULL`.
+///
+/// Plan is `QUERY PLAN` column of rows joined by `\n`, queries which are not explainable are
+/// skipped.
+pub async fn explain_all(
+    client: &Client,
+    queries: &IndexMap<String, Query>,
+) -> IndexMap<String, Result<String, Error>> {
+    let mut plans = IndexMap::new();
+    for (tag, query) in queries {
+        if !is_explainable(&query.text) {
+            continue;
+        }
+        let plan = explain(client, &query.text)
+            .await
+            .map_err(|err| Error::Explain {
+                tag: tag.clone(),
+                err,
+            });
+        plans.insert(tag.clone(), plan);
+    }
+    plans
+}
+
+async fn explain(client: &Client, query: &str) -> Result<String, tokio_postgres::Error> {
+    let statement = client.prepare(&format!("EXPLAIN {}", query)).await?;
+    let params = vec![&Null as &(dyn ToSql + Sync); statement.params().len()];
+    let plan = client
+        .query(&statement, &params)
+        .await?
+        .iter()
+        .map(|row| row.get::<_, String>(0))
+        .collect::<Vec<_>>();
+    Ok(plan.join("\n"))
+}
+
+// `NULL` of any type.
+#[derive(Debug)]
+struct Null;
+
+impl ToSql for Null {
+    fn to_sql(
+        &self,
+        _: &Type,
+        _: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(IsNull::Yes)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// Run [Query::fixtures] against test database, returns failed tests.
+///
+/// Every test is executed in own transaction, which is rolled back. Named parameters are bound by
+/// [positional], numbers and booleans are converted to parameter type, other values are sent as
+/// text and parsed by server (`null` is `NULL`, quotes `'...'` are removed).
+///
+/// ```ignore
+/// let failures = rsyesql::postgres::run_fixtures(&mut client, &queries).await;
+/// assert!(failures.is_empty(), "{:?}", failures);
+/// ```
+pub async fn run_fixtures(client: &mut Client, queries: &IndexMap<String, Query>) -> Vec<Error> {
+    let mut failures = vec![];
+    for (tag, query) in queries {
+        for fixture in &query.fixtures {
+            let result = count_rows(client, &query.text, fixture).await;
+            if let Err(message) = fixture.check(result) {
+                failures.push(Error::Fixture {
+                    tag: tag.clone(),
+                    line: fixture.line,
+                    message,
+                });
+            }
+        }
+    }
+    failures
+}
+
+// Number of returned rows, or changed rows for query without columns.
+async fn count_rows(
+    client: &mut Client,
+    query: &str,
+    fixture: &Fixture,
+) -> Result<usize, tokio_postgres::Error> {
+    let query = positional(query, Placeholder::Dollar);
+    let tx = client.transaction().await?;
+    let statement = tx.prepare(&query.text).await?;
+    let params = (0..statement.params().len())
+        .map(|idx| {
+            let number = (idx + 1).to_string();
+            let value = fixture.params.iter().find(|(name, _)| {
+                *name == number || query.params.get(idx).is_some_and(|param| param == name)
+            });
+            FixtureValue(value.map(|(_, value)| value.as_str()))
+        })
+        .collect::<Vec<_>>();
+    let params = params
+        .iter()
+        .map(|value| value as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    if statement.columns().is_empty() {
+        Ok(tx.execute(&statement, &params).await? as usize)
+    } else {
+        Ok(tx.query(&statement, &params).await?.len())
+    }
+}
+
+// Fixture value converted to parameter type, missing value is `NULL`.
+#[derive(Debug)]
+struct FixtureValue<'a>(Option<&'a str>);
+
+impl ToSql for FixtureValue<'_> {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        let value = match self.0 {
+            Some(value) if !value.eq_ignore_ascii_case("null") => value,
+            _ => return Ok(IsNull::Yes),
+        };
+        match *ty {
+            Type::BOOL => value.parse::<bool>()?.to_sql(ty, out),
+            Type::INT2 => value.parse::<i16>()?.to_sql(ty, out),
+            Type::INT4 => value.parse::<i32>()?.to_sql(ty, out),
+            Type::INT8 => value.parse::<i64>()?.to_sql(ty, out),
+            Type::FLOAT4 => value.parse::<f32>()?.to_sql(ty, out),
+            Type::FLOAT8 => value.parse::<f64>()?.to_sql(ty, out),
+            // Sent in text format, see `encode_format`, so server parses value by own type.
+            _ => {
+                let value = value
+                    .strip_prefix('\'')
+                    .and_then(|value| value.strip_suffix('\''))
+                    .unwrap_or(value);
+                out.extend_from_slice(value.as_bytes());
+                Ok(IsNull::No)
+            }
+        }
+    }
+
+    fn encode_format(&self, ty: &Type) -> Format {
+        match *ty {
+            Type::BOOL | Type::INT2 | Type::INT4 | Type::INT8 | Type::FLOAT4 | Type::FLOAT8 => {
+                Format::Binary
+            }
+            _ => Format::Text,
+        }
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+fn query_error(tag: &str, err: tokio_postgres::Error) -> Error {
+    Error::Query {
+        tag: tag.to_owned(),
+        err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    #[test]
+    fn timeout_around_query() {
+        let queries = ParseOptions::new()
+            .attributes(true)
+            .parse_detailed("-- name: report timeout=1500ms\nSELECT 1;\n-- name: plain\nSELECT 2;")
+            .unwrap();
+        assert_eq!(
+            with_timeout(&queries["report"]),
+            "SET LOCAL statement_timeout = 1500;\nSELECT 1;\n;SET LOCAL statement_timeout = DEFAULT;"
+        );
+        assert_eq!(with_timeout(&queries["plain"]), "SELECT 2;");
+        assert_eq!(statement_timeout(&queries["plain"]), None);
+    }
+
+    #[test]
+    fn transient_errors() {
+        assert!(is_transient_code(Some(
+            &SqlState::T_R_SERIALIZATION_FAILURE
+        )));
+        assert!(is_transient_code(Some(&SqlState::T_R_DEADLOCK_DETECTED)));
+        assert!(!is_transient_code(Some(&SqlState::UNIQUE_VIOLATION)));
+        assert!(!is_transient_code(None));
+        assert!(!is_transient(&Error::UnknownTag("x".to_owned())));
+    }
+
+    #[test]
+    fn fixture_values() {
+        let encode = |value, ty| {
+            let mut out = BytesMut::new();
+            let is_null = FixtureValue(value).to_sql(&ty, &mut out).unwrap();
+            (matches!(is_null, IsNull::Yes), out.to_vec())
+        };
+        assert_eq!(
+            encode(Some("7"), Type::INT4),
+            (false, 7i32.to_be_bytes().to_vec())
+        );
+        assert_eq!(encode(Some("true"), Type::BOOL), (false, vec![1]));
+        assert_eq!(encode(Some("'x y'"), Type::TEXT), (false, b"x y".to_vec()));
+        assert_eq!(encode(Some("NULL"), Type::INT4), (true, vec![]));
+        assert_eq!(encode(None, Type::TEXT), (true, vec![]));
+        assert!(FixtureValue(Some("x"))
+            .to_sql(&Type::INT8, &mut BytesMut::new())
+            .is_err());
+        assert!(matches!(
+            FixtureValue(None).encode_format(&Type::INT8),
+            Format::Binary
+        ));
+        assert!(matches!(
+            FixtureValue(None).encode_format(&Type::DATE),
+            Format::Text
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+//! [pyo3](https://docs.rs/pyo3/) bindings, module `rsyesql` with `parse` function and
+//! `ParseError` exception.
+//!
+//! Extension module is built with [maturin](https://www.maturin.rs/) and `python` feature.
+//!
+//! ```python
+//! import rsyesql
+//!
+//! queries = rsyesql.parse("-- name: x\nSELECT 1;")
+//! assert queries == {"x": "SELECT 1;"}
+//!
+//! try:
+//!     rsyesql.parse("SELECT 1;")
+//! except rsyesql.ParseError as e:
+//!     print(e.line, e.column, e.offset, e)
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+create_exception!(
+    rsyesql,
+    ParseError,
+    PyValueError,
+    "Invalid queries text, position in `line`, `column` and `offset` attributes."
+);
+
+/// Parse SQL queries as text to `dict` (`tag` => `query`), keys in definition order.
+#[pyfunction]
+fn parse<'py>(py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyDict>> {
+    let queries = crate::parse(text).map_err(|err| parse_error(py, err))?;
+    let dict = PyDict::new(py);
+    for (tag, query) in queries {
+        dict.set_item(tag, query)?;
+    }
+    Ok(dict)
+}
+
+fn parse_error(py: Python<'_>, err: crate::ParseError) -> PyErr {
+    let exc = ParseError::new_err(err.to_string());
+    let value = exc.value(py);
+    let attrs = [
+        ("line", err.line()),
+        ("column", err.column()),
+        ("offset", err.offset()),
+    ];
+    for (name, attr) in attrs {
+        if let Err(err) = value.setattr(name, attr) {
+            return err;
+        }
+    }
+    exc
+}
+
+/// Python module `rsyesql`.
+#[pymodule]
+fn rsyesql(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dict_and_error() {
+        Python::initialize();
+        Python::attach(|py| {
+            let dict = parse(py, "--name: b\nselect 2;\n--name: a\nselect 1;").unwrap();
+            let keys = dict.keys().extract::<Vec<String>>().unwrap();
+            assert_eq!(keys, vec!["b", "a"]);
+
+            let err = parse(py, "\nselect 1;").unwrap_err();
+            assert!(err.is_instance_of::<ParseError>(py));
+            assert!(err.is_instance_of::<PyValueError>(py));
+            let line = err.value(py).getattr("line").unwrap();
+            assert_eq!(line.extract::<usize>().unwrap(), 2);
+        });
+    }
+}
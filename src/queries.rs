@@ -0,0 +1,842 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use regex::Regex;
+
+use crate::options::Hook;
+use crate::{parse, positional, Migrations, ParseError, Placeholder};
+
+quick_error! {
+    /// The error type for operations on [Queries].
+    #[derive(Debug, PartialEq)]
+    pub enum QueriesError {
+        /// Different tags are same after normalization.
+        DuplicateTag { tag: String, other: String } {
+            display(r#"Tag "{}" is duplicate of "{}""#, tag, other)
+        }
+        /// Query with tag not found.
+        MissingTag { tag: String } {
+            display(r#"Query "{}" not found"#, tag)
+        }
+        /// Migration `name.down` is defined without `name.up`.
+        MigrationWithoutUp { tag: String } {
+            display(r#"Migration "{}" without up query"#, tag)
+        }
+    }
+}
+
+/// Tag matching in [Queries::get].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TagLookup {
+    /// Tags should be equal.
+    #[default]
+    Exact,
+    /// Tags compared ignoring case, `_` and `-`, so `SelectUsers`, `select_users` and
+    /// `select-users` are same tag.
+    Normalized,
+}
+
+impl TagLookup {
+    fn normalize<'a>(&self, tag: &'a str) -> Cow<'a, str> {
+        match self {
+            TagLookup::Exact => tag.into(),
+            TagLookup::Normalized => tag
+                .chars()
+                .filter(|c| !matches!(c, '_' | '-'))
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+                .into(),
+        }
+    }
+}
+
+/// Tag pattern for [Queries::get_matching], glob from `&str` or [Regex].
+///
+/// In glob `*` matches any characters and `?` one character, glob matches whole tag.
+#[derive(Debug, Clone)]
+pub struct TagPattern(Regex);
+
+impl TagPattern {
+    /// Pattern from glob.
+    pub fn glob(glob: &str) -> Self {
+        let mut re = String::from("^");
+        for (idx, part) in glob.split('*').enumerate() {
+            if idx > 0 {
+                re.push_str(".*");
+            }
+            let part = part.split('?').map(regex::escape).collect::<Vec<_>>();
+            re.push_str(&part.join("."));
+        }
+        re.push('$');
+        Self(Regex::new(&re).unwrap())
+    }
+
+    /// Returns `true` if tag matches pattern.
+    pub fn is_match(&self, tag: &str) -> bool {
+        self.0.is_match(tag)
+    }
+}
+
+impl From<&str> for TagPattern {
+    fn from(glob: &str) -> Self {
+        Self::glob(glob)
+    }
+}
+
+impl From<Regex> for TagPattern {
+    fn from(re: Regex) -> Self {
+        Self(re)
+    }
+}
+
+/// Parsed queries (`tag` => `query`) in definition order.
+///
+/// ```
+/// use rsyesql::{Queries, TagLookup};
+///
+/// let queries = Queries::parse("-- name: select_users\nSELECT * FROM users;")
+///     .unwrap()
+///     .with_lookup(TagLookup::Normalized)
+///     .unwrap();
+/// assert_eq!(queries.get("SelectUsers"), Some("SELECT * FROM users;"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Queries {
+    map: IndexMap<String, String>,
+    lookup: TagLookup,
+    // Normalized tag to index in map, empty for exact lookup.
+    index: HashMap<String, usize>,
+    usage: Option<Usage>,
+    on_lookup: Option<Hook<OnLookup>>,
+}
+
+type OnLookup = dyn Fn(&str, bool) + Send + Sync;
+
+// Fetched flag for every query, shared between clones.
+#[derive(Clone, Default)]
+struct Usage(Arc<Vec<AtomicBool>>);
+
+impl Usage {
+    fn new(len: usize) -> Self {
+        Usage(Arc::new((0..len).map(|_| AtomicBool::new(false)).collect()))
+    }
+
+    fn mark(&self, idx: usize) {
+        self.0[idx].store(true, Ordering::Relaxed);
+    }
+
+    fn is_used(&self, idx: usize) -> bool {
+        self.0[idx].load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Usage")
+    }
+}
+
+// Usage is runtime state, not part of queries.
+impl PartialEq for Usage {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Queries {
+    /// Parse SQL queries as text, see [parse].
+    pub fn parse<S: AsRef<str>>(text: S) -> Result<Self, ParseError> {
+        parse(text).map(Self::from)
+    }
+
+    /// Change tag matching for [get](Self::get).
+    ///
+    /// Returns [QueriesError::DuplicateTag] if tags are same under new matching.
+    pub fn with_lookup(mut self, lookup: TagLookup) -> Result<Self, QueriesError> {
+        self.index = index(&self.map, lookup)?;
+        self.lookup = lookup;
+        Ok(self)
+    }
+
+    /// Record tags fetched with [get](Self::get) and [get_full](Self::get_full), see
+    /// [unused](Self::unused).
+    ///
+    /// Clones share recorded usage.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let queries = Queries::parse("-- name: a\nSELECT 1;\n-- name: b\nSELECT 2;")
+    ///     .unwrap()
+    ///     .track_usage();
+    /// queries.get("a");
+    /// assert_eq!(queries.unused(), vec!["b"]);
+    /// ```
+    pub fn track_usage(mut self) -> Self {
+        self.usage = Some(Usage::new(self.map.len()));
+        self
+    }
+
+    /// Call `f` on every [get](Self::get) and [get_full](Self::get_full), for example for
+    /// per-query usage metrics.
+    ///
+    /// Closure receives tag as defined in source text if query found, otherwise requested tag,
+    /// and `true` if query found. Clones share closure.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use rsyesql::Queries;
+    ///
+    /// let hits = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&hits);
+    /// let queries = Queries::parse("-- name: a\nSELECT 1;")
+    ///     .unwrap()
+    ///     .on_lookup(move |_tag, found| {
+    ///         if found {
+    ///             counter.fetch_add(1, Ordering::Relaxed);
+    ///         }
+    ///     });
+    /// queries.get("a");
+    /// queries.get("b");
+    /// assert_eq!(hits.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn on_lookup<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, bool) + Send + Sync + 'static,
+    {
+        self.on_lookup = Some(Hook(Arc::new(f)));
+        self
+    }
+
+    /// Tags which were never fetched since [track_usage](Self::track_usage), in definition order.
+    ///
+    /// Without tracking every tag is returned.
+    pub fn unused(&self) -> Vec<&str> {
+        self.tags()
+            .enumerate()
+            .filter(|(idx, _)| !self.usage.as_ref().is_some_and(|usage| usage.is_used(*idx)))
+            .map(|(_, tag)| tag)
+            .collect()
+    }
+
+    /// Query by tag.
+    pub fn get(&self, tag: &str) -> Option<&str> {
+        self.get_full(tag).map(|(_, query)| query)
+    }
+
+    /// Query with tag as defined in source text.
+    pub fn get_full(&self, tag: &str) -> Option<(&str, &str)> {
+        let entry = self.find(tag).and_then(|idx| {
+            if let Some(usage) = &self.usage {
+                usage.mark(idx);
+            }
+            self.map.get_index(idx)
+        });
+        let (lookup_tag, found) = match entry {
+            Some((tag, _)) => (tag.as_str(), true),
+            None => (tag, false),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::trace!(tag = lookup_tag, found, "query lookup");
+        if let Some(Hook(f)) = &self.on_lookup {
+            f(lookup_tag, found);
+        }
+        entry.map(|(tag, query)| (tag.as_str(), query.as_str()))
+    }
+
+    /// Check that query with tag exists, tag is not recorded as fetched.
+    pub fn contains(&self, tag: &str) -> bool {
+        self.find(tag).is_some()
+    }
+
+    // Index of query in map.
+    fn find(&self, tag: &str) -> Option<usize> {
+        match self.lookup {
+            TagLookup::Exact => self.map.get_index_of(tag),
+            _ => self.index.get(self.lookup.normalize(tag).as_ref()).copied(),
+        }
+    }
+
+    /// Queries with tags from `tags` in order of `tags`, for passing only owned queries to subsystem.
+    ///
+    /// Tags are matched as in [get](Self::get), lookup and [on_lookup](Self::on_lookup) closure are
+    /// kept, usage is tracked separately if was tracked. Returns [QueriesError::MissingTag] if
+    /// any tag is not found.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let queries = Queries::parse("-- name: a\nSELECT 1;\n-- name: b\nSELECT 2;").unwrap();
+    /// let users = queries.select(&["b"]).unwrap();
+    /// assert_eq!(users.tags().collect::<Vec<_>>(), vec!["b"]);
+    /// assert!(queries.select(&["c"]).is_err());
+    /// ```
+    pub fn select<T: AsRef<str>>(&self, tags: &[T]) -> Result<Self, QueriesError> {
+        let mut map = IndexMap::with_capacity(tags.len());
+        for tag in tags {
+            let tag = tag.as_ref();
+            let (tag, query) = self
+                .find(tag)
+                .and_then(|idx| self.map.get_index(idx))
+                .ok_or_else(|| QueriesError::MissingTag {
+                    tag: tag.to_owned(),
+                })?;
+            map.insert(tag.clone(), query.clone());
+        }
+
+        let mut queries = Self::from(map).with_lookup(self.lookup)?;
+        if self.usage.is_some() {
+            queries = queries.track_usage();
+        }
+        queries.on_lookup = self.on_lookup.clone();
+        Ok(queries)
+    }
+
+    /// `(tag, query)` with tags matching glob or regex, in definition order.
+    ///
+    /// Tags are matched as defined in source text, matched queries are recorded as fetched.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    /// use regex::Regex;
+    ///
+    /// let queries =
+    ///     Queries::parse("-- name: seed_users\nINSERT 1;\n-- name: users\nSELECT 1;").unwrap();
+    /// assert_eq!(queries.get_matching("seed_*"), vec![("seed_users", "INSERT 1;")]);
+    /// assert_eq!(queries.get_matching(Regex::new("users$").unwrap()).len(), 2);
+    /// ```
+    pub fn get_matching<P: Into<TagPattern>>(&self, pattern: P) -> Vec<(&str, &str)> {
+        let pattern = pattern.into();
+        self.map
+            .iter()
+            .enumerate()
+            .filter(|(_, (tag, _))| pattern.is_match(tag))
+            .map(|(idx, (tag, query))| {
+                if let Some(usage) = &self.usage {
+                    usage.mark(idx);
+                }
+                (tag.as_str(), query.as_str())
+            })
+            .collect()
+    }
+
+    /// Iterate over `(tag, query)` in definition order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.map
+            .iter()
+            .map(|(tag, query)| (tag.as_str(), query.as_str()))
+    }
+
+    /// Iterate over `(tag, query)` with tags starting with `prefix`, in definition order.
+    ///
+    /// With `strip` prefix is removed from yielded tags.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let text = "-- name: users.select\nSELECT 1;\n-- name: posts.select\nSELECT 2;";
+    /// let queries = Queries::parse(text).unwrap();
+    /// assert_eq!(
+    ///     queries.iter_prefix("users.", true).collect::<Vec<_>>(),
+    ///     vec![("select", "SELECT 1;")]
+    /// );
+    /// ```
+    pub fn iter_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+        strip: bool,
+    ) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.iter().filter_map(move |(tag, query)| {
+            let rest = tag.strip_prefix(prefix)?;
+            Some((if strip { rest } else { tag }, query))
+        })
+    }
+
+    /// Tags in definition order.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    /// Number of queries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if there are no queries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Insert query with `new_tag` after query with `tag`, tags are matched as in [get](Self::get).
+    ///
+    /// Returns [QueriesError::MissingTag] if `tag` is not found and [QueriesError::DuplicateTag]
+    /// if `new_tag` is already used. Recorded usage of other queries is kept, but not shared with
+    /// clones anymore.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let mut queries = Queries::parse("-- name: a\nSELECT 1;\n-- name: c\nSELECT 3;").unwrap();
+    /// queries.insert_after("a", "b", "SELECT 2;").unwrap();
+    /// queries.rename("c", "d").unwrap();
+    /// assert_eq!(queries.remove("a"), Some("SELECT 1;".to_owned()));
+    /// assert_eq!(queries.tags().collect::<Vec<_>>(), vec!["b", "d"]);
+    /// assert!(queries.rename("b", "d").is_err());
+    /// ```
+    pub fn insert_after<Q: Into<String>>(
+        &mut self,
+        tag: &str,
+        new_tag: &str,
+        query: Q,
+    ) -> Result<(), QueriesError> {
+        let after = self.find_existing(tag)?;
+        self.check_unused(new_tag, None)?;
+        let mut map = IndexMap::with_capacity(self.map.len() + 1);
+        let mut old = Vec::with_capacity(self.map.len() + 1);
+        let mut query = Some(query.into());
+        for (idx, (tag, text)) in std::mem::take(&mut self.map).into_iter().enumerate() {
+            map.insert(tag, text);
+            old.push(Some(idx));
+            if idx == after {
+                map.insert(new_tag.to_owned(), query.take().unwrap());
+                old.push(None);
+            }
+        }
+        self.replace(map, old);
+        Ok(())
+    }
+
+    /// Remove query, tag is matched as in [get](Self::get). Order of other queries is kept.
+    pub fn remove(&mut self, tag: &str) -> Option<String> {
+        let removed = self.find(tag)?;
+        let old = (0..self.map.len())
+            .filter(|idx| *idx != removed)
+            .map(Some)
+            .collect();
+        let (_, query) = self.map.shift_remove_index(removed)?;
+        let map = std::mem::take(&mut self.map);
+        self.replace(map, old);
+        Some(query)
+    }
+
+    /// Rename query in place, tag is matched as in [get](Self::get).
+    ///
+    /// Returns [QueriesError::MissingTag] if `tag` is not found and [QueriesError::DuplicateTag]
+    /// if `new_tag` is used by other query.
+    pub fn rename(&mut self, tag: &str, new_tag: &str) -> Result<(), QueriesError> {
+        let renamed = self.find_existing(tag)?;
+        self.check_unused(new_tag, Some(renamed))?;
+        let map = std::mem::take(&mut self.map)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (tag, query))| {
+                let tag = if idx == renamed {
+                    new_tag.to_owned()
+                } else {
+                    tag
+                };
+                (tag, query)
+            })
+            .collect::<IndexMap<_, _>>();
+        let old = (0..map.len()).map(Some).collect();
+        self.replace(map, old);
+        Ok(())
+    }
+
+    fn find_existing(&self, tag: &str) -> Result<usize, QueriesError> {
+        self.find(tag).ok_or_else(|| QueriesError::MissingTag {
+            tag: tag.to_owned(),
+        })
+    }
+
+    // Tag is not used by query other than `except`.
+    fn check_unused(&self, tag: &str, except: Option<usize>) -> Result<(), QueriesError> {
+        match self.find(tag) {
+            Some(idx) if Some(idx) != except => Err(QueriesError::DuplicateTag {
+                tag: tag.to_owned(),
+                other: self.map.get_index(idx).unwrap().0.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Replace map after mutation, `old` is previous index of every query for usage flags.
+    fn replace(&mut self, map: IndexMap<String, String>, old: Vec<Option<usize>>) {
+        if let Some(usage) = &self.usage {
+            let flags = old
+                .into_iter()
+                .map(|idx| AtomicBool::new(idx.is_some_and(|idx| usage.is_used(idx))))
+                .collect();
+            self.usage = Some(Usage(Arc::new(flags)));
+        }
+        // Tags are checked for collisions before mutation.
+        self.index = index(&map, self.lookup).unwrap_or_default();
+        self.map = map;
+    }
+
+    /// Migrations from queries with tags `name.up` and `name.down`, see [Migrations].
+    pub fn migrations(&self) -> Result<Migrations<'_>, QueriesError> {
+        Migrations::new(&self.map)
+    }
+
+    /// SHA-256 of every query as lowercase hex, in definition order.
+    ///
+    /// Whitespace is normalized before hashing (runs of whitespace replaced by single space, leading
+    /// and trailing whitespace removed), so checksum is same for queries parsed with different
+    /// options. Whitespace in quoted strings and identifiers is hashed as is.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let a = Queries::parse("-- name: x\nSELECT 1;").unwrap();
+    /// let b = Queries::parse("-- name: x\nSELECT\n    1;").unwrap();
+    /// assert_eq!(a.checksums(), b.checksums());
+    /// ```
+    #[cfg(feature = "checksums")]
+    pub fn checksums(&self) -> IndexMap<&str, String> {
+        self.iter()
+            .map(|(tag, query)| (tag, crate::lockfile::checksum(query)))
+            .collect()
+    }
+
+    /// Prepared-statement name of every query, in definition order.
+    ///
+    /// Names are lowercase tags with characters other than ASCII letters, digits and `_` replaced
+    /// by `_`, prefixed by `q_` if not starting with letter or `_`. Names longer than 63 bytes
+    /// (PostgreSQL identifier limit) or colliding with previous name are truncated and suffixed
+    /// with hash of tag, so name of tag does not depend on other queries unless hashes collide.
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let queries = Queries::parse("-- name: users.Select\nSELECT 1;\n-- name: users_select\nSELECT 2;").unwrap();
+    /// let names = queries.statement_names();
+    /// assert_eq!(names["users.Select"], "users_select");
+    /// assert_eq!(names["users_select"], "users_select_42f1f6dc");
+    /// ```
+    pub fn statement_names(&self) -> IndexMap<&str, String> {
+        let mut names = IndexMap::<&str, String>::with_capacity(self.map.len());
+        let mut taken = HashSet::with_capacity(self.map.len());
+        for tag in self.tags() {
+            let mut name = String::with_capacity(tag.len());
+            if !tag.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                name.push_str("q_");
+            }
+            for c in tag.chars() {
+                name.push(match c {
+                    'a'..='z' | '0'..='9' | '_' => c,
+                    'A'..='Z' => c.to_ascii_lowercase(),
+                    _ => '_',
+                });
+            }
+            if name.len() > 63 || taken.contains(&name) {
+                name.truncate(54);
+                name.push_str(&format!("_{:08x}", fnv1a(tag)));
+            }
+            let base = name.clone();
+            let mut n = 2;
+            while taken.contains(&name) {
+                // Base is cut by length of suffix, so name fits in 63 bytes for any `n`.
+                let suffix = format!("_{}", n);
+                name = format!("{}{}", &base[..base.len().min(63 - suffix.len())], suffix);
+                n += 1;
+            }
+            taken.insert(name.clone());
+            names.insert(tag, name);
+        }
+        names
+    }
+
+    /// PostgreSQL script with `PREPARE name AS query;` for every query, names are from
+    /// [statement_names](Self::statement_names), named parameters are replaced by `$1`, `$2`, ...
+    ///
+    /// ```
+    /// use rsyesql::Queries;
+    ///
+    /// let queries = Queries::parse("-- name: select-user\nSELECT * FROM users WHERE id = :id;").unwrap();
+    /// assert_eq!(
+    ///     queries.prepare_script(),
+    ///     "PREPARE select_user AS SELECT * FROM users WHERE id = $1;\n"
+    /// );
+    /// ```
+    pub fn prepare_script(&self) -> String {
+        let mut script = String::new();
+        for ((_, query), name) in self.iter().zip(self.statement_names().values()) {
+            let query = positional(query, Placeholder::Dollar);
+            let text = query.text.trim().trim_end_matches(';').trim_end();
+            script.push_str(&format!("PREPARE {} AS {};\n", name, text));
+        }
+        script
+    }
+
+    /// Queries as [IndexMap].
+    pub fn as_map(&self) -> &IndexMap<String, String> {
+        &self.map
+    }
+
+    /// Convert to [IndexMap].
+    pub fn into_map(self) -> IndexMap<String, String> {
+        self.map
+    }
+}
+
+// Normalized tag to index in map, empty for exact lookup.
+fn index(
+    map: &IndexMap<String, String>,
+    lookup: TagLookup,
+) -> Result<HashMap<String, usize>, QueriesError> {
+    let mut index = HashMap::new();
+    if lookup != TagLookup::Exact {
+        for (idx, tag) in map.keys().enumerate() {
+            if let Some(prev) = index.insert(lookup.normalize(tag).into_owned(), idx) {
+                return Err(QueriesError::DuplicateTag {
+                    tag: tag.clone(),
+                    other: map.get_index(prev).unwrap().0.clone(),
+                });
+            }
+        }
+    }
+    Ok(index)
+}
+
+// 32-bit FNV-1a hash of tag, stable between releases and platforms.
+fn fnv1a(tag: &str) -> u32 {
+    tag.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+impl From<IndexMap<String, String>> for Queries {
+    fn from(map: IndexMap<String, String>) -> Self {
+        Self {
+            map,
+            lookup: TagLookup::Exact,
+            index: HashMap::new(),
+            usage: None,
+            on_lookup: None,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Queries {
+    type Item = (&'a String, &'a String);
+    type IntoIter = indexmap::map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_exact() {
+        let queries = Queries::parse("--name: select_users\nselect 1;").unwrap();
+        assert_eq!(queries.get("select_users"), Some("select 1;"));
+        assert_eq!(queries.get("SelectUsers"), None);
+    }
+
+    #[test]
+    fn lookup_normalized() {
+        let queries = Queries::parse("--name: select_users\nselect 1;")
+            .unwrap()
+            .with_lookup(TagLookup::Normalized)
+            .unwrap();
+        for tag in &[
+            "select_users",
+            "SelectUsers",
+            "select-users",
+            "SELECT_USERS",
+        ] {
+            assert_eq!(queries.get_full(tag), Some(("select_users", "select 1;")));
+        }
+        assert_eq!(queries.get("select_user"), None);
+    }
+
+    #[cfg(feature = "checksums")]
+    #[test]
+    fn checksums() {
+        let queries = Queries::parse("--name: x\nselect 1;\n--name: y\nselect 2;").unwrap();
+        let checksums = queries.checksums();
+        assert_eq!(
+            checksums["x"],
+            "354b7196c9ba5fb4b21cf615bb6ec4cd5c07503c34229feef033fc081a8c03f4"
+        );
+        assert_ne!(checksums["x"], checksums["y"]);
+    }
+
+    #[test]
+    fn mutate_in_order() {
+        let mut queries = Queries::parse("--name: a\n1;\n--name: b_c\n2;")
+            .unwrap()
+            .with_lookup(TagLookup::Normalized)
+            .unwrap()
+            .track_usage();
+        queries.get("b_c");
+        queries.insert_after("A", "x", "3;").unwrap();
+        assert_eq!(queries.tags().collect::<Vec<_>>(), ["a", "x", "b_c"]);
+        assert_eq!(queries.unused(), ["a", "x"]);
+        assert_eq!(
+            queries.insert_after("a", "B-C", "4;"),
+            Err(QueriesError::DuplicateTag {
+                tag: "B-C".to_owned(),
+                other: "b_c".to_owned()
+            })
+        );
+        assert_eq!(
+            queries.insert_after("z", "y", "4;"),
+            Err(QueriesError::MissingTag {
+                tag: "z".to_owned()
+            })
+        );
+
+        queries.rename("bc", "B-C").unwrap();
+        assert!(queries.rename("x", "a").is_err());
+        assert_eq!(queries.remove("A"), Some("1;".to_owned()));
+        assert_eq!(queries.remove("a"), None);
+        assert_eq!(
+            queries.iter().collect::<Vec<_>>(),
+            [("x", "3;"), ("B-C", "2;")]
+        );
+        assert_eq!(queries.get("b_c"), Some("2;"));
+        assert_eq!(queries.unused(), ["x"]);
+    }
+
+    #[test]
+    fn unused_tags() {
+        let queries = Queries::parse("--name: a\n1;\n--name: b\n2;\n--name: c\n3;").unwrap();
+        assert_eq!(queries.unused(), vec!["a", "b", "c"]);
+
+        let queries = queries
+            .with_lookup(TagLookup::Normalized)
+            .unwrap()
+            .track_usage();
+        let clone = queries.clone();
+        clone.get("C");
+        queries.get_full("a");
+        queries.get("x");
+        assert!(queries.contains("b"));
+        assert_eq!(queries.unused(), vec!["b"]);
+    }
+
+    #[test]
+    fn on_lookup_tags() {
+        use std::sync::Mutex;
+
+        let calls = Arc::new(Mutex::new(vec![]));
+        let calls2 = Arc::clone(&calls);
+        let queries = Queries::parse("--name: select_users\nselect 1;")
+            .unwrap()
+            .with_lookup(TagLookup::Normalized)
+            .unwrap()
+            .on_lookup(move |tag, found| calls2.lock().unwrap().push((tag.to_owned(), found)));
+        queries.get("SelectUsers");
+        queries.get_full("x");
+        assert!(queries.contains("select_users"));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![("select_users".to_owned(), true), ("x".to_owned(), false)]
+        );
+    }
+
+    #[test]
+    fn glob_pattern() {
+        let pattern = TagPattern::glob("seed_?.*");
+        assert!(pattern.is_match("seed_1.users"));
+        assert!(pattern.is_match("seed_a."));
+        assert!(!pattern.is_match("seed_12.x"));
+        assert!(!pattern.is_match("xseed_1.x"));
+
+        let queries = Queries::parse("--name: a.b\n1;\n--name: axb\n2;\n--name: c\n3;")
+            .unwrap()
+            .track_usage();
+        assert_eq!(queries.get_matching("a.*"), vec![("a.b", "1;")]);
+        assert_eq!(queries.unused(), vec!["axb", "c"]);
+    }
+
+    #[test]
+    fn prefix_iteration() {
+        let queries = Queries::parse("--name: a.x\n1;\n--name: b.x\n2;\n--name: a.y\n3;").unwrap();
+        assert_eq!(
+            queries.iter_prefix("a.", false).collect::<Vec<_>>(),
+            vec![("a.x", "1;"), ("a.y", "3;")]
+        );
+        assert_eq!(
+            queries.iter_prefix("a.", true).collect::<Vec<_>>(),
+            vec![("x", "1;"), ("y", "3;")]
+        );
+        assert_eq!(queries.iter_prefix("c.", true).count(), 0);
+    }
+
+    #[test]
+    fn select_subset() {
+        let queries = Queries::parse("--name: a\n1;\n--name: b_c\n2;\n--name: d\n3;")
+            .unwrap()
+            .with_lookup(TagLookup::Normalized)
+            .unwrap()
+            .track_usage();
+        let subset = queries.select(&["d", "BC"]).unwrap();
+        assert_eq!(
+            subset.iter().collect::<Vec<_>>(),
+            vec![("d", "3;"), ("b_c", "2;")]
+        );
+        assert_eq!(subset.get("b-c"), Some("2;"));
+        assert_eq!(subset.unused(), vec!["d"]);
+        assert_eq!(queries.unused(), vec!["a", "b_c", "d"]);
+        assert_eq!(
+            queries.select(&["a", "x"]).err(),
+            Some(QueriesError::MissingTag {
+                tag: "x".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn statement_names_limited() {
+        let long = "a".repeat(70);
+        let text = format!(
+            "--name: 1st\n1;\n--name: {}\n2;\n--name: {}b\n3;",
+            long, long
+        );
+        let queries = Queries::parse(text).unwrap();
+        let names = queries.statement_names();
+        assert_eq!(names["1st"], "q_1st");
+        assert!(names.values().all(|name| name.len() <= 63));
+        assert_eq!(names[1].len(), 63);
+        assert_ne!(names[1], names[2]);
+        assert_eq!(names, queries.statement_names());
+
+        // Names `<hashed>_2` ... `<hashed>_99` are taken, so suffix is `_100`.
+        let hashed = format!("{}_{:08x}", &long[..54], fnv1a(&long));
+        let mut text = String::new();
+        for n in 2..100 {
+            let base = &hashed[..63 - format!("_{}", n).len()];
+            text.push_str(&format!("--name: {}_{}\n{};\n", base, n, n));
+        }
+        text.push_str(&format!("--name: {}\n1;\n--name: {}\n2;", hashed, long));
+        let queries = Queries::parse(text).unwrap();
+        let names = queries.statement_names();
+        assert_eq!(names[long.as_str()], format!("{}_100", &hashed[..59]));
+        assert!(names.values().all(|name| name.len() <= 63));
+    }
+
+    #[test]
+    fn lookup_normalized_duplicate() {
+        let queries =
+            Queries::parse("--name: select_users\nselect 1;\n--name: SelectUsers\nselect 2;");
+        assert_eq!(
+            queries.unwrap().with_lookup(TagLookup::Normalized).err(),
+            Some(QueriesError::DuplicateTag {
+                tag: "SelectUsers".to_owned(),
+                other: "select_users".to_owned()
+            })
+        );
+    }
+}
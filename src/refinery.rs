@@ -0,0 +1,190 @@
+//! [refinery](https://docs.rs/refinery/) integration, queries exported as migrations.
+//!
+//! ```ignore
+//! let queries = rsyesql::ParseOptions::new()
+//!     .attributes(true)
+//!     .parse_detailed(include_str!("./migrations.sql"))?;
+//! let migrations = rsyesql::refinery::migrations(&queries)?;
+//! refinery::Runner::new(&migrations).run(&mut conn)?;
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use refinery_core::Migration;
+
+use crate::Query;
+
+quick_error! {
+    /// The error type for migrations export.
+    #[derive(Debug)]
+    pub enum Error {
+        /// `version` attribute is not a number.
+        InvalidVersion { tag: String, version: String } {
+            display(r#"Invalid version of "{}": "{}""#, tag, version)
+        }
+        /// Tag has neither `version` attribute nor leading number.
+        MissingVersion(tag: String) {
+            display(r#"Missing version of "{}": use `version=N` attribute or leading number"#, tag)
+        }
+        /// Same version for different tags.
+        DuplicateVersion { tag: String, other: String, version: u32 } {
+            display(r#"Version {} of "{}" already used by "{}""#, version, tag, other)
+        }
+        /// Migration rejected by refinery.
+        Refinery { tag: String, err: refinery_core::Error } {
+            display(r#"Invalid migration "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Writing of migration file failed.
+        Io(err: io::Error) {
+            display("{}", err)
+            cause(err)
+            from()
+        }
+    }
+}
+
+/// Migration file names (`V<version>__<name>.sql`) with queries in order of versions.
+///
+/// With `name.up` tags only these queries are migrations (`.down` queries are ignored, refinery
+/// does not revert), otherwise every query is migration. Version is `version` attribute of tag
+/// line (`-- name: users version=3`, see
+/// [ParseOptions::attributes](crate::ParseOptions::attributes)) or leading number of tag
+/// (`001_users`), tags without version return [Error::MissingVersion], so versions of applied
+/// migrations do not change when migration is inserted. Name is tag without version (whole tag
+/// for numeric tags like `001`), characters which are not alphanumeric are replaced by `_`.
+///
+/// ```
+/// let text = "-- name: 001_users.up\nCREATE TABLE users (id INT);\n-- name: 001_users.down\nDROP TABLE users;\n-- name: posts.up version=5\nCREATE TABLE posts (id INT);";
+/// let options = rsyesql::ParseOptions::new().attributes(true);
+/// let queries = options.parse_detailed(text).unwrap();
+/// let files = rsyesql::refinery::migration_files(&queries).unwrap();
+/// assert_eq!(
+///     files,
+///     [
+///         ("V1__users.sql".to_owned(), "CREATE TABLE users (id INT);".to_owned()),
+///         ("V5__posts.sql".to_owned(), "CREATE TABLE posts (id INT);".to_owned()),
+///     ]
+/// );
+/// ```
+pub fn migration_files(queries: &IndexMap<String, Query>) -> Result<Vec<(String, String)>, Error> {
+    let has_up = queries.keys().any(|tag| tag.ends_with(".up"));
+    let mut versions: Vec<(u32, &str, String)> = vec![];
+    for (tag, query) in queries {
+        let name = match tag.strip_suffix(".up") {
+            Some(name) => name,
+            None if has_up => continue,
+            None => tag,
+        };
+        let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let (version, name) = match query.attribute("version") {
+            Some(version) => (Some(version), name),
+            None if digits > 0 => (
+                Some(&name[..digits]),
+                name[digits..].trim_start_matches(|c: char| !c.is_alphanumeric()),
+            ),
+            None => (None, name),
+        };
+        let version = version.ok_or_else(|| Error::MissingVersion(tag.clone()))?;
+        let version = version.parse().map_err(|_| Error::InvalidVersion {
+            tag: tag.clone(),
+            version: version.to_owned(),
+        })?;
+        // Refinery requires name after version.
+        let name = match name.is_empty() {
+            true => tag.strip_suffix(".up").unwrap_or(tag),
+            false => name,
+        };
+        if let Some((_, other, _)) = versions.iter().find(|(v, _, _)| *v == version) {
+            return Err(Error::DuplicateVersion {
+                tag: tag.clone(),
+                other: (*other).to_owned(),
+                version,
+            });
+        }
+        let name = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        versions.push((version, tag, name));
+    }
+    versions.sort_by_key(|(version, _, _)| *version);
+    Ok(versions
+        .into_iter()
+        .map(|(version, tag, name)| {
+            (
+                format!("V{}__{}.sql", version, name),
+                queries[tag].text.clone(),
+            )
+        })
+        .collect())
+}
+
+/// Refinery migrations from queries, see [migration_files].
+pub fn migrations(queries: &IndexMap<String, Query>) -> Result<Vec<Migration>, Error> {
+    migration_files(queries)?
+        .into_iter()
+        .map(|(file, sql)| {
+            let name = file.trim_end_matches(".sql");
+            Migration::unapplied(name, &sql).map_err(|err| Error::Refinery {
+                tag: name.to_owned(),
+                err,
+            })
+        })
+        .collect()
+}
+
+/// Write [migration_files] to directory, for `refinery::embed_migrations!` or refinery CLI.
+pub fn write_files<P: AsRef<Path>>(queries: &IndexMap<String, Query>, dir: P) -> Result<(), Error> {
+    let files = migration_files(queries)?;
+    fs::create_dir_all(dir.as_ref())?;
+    for (file, sql) in files {
+        fs::write(dir.as_ref().join(file), sql + "\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Map, ParseError, ParseOptions};
+
+    fn parse_detailed(text: &str) -> Result<Map<Query>, ParseError> {
+        ParseOptions::new().attributes(true).parse_detailed(text)
+    }
+
+    #[test]
+    fn ordered_tags() {
+        let text = "-- name: create-users version=1\nCREATE TABLE users (id INT);\n-- name: 10_posts\nCREATE TABLE posts (id INT);\n-- name: index version=3\nCREATE INDEX users_id ON users (id);";
+        let queries = parse_detailed(text).unwrap();
+        let migrations = migrations(&queries).unwrap();
+        let names = migrations
+            .iter()
+            .map(|m| (m.version(), m.name()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, [(1, "create_users"), (3, "index"), (10, "posts")]);
+        assert_eq!(migrations[0].sql(), Some("CREATE TABLE users (id INT);"));
+
+        let text = "-- name: a version=2\nSELECT 1;\n-- name: 002_b\nSELECT 2;";
+        let err = migration_files(&parse_detailed(text).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Version 2 of "002_b" already used by "a""#
+        );
+
+        let text = "-- name: users\nSELECT 1;\n-- name: 001_posts\nSELECT 2;";
+        let err = migration_files(&parse_detailed(text).unwrap()).unwrap_err();
+        assert!(matches!(err, Error::MissingVersion(tag) if tag == "users"));
+
+        let text = "-- name: 001.up\nSELECT 1;\n-- name: 001.down\nSELECT 2;";
+        let numeric = super::migrations(&parse_detailed(text).unwrap()).unwrap();
+        assert_eq!((numeric[0].version(), numeric[0].name()), (1, "001"));
+
+        let text = "-- name: a version=x\nSELECT 1;";
+        let err = migration_files(&parse_detailed(text).unwrap()).unwrap_err();
+        assert!(matches!(err, Error::InvalidVersion { .. }));
+    }
+}
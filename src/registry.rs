@@ -0,0 +1,170 @@
+//! Process-wide registry of queries, see [register_queries](crate::register_queries).
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+use crate::{FileError, ParseOptions};
+
+static GLOBAL: Registry = Registry::new();
+
+/// Registry filled by [register_queries](crate::register_queries).
+///
+/// ```
+/// rsyesql::global().register("app", "app.sql", "-- name: ping\nSELECT 1;");
+/// assert_eq!(rsyesql::global().get("app.ping"), Some("SELECT 1;"));
+/// ```
+pub fn global() -> &'static Registry {
+    &GLOBAL
+}
+
+/// Queries from registered static texts, parsed on first access.
+///
+/// Tags are prefixed by namespace and `.`, tags of text without namespace are not prefixed. Tag
+/// of later registered text replaces same tag of previous text.
+///
+/// ```
+/// use rsyesql::registry::Registry;
+///
+/// static QUERIES: Registry = Registry::new();
+///
+/// QUERIES.register("users", "users.sql", "-- name: select\nSELECT * FROM users;");
+/// assert_eq!(QUERIES.get("users.select"), Some("SELECT * FROM users;"));
+/// ```
+#[derive(Debug)]
+pub struct Registry {
+    // Registered and not parsed texts: namespace, file and text.
+    pending: Mutex<Vec<(&'static str, &'static str, &'static str)>>,
+    queries: RwLock<BTreeMap<String, &'static str>>,
+}
+
+impl Registry {
+    /// Empty registry.
+    pub const fn new() -> Self {
+        Self {
+            pending: Mutex::new(Vec::new()),
+            queries: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Register text of `file` with queries, text is parsed on first access.
+    pub fn register(&self, namespace: &'static str, file: &'static str, text: &'static str) {
+        self.pending.lock().unwrap().push((namespace, file, text));
+    }
+
+    /// Parse registered texts, returns error of first invalid text.
+    ///
+    /// Called by every access, useful in tests to check registered queries.
+    pub fn load(&self) -> Result<(), FileError> {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut queries = self.queries.write().unwrap();
+        // Invalid text stays pending, so every access fails.
+        while let Some(&(namespace, file, text)) = pending.first() {
+            let parsed =
+                ParseOptions::new()
+                    .parse_static(text)
+                    .map_err(|err| FileError::Parse {
+                        file: PathBuf::from(file),
+                        err,
+                    })?;
+            pending.remove(0);
+            for (tag, query) in parsed {
+                let tag = match namespace {
+                    "" => tag.into_owned(),
+                    namespace => format!("{}.{}", namespace, tag),
+                };
+                let query = match query {
+                    Cow::Borrowed(query) => query,
+                    // Registry lives until process exit.
+                    Cow::Owned(query) => Box::leak(query.into_boxed_str()),
+                };
+                queries.insert(tag, query);
+            }
+        }
+        Ok(())
+    }
+
+    /// Query by namespaced tag (`users.select`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if registered text is not valid, see [load](Self::load).
+    pub fn get(&self, tag: &str) -> Option<&'static str> {
+        if let Err(err) = self.load() {
+            panic!("Failed to parse registered queries: {}", err);
+        }
+        self.queries.read().unwrap().get(tag).copied()
+    }
+
+    /// Namespaced tags in alphabetical order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if registered text is not valid, see [load](Self::load).
+    pub fn tags(&self) -> Vec<String> {
+        if let Err(err) = self.load() {
+            panic!("Failed to parse registered queries: {}", err);
+        }
+        self.queries.read().unwrap().keys().cloned().collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Register SQL files in [global](crate::registry::global) registry, files are embedded by
+/// `include_str!` and parsed on first access.
+///
+/// Every file is `"namespace" => "path"` or `"path"` without namespace, path is relative to
+/// current file.
+///
+/// ```ignore
+/// fn main() {
+///     rsyesql::register_queries!("users" => "../sql/users.sql", "../sql/common.sql");
+///     let query = rsyesql::global().get("users.select").unwrap();
+/// }
+/// ```
+#[macro_export]
+macro_rules! register_queries {
+    () => {};
+    ($namespace:literal => $path:literal $(, $($rest:tt)*)?) => {
+        $crate::registry::global().register($namespace, $path, include_str!($path));
+        $($crate::register_queries!($($rest)*);)?
+    };
+    ($path:literal $(, $($rest:tt)*)?) => {
+        $crate::registry::global().register("", $path, include_str!($path));
+        $($crate::register_queries!($($rest)*);)?
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn namespaced_lookup() {
+        let registry = Registry::new();
+        registry.register("users", "users.sql", "-- name: select\nSELECT\n  1;");
+        registry.register("", "common.sql", "-- name: ping\nSELECT 1;");
+        assert_eq!(registry.tags(), ["ping", "users.select"]);
+        assert_eq!(registry.get("users.select"), Some("SELECT 1;"));
+        assert_eq!(registry.get("select"), None);
+
+        registry.register("users", "other.sql", "-- name: select\nSELECT 2;");
+        assert_eq!(registry.get("users.select"), Some("SELECT 2;"));
+
+        registry.register("bad", "bad.sql", "SELECT 1;");
+        assert!(matches!(
+            registry.load(),
+            Err(FileError::Parse { file, .. }) if file == Path::new("bad.sql")
+        ));
+    }
+}
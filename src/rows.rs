@@ -0,0 +1,122 @@
+use std::error::Error;
+
+quick_error! {
+    /// Row columns do not match `-- returns:` annotation, see [check_row_shape].
+    #[derive(Debug, PartialEq)]
+    pub enum RowShapeError {
+        /// Annotated column is not in row.
+        Missing { column: String } {
+            display(r#"Column "{}" from returns annotation is missing in row"#, column)
+        }
+        /// Row column is not annotated.
+        Unexpected { column: String } {
+            display(r#"Column "{}" is not in returns annotation"#, column)
+        }
+    }
+}
+
+quick_error! {
+    /// The error type for mapping rows to structs generated by
+    /// [Codegen::row_structs](crate::codegen::Codegen::row_structs).
+    #[derive(Debug)]
+    pub enum RowError {
+        /// Row columns do not match annotation.
+        Shape(err: RowShapeError) {
+            display("{}", err)
+            cause(err)
+            from()
+        }
+        /// Column value can not be converted to field type.
+        Value { column: String, err: Box<dyn Error + Send + Sync> } {
+            display(r#"Failed to get column "{}": {}"#, column, err)
+        }
+    }
+}
+
+/// Check that row has exactly columns from `-- returns:` annotation, order is not checked.
+///
+/// ```
+/// use rsyesql::{check_row_shape, RowShapeError};
+///
+/// let text = "-- name: x\n-- returns: id:i64, name:text\nSELECT id, name FROM users;";
+/// let queries = rsyesql::parse_detailed(text).unwrap();
+/// let returns = queries["x"].returns.as_ref().unwrap();
+/// let expected = returns.iter().map(|c| &c.name).collect::<Vec<_>>();
+/// assert_eq!(check_row_shape(&expected, ["name", "id"]), Ok(()));
+/// assert_eq!(
+///     check_row_shape(&expected, ["id"]),
+///     Err(RowShapeError::Missing { column: "name".to_owned() })
+/// );
+/// ```
+pub fn check_row_shape<E, I, S>(expected: &[E], columns: I) -> Result<(), RowShapeError>
+where
+    E: AsRef<str>,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let columns = columns
+        .into_iter()
+        .map(|name| name.as_ref().to_owned())
+        .collect::<Vec<_>>();
+    if let Some(column) = expected
+        .iter()
+        .find(|name| !columns.iter().any(|c| c == name.as_ref()))
+    {
+        return Err(RowShapeError::Missing {
+            column: column.as_ref().to_owned(),
+        });
+    }
+    if let Some(column) = columns
+        .iter()
+        .find(|&c| !expected.iter().any(|name| name.as_ref() == c))
+    {
+        return Err(RowShapeError::Unexpected {
+            column: column.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Rust type for annotated column type.
+///
+/// Common SQL types are mapped (`int8` / `bigint` to `i64`, `text` to `String`, ...), other types
+/// are used as is, so annotation can contain Rust types. Suffix `?` makes type [Option].
+pub(crate) fn rust_type(ty: &str) -> String {
+    if let Some(ty) = ty.strip_suffix('?') {
+        return format!("Option<{}>", rust_type(ty.trim_end()));
+    }
+    let mapped = match ty.to_lowercase().as_str() {
+        "int2" | "smallint" => "i16",
+        "int" | "int4" | "integer" => "i32",
+        "int8" | "bigint" => "i64",
+        "real" | "float4" => "f32",
+        "float8" | "double precision" => "f64",
+        "bool" | "boolean" => "bool",
+        "text" | "varchar" | "char" | "character varying" | "name" => "String",
+        "bytea" | "blob" => "Vec<u8>",
+        _ => ty,
+    };
+    mapped.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unexpected_column() {
+        assert_eq!(
+            check_row_shape(&["id"], vec!["id", "name"]),
+            Err(RowShapeError::Unexpected {
+                column: "name".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn rust_types() {
+        assert_eq!(rust_type("BIGINT"), "i64");
+        assert_eq!(rust_type("text?"), "Option<String>");
+        assert_eq!(rust_type("chrono::NaiveDate"), "chrono::NaiveDate");
+    }
+}
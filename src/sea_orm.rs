@@ -0,0 +1,148 @@
+//! [sea-orm](https://docs.rs/sea-orm/) integration, queries as raw statements.
+//!
+//! ```ignore
+//! let queries = rsyesql::parse(include_str!("./queries.sql"))?;
+//! let statements = rsyesql::sea_orm::Statements::new(DbBackend::Postgres, &queries);
+//! let statement = statements.statement("select_user", [("id", user_id.into())])?;
+//! let users = User::find_by_statement(statement).all(&db).await?;
+//! ```
+
+use ::sea_orm::{DbBackend, Statement, Value};
+use indexmap::IndexMap;
+
+use crate::{positional, Placeholder, PositionalQuery};
+
+quick_error! {
+    /// The error type for [Statements].
+    #[derive(Debug, PartialEq)]
+    pub enum Error {
+        /// Query with tag is not defined.
+        UnknownTag(tag: String) {
+            display(r#"Unknown tag: "{}""#, tag)
+        }
+        /// Value for named parameter of query is not passed.
+        MissingParam { tag: String, param: String } {
+            display(r#"Missing parameter "{}" of "{}""#, param, tag)
+        }
+    }
+}
+
+/// Queries with placeholders of backend, converted to [Statement] by tag.
+#[derive(Debug, Clone)]
+pub struct Statements {
+    backend: DbBackend,
+    queries: IndexMap<String, PositionalQuery>,
+}
+
+impl Statements {
+    /// Named parameters (`:name`) of queries replaced by placeholders of backend (`$1` for
+    /// PostgreSQL, `?` for MySQL and SQLite).
+    pub fn new<I, T, Q>(backend: DbBackend, queries: I) -> Self
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let style = match backend {
+            DbBackend::Postgres => Placeholder::Dollar,
+            _ => Placeholder::Question,
+        };
+        let queries = queries
+            .into_iter()
+            .map(|(tag, query)| (tag.as_ref().to_owned(), positional(query.as_ref(), style)))
+            .collect();
+        Self { backend, queries }
+    }
+
+    /// Backend of statements.
+    pub fn backend(&self) -> DbBackend {
+        self.backend
+    }
+
+    /// Statement of query with values of named parameters bound in order of placeholders.
+    ///
+    /// ```
+    /// use sea_orm::{DbBackend, Value};
+    ///
+    /// let queries = rsyesql::parse("-- name: select_user\nSELECT * FROM users WHERE id = :id;").unwrap();
+    /// let statements = rsyesql::sea_orm::Statements::new(DbBackend::Sqlite, &queries);
+    /// let statement = statements.statement("select_user", [("id", 42)]).unwrap();
+    /// assert_eq!(statement.sql, "SELECT * FROM users WHERE id = ?;");
+    /// assert_eq!(statement.values.unwrap().0, [Value::from(42)]);
+    /// ```
+    pub fn statement<P, K, V>(&self, tag: &str, params: P) -> Result<Statement, Error>
+    where
+        P: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<Value>,
+    {
+        let query = self
+            .queries
+            .get(tag)
+            .ok_or_else(|| Error::UnknownTag(tag.to_owned()))?;
+        let params = params
+            .into_iter()
+            .map(|(name, value)| (name, value.into()))
+            .collect::<Vec<_>>();
+        let values = query
+            .params
+            .iter()
+            .map(|param| {
+                params
+                    .iter()
+                    .find(|(name, _)| name.as_ref() == param)
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| Error::MissingParam {
+                        tag: tag.to_owned(),
+                        param: param.clone(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Statement::from_sql_and_values(
+            self.backend,
+            query.text.clone(),
+            values,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_in_order() {
+        let queries = crate::parse(
+            "-- name: x\nSELECT * FROM t WHERE a = :a AND b = :b OR a = :a;\n-- name: y\nSELECT 1;",
+        )
+        .unwrap();
+        let statements = Statements::new(DbBackend::Postgres, &queries);
+        let statement = statements
+            .statement("x", [("b", Value::from("b")), ("a", Value::from(1))])
+            .unwrap();
+        assert_eq!(
+            statement.sql,
+            "SELECT * FROM t WHERE a = $1 AND b = $2 OR a = $1;"
+        );
+        assert_eq!(
+            statement.values.unwrap().0,
+            [Value::from(1), Value::from("b")]
+        );
+        assert_eq!(
+            statements.statement("x", [("a", 1)]).unwrap_err(),
+            Error::MissingParam {
+                tag: "x".to_owned(),
+                param: "b".to_owned()
+            }
+        );
+        assert_eq!(
+            statements.statement("z", [("a", 1)]).unwrap_err(),
+            Error::UnknownTag("z".to_owned())
+        );
+
+        let statements = Statements::new(DbBackend::MySql, &queries);
+        let statement = statements.statement("x", [("a", 1), ("b", 2)]).unwrap();
+        assert_eq!(statement.values.unwrap().0.len(), 3);
+        assert_eq!(statement.db_backend, DbBackend::MySql);
+    }
+}
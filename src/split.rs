@@ -0,0 +1,289 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Map;
+
+/// Split query to separate statements.
+///
+/// Statements separated by semicolons. Semicolons are ignored in quoted strings and identifiers
+/// (`'...'`, `"..."`), dollar-quoted strings (`$$...$$`, `$body$...$body$`), comments
+/// (`-- ...`, `/* ... */`) and in `BEGIN ... END` and `CASE ... END` blocks, so function and
+/// trigger bodies are not split. `BEGIN` followed by `;`, `TRANSACTION`, `WORK` and other
+/// transaction modes starts transaction, not block. Every statement is trimmed and keep own
+/// terminating semicolon, empty statements are skipped.
+///
+/// ```
+/// let statements = rsyesql::split_statements("SELECT 1; SELECT ';';");
+/// assert_eq!(statements, vec!["SELECT 1;", "SELECT ';';"]);
+///
+/// let statements = rsyesql::split_statements(
+///     "CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql; SELECT f();",
+/// );
+/// assert_eq!(statements.len(), 2);
+/// ```
+pub fn split_statements(query: &str) -> Vec<String> {
+    let bytes = query.as_bytes();
+    let mut statements = vec![];
+    let mut start = 0;
+    // Depth of `BEGIN` / `CASE` blocks.
+    let mut depth = 0usize;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        idx = match bytes[idx] {
+            // Doubled quote is escaped quote, it's parsed as closed and opened again.
+            q @ (b'\'' | b'"') => {
+                find_from(query, idx + 1, q as char).map_or(bytes.len(), |end| end + 1)
+            }
+            b'-' if bytes.get(idx + 1) == Some(&b'-') => {
+                find_from(query, idx, '\n').unwrap_or(bytes.len())
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => query[idx + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| idx + 2 + end + 2),
+            b'$' if idx == 0 || !is_word_byte(bytes[idx - 1]) => dollar_quote_end(query, idx),
+            b';' if depth == 0 => {
+                push_statement(&mut statements, &query[start..=idx]);
+                start = idx + 1;
+                idx + 1
+            }
+            c if is_word_byte(c) => {
+                let end = word_end(bytes, idx);
+                let word = &query[idx..end];
+                let next = next_word(&query[end..]);
+                if word.eq_ignore_ascii_case("case")
+                    || (word.eq_ignore_ascii_case("begin") && !starts_transaction(&query[end..]))
+                {
+                    depth += 1;
+                    end
+                } else if word.eq_ignore_ascii_case("end") && ends_statement(next) {
+                    // `END CASE` closes `CASE`, word after `END` is not counted again.
+                    if next.eq_ignore_ascii_case("case") {
+                        depth = depth.saturating_sub(1);
+                    }
+                    next.as_ptr() as usize - query.as_ptr() as usize + next.len()
+                } else {
+                    if word.eq_ignore_ascii_case("end") {
+                        depth = depth.saturating_sub(1);
+                    }
+                    end
+                }
+            }
+            _ => idx + 1,
+        };
+    }
+    push_statement(&mut statements, &query[start..]);
+
+    statements
+}
+
+fn find_from(text: &str, from: usize, c: char) -> Option<usize> {
+    text[from..].find(c).map(|idx| from + idx)
+}
+
+// Identifier characters, non-ASCII bytes are part of identifiers.
+fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80
+}
+
+fn word_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&c| !is_word_byte(c))
+        .map_or(bytes.len(), |idx| start + idx)
+}
+
+// Position after closing delimiter of dollar quote at `start`, or after `$` if it's not quote.
+pub(crate) fn dollar_quote_end(text: &str, start: usize) -> usize {
+    let rest = &text[start + 1..];
+    let tag_len = match rest.find('$') {
+        Some(len) => len,
+        None => return start + 1,
+    };
+    let tag = &rest[..tag_len];
+    let valid = tag.starts_with(|c: char| !c.is_ascii_digit())
+        && tag
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80);
+    if !tag.is_empty() && !valid {
+        return start + 1;
+    }
+
+    let delimiter = &text[start..start + tag_len + 2];
+    let body = start + delimiter.len();
+    text[body..]
+        .find(delimiter)
+        .map_or(text.len(), |idx| body + idx + delimiter.len())
+}
+
+fn next_word(text: &str) -> &str {
+    let text = text.trim_start();
+    &text[..word_end(text.as_bytes(), 0)]
+}
+
+// `BEGIN` is start of transaction, not block.
+fn starts_transaction(rest: &str) -> bool {
+    const MODES: &[&str] = &[
+        "transaction",
+        "tran",
+        "work",
+        "deferred",
+        "immediate",
+        "exclusive",
+        "isolation",
+        "read",
+    ];
+    let word = next_word(rest);
+    rest.trim_start().is_empty()
+        || rest.trim_start().starts_with(';')
+        || MODES.iter().any(|mode| word.eq_ignore_ascii_case(mode))
+}
+
+// `END IF`, `END LOOP`, `END CASE` end statement inside block, not block.
+fn ends_statement(word: &str) -> bool {
+    const STATEMENTS: &[&str] = &["if", "loop", "while", "repeat", "for", "case"];
+    STATEMENTS
+        .iter()
+        .any(|statement| word.eq_ignore_ascii_case(statement))
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    if !statement.is_empty() && statement != ";" {
+        statements.push(statement.to_owned());
+    }
+}
+
+/// Query is single `SELECT`, `INSERT`, `UPDATE`, `DELETE`, `MERGE`, `WITH`, `VALUES` or `TABLE`
+/// statement, which can be passed to `EXPLAIN`.
+///
+/// ```
+/// assert!(rsyesql::is_explainable("with x AS (SELECT 1) SELECT * FROM x;"));
+/// assert!(!rsyesql::is_explainable("CREATE TABLE users (id INT);"));
+/// assert!(!rsyesql::is_explainable("SELECT 1; SELECT 2;"));
+/// ```
+pub fn is_explainable(query: &str) -> bool {
+    const STATEMENTS: &[&str] = &[
+        "select", "insert", "update", "delete", "merge", "with", "values", "table",
+    ];
+    let statements = split_statements(query);
+    let statement = match statements.as_slice() {
+        [statement] => statement.as_str(),
+        _ => return false,
+    };
+    let word = next_word(skip_comments(statement).trim_start_matches('('));
+    STATEMENTS
+        .iter()
+        .any(|statement| word.eq_ignore_ascii_case(statement))
+}
+
+// Text after leading whitespace and comments.
+fn skip_comments(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        if text.starts_with("--") {
+            text = text.find('\n').map_or("", |idx| &text[idx + 1..]);
+        } else if text.starts_with("/*") {
+            text = text.find("*/").map_or("", |idx| &text[idx + 2..]);
+        } else {
+            return text;
+        }
+    }
+}
+
+/// Split every query with [split_statements].
+pub fn split_queries(queries: &Map<String>) -> Map<Vec<String>> {
+    queries
+        .iter()
+        .map(|(tag, query)| (tag.clone(), split_statements(query)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_simple() {
+        assert_eq!(
+            split_statements("SELECT 1;  SELECT 2; ; SELECT 3"),
+            vec!["SELECT 1;", "SELECT 2;", "SELECT 3"]
+        );
+    }
+
+    #[test]
+    fn split_quoted() {
+        assert_eq!(
+            split_statements(r#"SELECT 'a;''b'; SELECT "x;y" FROM t;"#),
+            vec![r#"SELECT 'a;''b';"#, r#"SELECT "x;y" FROM t;"#]
+        );
+    }
+
+    #[test]
+    fn split_dollar_quoted() {
+        let query = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $$; $body$ LANGUAGE sql; SELECT $1, $$a;b$$, x$y;";
+        assert_eq!(
+            split_statements(query),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $$; $body$ LANGUAGE sql;",
+                "SELECT $1, $$a;b$$, x$y;"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_blocks() {
+        let query = "CREATE TRIGGER t AFTER INSERT ON a BEGIN\n  UPDATE b SET x = CASE WHEN 1 THEN 2 END;\n  IF x THEN y; END IF;\nEND;\nBEGIN; SELECT 1; END; begin transaction; commit;";
+        assert_eq!(
+            split_statements(query),
+            vec![
+                "CREATE TRIGGER t AFTER INSERT ON a BEGIN\n  UPDATE b SET x = CASE WHEN 1 THEN 2 END;\n  IF x THEN y; END IF;\nEND;",
+                "BEGIN;",
+                "SELECT 1;",
+                "END;",
+                "begin transaction;",
+                "commit;"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_end_case() {
+        let query = "CREATE PROCEDURE p() BEGIN CASE x WHEN 1 THEN SELECT 1; END CASE; END; SELECT 2; SELECT 3;";
+        assert_eq!(
+            split_statements(query),
+            vec![
+                "CREATE PROCEDURE p() BEGIN CASE x WHEN 1 THEN SELECT 1; END CASE; END;",
+                "SELECT 2;",
+                "SELECT 3;"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_comments() {
+        assert_eq!(
+            split_statements("SELECT 1; -- a; b\nSELECT /* ; */ 2;"),
+            vec!["SELECT 1;", "-- a; b\nSELECT /* ; */ 2;"]
+        );
+    }
+
+    #[test]
+    fn explainable() {
+        assert!(is_explainable("-- x\n/* y */ (select 1) union (select 2)"));
+        assert!(is_explainable("DELETE FROM t WHERE id = $1;"));
+        assert!(!is_explainable("selected"));
+        assert!(!is_explainable("DROP TABLE t;"));
+        assert!(!is_explainable(""));
+    }
+
+    #[test]
+    fn split_every_query() {
+        let mut queries = Map::default();
+        queries.insert("x".to_owned(), "SELECT 1; SELECT 2;".to_owned());
+        let split = split_queries(&queries);
+        assert_eq!(split.get("x").unwrap(), &vec!["SELECT 1;", "SELECT 2;"]);
+    }
+}
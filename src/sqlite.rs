@@ -0,0 +1,479 @@
+//! [rusqlite](https://docs.rs/rusqlite/) integration.
+//!
+//! ```ignore
+//! use rsyesql::sqlite::ConnectionExt;
+//!
+//! let queries = rsyesql::Queries::parse(include_str!("./queries.sql"))?;
+//! let names = conn
+//!     .yesql(&queries)
+//!     .query("select_users", rusqlite::params![], |row| row.get::<_, String>(0))?;
+//! ```
+
+use std::future::{ready, Future};
+use std::thread;
+
+use rusqlite::types::Value;
+use rusqlite::{CachedStatement, Connection, ErrorCode, Params, Row, ToSql};
+
+use indexmap::IndexMap;
+
+use crate::{is_explainable, transactions, Fixture, Queries, Query, QueryExecutor, Transaction};
+
+quick_error! {
+    /// The error type for [Yesql] connection.
+    #[derive(Debug)]
+    pub enum Error {
+        /// Query with tag is not defined.
+        UnknownTag(tag: String) {
+            display(r#"Unknown tag: "{}""#, tag)
+        }
+        /// Query preparation or execution failed.
+        Sqlite { tag: String, err: rusqlite::Error } {
+            display(r#"Failed to execute "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// `EXPLAIN QUERY PLAN` of query failed in [explain_all].
+        Explain { tag: String, err: rusqlite::Error } {
+            display(r#"Failed to explain "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Test from `-- test:` line failed in [run_fixtures].
+        Fixture { tag: String, line: usize, message: String } {
+            display(r#"Test of "{}" (line: {}) failed: {}"#, tag, line, message)
+        }
+        /// Query execution failed in [execute_all] or [execute_transactions].
+        ExecuteAll { tag: String, line: usize, err: rusqlite::Error } {
+            display(r#"Failed to execute "{}" (line: {}): {}"#, tag, line, err)
+            cause(err)
+        }
+        /// Transaction begin or commit failed.
+        Transaction(err: rusqlite::Error) {
+            display("Transaction failed: {}", err)
+            cause(err)
+        }
+    }
+}
+
+/// Extension for [Connection] with queries executed by tag.
+pub trait ConnectionExt {
+    /// Connection which executes queries by tag.
+    fn yesql<'a>(&'a self, queries: &'a Queries) -> Yesql<'a>;
+}
+
+impl ConnectionExt for Connection {
+    fn yesql<'a>(&'a self, queries: &'a Queries) -> Yesql<'a> {
+        Yesql {
+            conn: self,
+            queries,
+        }
+    }
+}
+
+/// Connection with queries, statements prepared with [Connection::prepare_cached].
+#[derive(Debug, Clone, Copy)]
+pub struct Yesql<'a> {
+    conn: &'a Connection,
+    queries: &'a Queries,
+}
+
+impl<'a> Yesql<'a> {
+    /// Cached statement for query by tag.
+    pub fn statement(&self, tag: &str) -> Result<CachedStatement<'a>, Error> {
+        let query = self
+            .queries
+            .get(tag)
+            .ok_or_else(|| Error::UnknownTag(tag.to_owned()))?;
+        self.conn
+            .prepare_cached(query)
+            .map_err(|err| sqlite_error(tag, err))
+    }
+
+    /// Execute query, returns number of modified rows.
+    pub fn execute<P: Params>(&self, tag: &str, params: P) -> Result<usize, Error> {
+        self.statement(tag)?
+            .execute(params)
+            .map_err(|err| sqlite_error(tag, err))
+    }
+
+    /// Execute query, every row converted by `f`.
+    pub fn query<T, P, F>(&self, tag: &str, params: P, f: F) -> Result<Vec<T>, Error>
+    where
+        P: Params,
+        F: FnMut(&Row<'_>) -> rusqlite::Result<T>,
+    {
+        self.statement(tag)?
+            .query_map(params, f)
+            .and_then(|rows| rows.collect())
+            .map_err(|err| sqlite_error(tag, err))
+    }
+
+    /// Execute query, first row converted by `f`.
+    pub fn query_row<T, P, F>(&self, tag: &str, params: P, f: F) -> Result<T, Error>
+    where
+        P: Params,
+        F: FnOnce(&Row<'_>) -> rusqlite::Result<T>,
+    {
+        self.statement(tag)?
+            .query_row(params, f)
+            .map_err(|err| sqlite_error(tag, err))
+    }
+
+    /// Underlying connection.
+    pub fn connection(&self) -> &'a Connection {
+        self.conn
+    }
+}
+
+/// Rows returned as column values, futures are already completed.
+impl QueryExecutor for Yesql<'_> {
+    type Params<'p> = &'p [&'p dyn ToSql];
+    type Row = Vec<Value>;
+    type Error = Error;
+
+    fn execute(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<u64, Error>> {
+        ready(Yesql::execute(self, tag, params).map(|count| count as u64))
+    }
+
+    fn query_many(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> impl Future<Output = Result<Vec<Vec<Value>>, Error>> {
+        ready(self.query(tag, params, |row| {
+            (0..row.as_ref().column_count())
+                .map(|idx| row.get(idx))
+                .collect()
+        }))
+    }
+}
+
+/// Execute every query in definition order inside transaction, for example for database scheme
+/// creation.
+///
+/// Every query can contain multiple statements. Execution stops on first failed query, transaction
+/// is rolled back.
+pub fn execute_all(conn: &mut Connection, queries: &IndexMap<String, Query>) -> Result<(), Error> {
+    let tx = conn.transaction().map_err(Error::Transaction)?;
+    for (tag, query) in queries {
+        tx.execute_batch(&query.text)
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.clone(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().map_err(Error::Transaction)
+}
+
+/// Execute queries in definition order, every group of [transactions] inside own transaction.
+///
+/// Queries of `-- tx: begin <name>` block are committed together, every query outside of block is
+/// committed alone. Execution stops on first failed query, only transaction of failed query is
+/// rolled back.
+///
+/// On busy or locked database group is executed again by [Transaction::retry].
+pub fn execute_transactions(
+    conn: &mut Connection,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error> {
+    for group in transactions(queries) {
+        let mut attempt = 0;
+        loop {
+            match (execute_group(conn, &group), group.retry()) {
+                (Err(err), Some(retry)) if attempt < retry.retries && is_transient(&err) => {
+                    thread::sleep(retry.delay(attempt));
+                    attempt += 1;
+                }
+                (result, _) => break result?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn execute_group(conn: &mut Connection, group: &Transaction<'_>) -> Result<(), Error> {
+    let tx = conn.transaction().map_err(Error::Transaction)?;
+    for &(tag, query) in &group.queries {
+        tx.execute_batch(&query.text)
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.to_owned(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().map_err(Error::Transaction)
+}
+
+// Database is busy or locked by other connection.
+fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::ExecuteAll { err, .. } | Error::Transaction(err) => matches!(
+            err.sqlite_error_code(),
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+        ),
+        _ => false,
+    }
+}
+
+/// `EXPLAIN QUERY PLAN` of every [is_explainable] query, for example to check queries against
+/// schema without data. Queries are not executed, parameters are bound to `NULL`.
+///
+/// Plan is `detail` column of rows joined by `\n`, queries which are not explainable are skipped.
+pub fn explain_all(
+    conn: &Connection,
+    queries: &IndexMap<String, Query>,
+) -> IndexMap<String, Result<String, Error>> {
+    queries
+        .iter()
+        .filter(|(_, query)| is_explainable(&query.text))
+        .map(|(tag, query)| {
+            let plan = explain(conn, &query.text).map_err(|err| Error::Explain {
+                tag: tag.clone(),
+                err,
+            });
+            (tag.clone(), plan)
+        })
+        .collect()
+}
+
+fn explain(conn: &Connection, query: &str) -> rusqlite::Result<String> {
+    let mut statement = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", query))?;
+    let params = vec![Value::Null; statement.parameter_count()];
+    let plan = statement
+        .query_map(rusqlite::params_from_iter(params), |row| {
+            row.get::<_, String>(3)
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(plan.join("\n"))
+}
+
+/// Run [Query::fixtures] against test database, returns failed tests.
+///
+/// Every test is executed in own transaction, which is rolled back. Parameter values are bound as
+/// `NULL` (`null`), integer, real or text (quotes `'...'` are removed).
+///
+/// ```
+/// let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+/// conn.execute_batch("CREATE TABLE users (id INTEGER PRIMARY KEY); INSERT INTO users VALUES (1);")
+///     .unwrap();
+/// let text = "-- name: select_user\n-- test: id=1 expect_rows=1\n-- test: id=2 expect_rows=0\nSELECT * FROM users WHERE id = :id;";
+/// let queries = rsyesql::parse_detailed(text).unwrap();
+/// assert!(rsyesql::sqlite::run_fixtures(&mut conn, &queries).is_empty());
+/// ```
+pub fn run_fixtures(conn: &mut Connection, queries: &IndexMap<String, Query>) -> Vec<Error> {
+    let mut failures = vec![];
+    for (tag, query) in queries {
+        for fixture in &query.fixtures {
+            let result = conn
+                .transaction()
+                .and_then(|tx| count_rows(&tx, &query.text, fixture));
+            if let Err(message) = fixture.check(result) {
+                failures.push(Error::Fixture {
+                    tag: tag.clone(),
+                    line: fixture.line,
+                    message,
+                });
+            }
+        }
+    }
+    failures
+}
+
+// Number of returned rows, or changed rows for query without columns.
+fn count_rows(conn: &Connection, query: &str, fixture: &Fixture) -> rusqlite::Result<usize> {
+    let mut statement = conn.prepare(query)?;
+    for (name, value) in &fixture.params {
+        let idx = match name.parse::<usize>() {
+            Ok(idx) => Some(idx),
+            Err(_) => [":", "@", "$"]
+                .iter()
+                .find_map(|prefix| {
+                    statement
+                        .parameter_index(&format!("{}{}", prefix, name))
+                        .transpose()
+                })
+                .transpose()?,
+        };
+        let idx = idx.ok_or_else(|| rusqlite::Error::InvalidParameterName(name.clone()))?;
+        statement.raw_bind_parameter(idx, fixture_value(value))?;
+    }
+    if statement.column_count() == 0 {
+        return statement.raw_execute();
+    }
+    let mut rows = statement.raw_query();
+    let mut count = 0;
+    while rows.next()?.is_some() {
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn fixture_value(value: &str) -> Value {
+    if value.eq_ignore_ascii_case("null") {
+        Value::Null
+    } else if let Ok(value) = value.parse() {
+        Value::Integer(value)
+    } else if let Ok(value) = value.parse() {
+        Value::Real(value)
+    } else {
+        let value = value
+            .strip_prefix('\'')
+            .and_then(|value| value.strip_suffix('\''))
+            .unwrap_or(value);
+        Value::Text(value.to_owned())
+    }
+}
+
+fn sqlite_error(tag: &str, err: rusqlite::Error) -> Error {
+    Error::Sqlite {
+        tag: tag.to_owned(),
+        err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    const TEXT: &str = "-- name: create
+CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+-- name: insert
+INSERT INTO users (name) VALUES (?1);
+-- name: select
+SELECT name FROM users ORDER BY id;";
+
+    #[test]
+    fn execute_and_query() {
+        let queries = Queries::parse(TEXT).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let db = conn.yesql(&queries);
+        db.execute("create", params![]).unwrap();
+        assert_eq!(db.execute("insert", params!["alice"]).unwrap(), 1);
+        assert_eq!(db.execute("insert", params!["bob"]).unwrap(), 1);
+
+        let names = db
+            .query("select", params![], |row| row.get::<_, String>(0))
+            .unwrap();
+        assert_eq!(names, vec!["alice", "bob"]);
+
+        let name = db
+            .query_row("select", params![], |row| row.get::<_, String>(0))
+            .unwrap();
+        assert_eq!(name, "alice");
+    }
+
+    #[test]
+    fn query_executor() {
+        let queries = Queries::parse(TEXT).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let db = conn.yesql(&queries);
+        db.execute("create", params![]).unwrap();
+        db.execute("insert", params!["alice"]).unwrap();
+
+        let rows = QueryExecutor::query_many(&db, "select", &[]);
+        let rows = futures_ready(rows).unwrap();
+        assert_eq!(rows, vec![vec![Value::Text("alice".to_owned())]]);
+    }
+
+    // Futures of sqlite executor are always ready.
+    fn futures_ready<F: Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Waker};
+        let mut future = std::pin::pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("sqlite future is not ready"),
+        }
+    }
+
+    #[test]
+    fn execute_all_in_transaction() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let text = "-- name: create\nCREATE TABLE t (id INTEGER);\n-- name: insert\nINSERT INTO t VALUES (1);\n-- name: broken\nINSERT INTO x VALUES (1);";
+        let queries = crate::parse_detailed(text).unwrap();
+        let result = execute_all(&mut conn, &queries);
+        assert!(matches!(result, Err(Error::ExecuteAll { tag, line: 5, .. }) if tag == "broken"));
+        assert!(conn.execute_batch("SELECT * FROM t").is_err());
+
+        let text = "-- name: create\nCREATE TABLE t (id INTEGER);\nINSERT INTO t VALUES (1);";
+        execute_all(&mut conn, &crate::parse_detailed(text).unwrap()).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM t", params![], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn execute_transaction_groups() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let text = "-- name: create\nCREATE TABLE t (id INTEGER);\n-- tx: begin fill\n-- name: insert\nINSERT INTO t VALUES (1);\n-- name: broken\nINSERT INTO x VALUES (1);\n-- tx: end";
+        let queries = crate::ParseOptions::new()
+            .transactions(true)
+            .parse_detailed(text)
+            .unwrap();
+        let result = execute_transactions(&mut conn, &queries);
+        assert!(matches!(result, Err(Error::ExecuteAll { tag, line: 6, .. }) if tag == "broken"));
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM t", params![], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn explain_queries() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);")
+            .unwrap();
+        let text = "-- name: create\nCREATE TABLE u (id INTEGER);\n-- name: select\nSELECT name FROM t WHERE id = :id;\n-- name: dropped\nSELECT deleted FROM t;";
+        let plans = explain_all(&conn, &crate::parse_detailed(text).unwrap());
+        assert_eq!(plans.keys().collect::<Vec<_>>(), ["select", "dropped"]);
+        assert!(plans["select"].as_ref().unwrap().contains("SEARCH t"));
+        assert!(matches!(&plans["dropped"], Err(Error::Explain { tag, .. }) if tag == "dropped"));
+    }
+
+    #[test]
+    fn fixtures() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);")
+            .unwrap();
+        let text = "-- name: insert\n-- test: 1=1 2='a b' expect_rows=1\n-- test: 1=x 2=null expect_error\nINSERT INTO t VALUES (?, ?);\n-- name: select\n-- test: id=1 expect_rows>0\n-- test: other=1\n-- test: expect_rows<1\nSELECT * FROM t WHERE id = @id;";
+        let queries = crate::parse_detailed(text).unwrap();
+        // Rows inserted by tests are rolled back.
+        let failures = run_fixtures(&mut conn, &queries)
+            .into_iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failures,
+            [
+                r#"Test of "select" (line: 6) failed: expected rows > 0, got 0"#,
+                r#"Test of "select" (line: 7) failed: query failed: Invalid parameter name: other"#,
+            ]
+        );
+        assert_eq!(fixture_value("'a b'"), Value::Text("a b".to_owned()));
+        assert_eq!(fixture_value("1.5"), Value::Real(1.5));
+    }
+
+    #[test]
+    fn transient_errors() {
+        let busy = rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(5), None);
+        assert!(is_transient(&Error::Transaction(busy)));
+        let err = rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), None);
+        assert!(!is_transient(&Error::Transaction(err)));
+    }
+
+    #[test]
+    fn unknown_tag() {
+        let queries = Queries::parse(TEXT).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let result = conn.yesql(&queries).execute("delete", params![]);
+        assert!(matches!(result, Err(Error::UnknownTag(tag)) if tag == "delete"));
+    }
+}
@@ -0,0 +1,546 @@
+//! [sqlx](https://docs.rs/sqlx/) integration.
+//!
+//! ```ignore
+//! let queries = rsyesql::parse(include_str!("./queries.sql"))?;
+//! let db = rsyesql::sqlx::SqlxQueries::new(pool, &queries);
+//! db.describe().await?; // every query prepared against database
+//!
+//! let mut args = sqlx::postgres::PgArguments::default();
+//! args.add(user_id)?;
+//! let users: Vec<User> = db.fetch_all_as("select_user", args).await?;
+//! ```
+
+use std::sync::Arc;
+
+use ::sqlx::{
+    Arguments, AssertSqlSafe, Database, Encode, Executor, FromRow, IntoArguments, Pool, SqlSafeStr,
+    Statement, Type,
+};
+use indexmap::IndexMap;
+
+use crate::{
+    is_explainable, positional, transactions, Fixture, Placeholder, Query, QueryExecutor,
+    Transaction,
+};
+
+quick_error! {
+    /// The error type for [SqlxQueries].
+    #[derive(Debug)]
+    pub enum Error {
+        /// Query with tag is not defined.
+        UnknownTag(tag: String) {
+            display(r#"Unknown tag: "{}""#, tag)
+        }
+        /// Query preparation or execution failed.
+        Sqlx { tag: String, err: ::sqlx::Error } {
+            display(r#"Failed to execute "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Query execution failed in [execute_all] or [execute_transactions].
+        ExecuteAll { tag: String, line: usize, err: ::sqlx::Error } {
+            display(r#"Failed to execute "{}" (line: {}): {}"#, tag, line, err)
+            cause(err)
+        }
+        /// Transaction begin or commit failed.
+        Transaction(err: ::sqlx::Error) {
+            display("Transaction failed: {}", err)
+            cause(err)
+        }
+        /// Number of changed rows is requested by [QueryExecutor::execute] without
+        /// [SqlxQueries::rows_affected].
+        RowsAffected(tag: String) {
+            display(r#"Number of changed rows of "{}" is unknown"#, tag)
+        }
+        /// `EXPLAIN` of query failed in [explain_all].
+        Explain { tag: String, err: ::sqlx::Error } {
+            display(r#"Failed to explain "{}": {}"#, tag, err)
+            cause(err)
+        }
+        /// Test of [Query::fixtures] failed in [run_fixtures].
+        Fixture { tag: String, line: usize, message: String } {
+            display(r#"Test of "{}" (line: {}) failed: {}"#, tag, line, message)
+        }
+    }
+}
+
+/// Pool with queries executed by tag.
+#[derive(Debug)]
+pub struct SqlxQueries<DB: Database> {
+    pool: Pool<DB>,
+    queries: IndexMap<String, Arc<str>>,
+    rows_affected: Option<fn(&DB::QueryResult) -> u64>,
+}
+
+impl<DB: Database> Clone for SqlxQueries<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            queries: self.queries.clone(),
+            rows_affected: self.rows_affected,
+        }
+    }
+}
+
+impl<DB> SqlxQueries<DB>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    /// Pair pool with queries.
+    pub fn new<I, T, Q>(pool: Pool<DB>, queries: I) -> Self
+    where
+        I: IntoIterator<Item = (T, Q)>,
+        T: AsRef<str>,
+        Q: AsRef<str>,
+    {
+        let queries = queries
+            .into_iter()
+            .map(|(tag, query)| (tag.as_ref().to_owned(), Arc::from(query.as_ref())))
+            .collect();
+        Self {
+            pool,
+            queries,
+            rows_affected: None,
+        }
+    }
+
+    /// Set function which reads number of changed rows from database specific result, like
+    /// `PgQueryResult::rows_affected`, required by [QueryExecutor::execute].
+    pub fn rows_affected(mut self, rows_affected: fn(&DB::QueryResult) -> u64) -> Self {
+        self.rows_affected = Some(rows_affected);
+        self
+    }
+
+    /// Prepare every query against database, returns statements with information about
+    /// parameters and columns.
+    ///
+    /// Useful on startup: references to unknown tables or columns found before first execution.
+    pub async fn describe(&self) -> Result<IndexMap<String, DB::Statement>, Error> {
+        let mut statements = IndexMap::new();
+        for (tag, query) in &self.queries {
+            let statement = self
+                .pool
+                .prepare(AssertSqlSafe(Arc::clone(query)).into_sql_str())
+                .await
+                .map_err(|err| sqlx_error(tag, err))?;
+            statements.insert(tag.clone(), statement);
+        }
+        Ok(statements)
+    }
+
+    /// Execute query, returns database specific result.
+    pub async fn execute<A>(&self, tag: &str, args: A) -> Result<DB::QueryResult, Error>
+    where
+        A: IntoArguments<DB> + 'static,
+    {
+        let query = self.query(tag)?;
+        ::sqlx::query_with(AssertSqlSafe(query), args)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| sqlx_error(tag, err))
+    }
+
+    /// Execute query, every row converted to `T`.
+    pub async fn fetch_all_as<T, A>(&self, tag: &str, args: A) -> Result<Vec<T>, Error>
+    where
+        T: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        A: IntoArguments<DB> + 'static,
+    {
+        let query = self.query(tag)?;
+        ::sqlx::query_as_with(AssertSqlSafe(query), args)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| sqlx_error(tag, err))
+    }
+
+    /// Execute query, first row converted to `T`.
+    pub async fn fetch_one_as<T, A>(&self, tag: &str, args: A) -> Result<T, Error>
+    where
+        T: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        A: IntoArguments<DB> + 'static,
+    {
+        let query = self.query(tag)?;
+        ::sqlx::query_as_with(AssertSqlSafe(query), args)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| sqlx_error(tag, err))
+    }
+
+    /// Underlying pool.
+    pub fn pool(&self) -> &Pool<DB> {
+        &self.pool
+    }
+
+    fn query(&self, tag: &str) -> Result<Arc<str>, Error> {
+        self.queries
+            .get(tag)
+            .cloned()
+            .ok_or_else(|| Error::UnknownTag(tag.to_owned()))
+    }
+}
+
+/// Rows returned as database specific rows, parameters are database specific arguments.
+impl<DB> QueryExecutor for SqlxQueries<DB>
+where
+    DB: Database,
+    DB::Arguments: IntoArguments<DB> + 'static,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    type Params<'p> = DB::Arguments;
+    type Row = DB::Row;
+    type Error = Error;
+
+    async fn execute(&self, tag: &str, params: Self::Params<'_>) -> Result<u64, Error> {
+        let rows_affected = self
+            .rows_affected
+            .ok_or_else(|| Error::RowsAffected(tag.to_owned()))?;
+        SqlxQueries::execute(self, tag, params)
+            .await
+            .map(|result| rows_affected(&result))
+    }
+
+    async fn query_many(&self, tag: &str, params: Self::Params<'_>) -> Result<Vec<DB::Row>, Error> {
+        let query = self.query(tag)?;
+        ::sqlx::query_with(AssertSqlSafe(query), params)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| sqlx_error(tag, err))
+    }
+
+    async fn query_one(
+        &self,
+        tag: &str,
+        params: Self::Params<'_>,
+    ) -> Result<Option<DB::Row>, Error> {
+        let query = self.query(tag)?;
+        ::sqlx::query_with(AssertSqlSafe(query), params)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| sqlx_error(tag, err))
+    }
+}
+
+/// Execute every query in definition order inside transaction, for example for database scheme
+/// creation.
+///
+/// Every query can contain multiple statements. Execution stops on first failed query, transaction
+/// is rolled back.
+pub async fn execute_all<DB>(
+    pool: &Pool<DB>,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let mut tx = pool.begin().await.map_err(Error::Transaction)?;
+    for (tag, query) in queries {
+        ::sqlx::raw_sql(AssertSqlSafe(query.text.as_str()))
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.clone(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().await.map_err(Error::Transaction)
+}
+
+/// Execute queries in definition order, every group of [transactions] inside own transaction.
+///
+/// Queries of `-- tx: begin <name>` block are committed together, every query outside of block is
+/// committed alone. Execution stops on first failed query, only transaction of failed query is
+/// rolled back.
+///
+/// On serialization failure or deadlock group is executed again by [Transaction::retry].
+pub async fn execute_transactions<DB>(
+    pool: &Pool<DB>,
+    queries: &IndexMap<String, Query>,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    for group in transactions(queries) {
+        let mut attempt = 0;
+        loop {
+            match (execute_group(pool, &group).await, group.retry()) {
+                (Err(err), Some(retry)) if attempt < retry.retries && is_transient(&err) => {
+                    tokio::time::sleep(retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                (result, _) => break result?,
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn execute_group<DB>(pool: &Pool<DB>, group: &Transaction<'_>) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let mut tx = pool.begin().await.map_err(Error::Transaction)?;
+    for &(tag, query) in &group.queries {
+        ::sqlx::raw_sql(AssertSqlSafe(query.text.as_str()))
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| Error::ExecuteAll {
+                tag: tag.to_owned(),
+                line: query.line,
+                err,
+            })?;
+    }
+    tx.commit().await.map_err(Error::Transaction)
+}
+
+// Serialization failure (`40001`) or deadlock (`40P01`) SQLSTATE, transaction can be executed
+// again.
+fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::ExecuteAll { err, .. } | Error::Transaction(err) => matches!(
+            err.as_database_error()
+                .and_then(|err| err.code())
+                .as_deref(),
+            Some("40001" | "40P01")
+        ),
+        _ => false,
+    }
+}
+
+/// `EXPLAIN` of every [is_explainable] query, for example to check queries against schema
+/// without data. Queries are not executed, named parameters translated by [positional] with
+/// `placeholder` of database and bound to `NULL`.
+///
+/// `EXPLAIN` syntax and output are database specific: `prefix` is prepended to query and `plan`
+/// reads line of plan from every returned row, lines are joined by `\n`. Queries which are not
+/// explainable are skipped.
+///
+/// ```ignore
+/// use sqlx::Row;
+///
+/// let plans = rsyesql::sqlx::explain_all(
+///     &pool,
+///     &queries,
+///     "EXPLAIN",
+///     Placeholder::Dollar,
+///     |row| row.try_get::<String, _>(0),
+/// )
+/// .await;
+/// ```
+pub async fn explain_all<DB, F>(
+    pool: &Pool<DB>,
+    queries: &IndexMap<String, Query>,
+    prefix: &str,
+    placeholder: Placeholder,
+    plan: F,
+) -> IndexMap<String, Result<String, Error>>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> Option<String>: Encode<'q, DB> + Type<DB>,
+    DB::Arguments: IntoArguments<DB>,
+    F: Fn(&DB::Row) -> Result<String, ::sqlx::Error>,
+{
+    let mut plans = IndexMap::new();
+    for (tag, query) in queries {
+        if !is_explainable(&query.text) {
+            continue;
+        }
+        let result = explain(pool, &query.text, prefix, placeholder, &plan)
+            .await
+            .map_err(|err| Error::Explain {
+                tag: tag.clone(),
+                err,
+            });
+        plans.insert(tag.clone(), result);
+    }
+    plans
+}
+
+async fn explain<DB, F>(
+    pool: &Pool<DB>,
+    query: &str,
+    prefix: &str,
+    placeholder: Placeholder,
+    plan: &F,
+) -> Result<String, ::sqlx::Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> Option<String>: Encode<'q, DB> + Type<DB>,
+    DB::Arguments: IntoArguments<DB>,
+    F: Fn(&DB::Row) -> Result<String, ::sqlx::Error>,
+{
+    let query = positional(query, placeholder);
+    let mut args = DB::Arguments::default();
+    for _ in &query.params {
+        args.add(None::<String>).map_err(::sqlx::Error::Encode)?;
+    }
+    let text = format!("{} {}", prefix, query.text);
+    let lines = ::sqlx::query_with(AssertSqlSafe(text), args)
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(plan)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// Run [Query::fixtures] against test database, returns failed tests.
+///
+/// Every test is executed in own transaction, which is rolled back. Named parameters are bound by
+/// [positional] with `placeholder` of database, numbers are bound as `i64` or `f64`, other values
+/// as `String` (`null` is `NULL`, quotes `'...'` are removed). Number of changed rows is not
+/// available for generic database, so `rows_affected` reads it from result of query without
+/// columns.
+///
+/// ```ignore
+/// use sqlx::postgres::PgQueryResult;
+///
+/// let failures = rsyesql::sqlx::run_fixtures(
+///     &pool,
+///     &queries,
+///     Placeholder::Dollar,
+///     PgQueryResult::rows_affected,
+/// )
+/// .await;
+/// assert!(failures.is_empty(), "{:?}", failures);
+/// ```
+pub async fn run_fixtures<DB, F>(
+    pool: &Pool<DB>,
+    queries: &IndexMap<String, Query>,
+    placeholder: Placeholder,
+    rows_affected: F,
+) -> Vec<Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> f64: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    DB::Arguments: IntoArguments<DB>,
+    F: Fn(&DB::QueryResult) -> u64,
+{
+    let mut failures = vec![];
+    for (tag, query) in queries {
+        for fixture in &query.fixtures {
+            let result = count_rows(pool, &query.text, fixture, placeholder, &rows_affected).await;
+            if let Err(message) = fixture.check(result) {
+                failures.push(Error::Fixture {
+                    tag: tag.clone(),
+                    line: fixture.line,
+                    message,
+                });
+            }
+        }
+    }
+    failures
+}
+
+// Number of returned rows, or changed rows for query without columns.
+async fn count_rows<DB, F>(
+    pool: &Pool<DB>,
+    query: &str,
+    fixture: &Fixture,
+    placeholder: Placeholder,
+    rows_affected: &F,
+) -> Result<usize, ::sqlx::Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> f64: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    DB::Arguments: IntoArguments<DB>,
+    F: Fn(&DB::QueryResult) -> u64,
+{
+    let query = positional(query, placeholder);
+    let mut args = DB::Arguments::default();
+    for (idx, param) in query.params.iter().enumerate() {
+        let number = (idx + 1).to_string();
+        let value = fixture
+            .params
+            .iter()
+            .find(|(name, _)| *name == number || name == param)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.eq_ignore_ascii_case("null"));
+        let added = match value {
+            None => args.add(None::<String>),
+            Some(value) => {
+                if let Ok(value) = value.parse::<i64>() {
+                    args.add(value)
+                } else if let Ok(value) = value.parse::<f64>() {
+                    args.add(value)
+                } else {
+                    let value = value
+                        .strip_prefix('\'')
+                        .and_then(|value| value.strip_suffix('\''))
+                        .unwrap_or(value);
+                    args.add(value.to_owned())
+                }
+            }
+        };
+        added.map_err(::sqlx::Error::Encode)?;
+    }
+
+    let mut tx = pool.begin().await?;
+    let text = Arc::<str>::from(query.text);
+    let statement = (&mut *tx)
+        .prepare(AssertSqlSafe(Arc::clone(&text)).into_sql_str())
+        .await?;
+    let query = ::sqlx::query_with(AssertSqlSafe(text), args);
+    let rows = if statement.columns().is_empty() {
+        rows_affected(&query.execute(&mut *tx).await?) as usize
+    } else {
+        query.fetch_all(&mut *tx).await?.len()
+    };
+    tx.rollback().await?;
+    Ok(rows)
+}
+
+fn sqlx_error(tag: &str, err: ::sqlx::Error) -> Error {
+    Error::Sqlx {
+        tag: tag.to_owned(),
+        err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_errors() {
+        assert!(!is_transient(&Error::UnknownTag("x".to_owned())));
+        assert!(!is_transient(&Error::Transaction(
+            ::sqlx::Error::PoolTimedOut
+        )));
+        assert!(!is_transient(&Error::ExecuteAll {
+            tag: "x".to_owned(),
+            line: 1,
+            err: ::sqlx::Error::RowNotFound,
+        }));
+    }
+
+    #[test]
+    fn error_display() {
+        assert_eq!(
+            Error::RowsAffected("insert".to_owned()).to_string(),
+            r#"Number of changed rows of "insert" is unknown"#
+        );
+        assert_eq!(
+            Error::Fixture {
+                tag: "select".to_owned(),
+                line: 3,
+                message: "2 rows".to_owned(),
+            }
+            .to_string(),
+            r#"Test of "select" (line: 3) failed: 2 rows"#
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Helpers for tests of SQL files.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Environment variable, if set snapshots are overwritten instead of compared.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "RSYESQL_UPDATE_SNAPSHOTS";
+
+/// Compare queries with snapshot file, panics with diff on mismatch.
+///
+/// Queries serialized in canonical form: sorted by tag, every query as `-- name: tag` line
+/// followed by query text and empty line. So moving queries between and inside files does not
+/// change snapshot, but change of any query does.
+///
+/// Snapshot is created if file does not exist, or overwritten if `RSYESQL_UPDATE_SNAPSHOTS`
+/// environment variable is set.
+///
+/// ```ignore
+/// #[test]
+/// fn queries_snapshot() {
+///     let queries = rsyesql::parse(include_str!("../sql/queries.sql")).unwrap();
+///     rsyesql::testing::assert_queries_snapshot(&queries, "tests/snapshots/queries.snap");
+/// }
+/// ```
+pub fn assert_queries_snapshot<I, T, Q, P>(queries: I, path: P)
+where
+    I: IntoIterator<Item = (T, Q)>,
+    T: AsRef<str>,
+    Q: AsRef<str>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let actual = canonical(queries);
+
+    if env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .unwrap_or_else(|e| panic!("Failed to create {}: {}", dir.display(), e));
+        }
+        fs::write(path, &actual)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    if expected != actual {
+        panic!(
+            "Queries do not match snapshot {} (set {} to update):\n{}",
+            path.display(),
+            UPDATE_SNAPSHOTS_ENV,
+            diff(&expected, &actual)
+        );
+    }
+}
+
+// Queries sorted by tag.
+fn canonical<I, T, Q>(queries: I) -> String
+where
+    I: IntoIterator<Item = (T, Q)>,
+    T: AsRef<str>,
+    Q: AsRef<str>,
+{
+    let mut queries = queries.into_iter().collect::<Vec<_>>();
+    queries.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+
+    let mut text = String::new();
+    for (tag, query) in queries {
+        writeln!(text, "-- name: {}\n{}\n", tag.as_ref(), query.as_ref()).unwrap();
+    }
+    text
+}
+
+// Line diff based on longest common subsequence, removed lines prefixed by `-`, added by `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let old = expected.lines().collect::<Vec<_>>();
+    let new = actual.lines().collect::<Vec<_>>();
+
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut text = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            writeln!(text, "  {}", old[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            writeln!(text, "+ {}", new[j]).unwrap();
+            j += 1;
+        } else {
+            writeln!(text, "- {}", old[i]).unwrap();
+            i += 1;
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_sorted() {
+        let queries = vec![("b", "SELECT 2;"), ("a", "SELECT 1;")];
+        assert_eq!(
+            canonical(queries),
+            "-- name: a\nSELECT 1;\n\n-- name: b\nSELECT 2;\n\n"
+        );
+    }
+
+    #[test]
+    fn diff_lines() {
+        assert_eq!(diff("a\nb\nc", "a\nx\nc"), "  a\n+ x\n- b\n  c\n");
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let path = env::temp_dir().join(format!("rsyesql-{}.snap", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_queries_snapshot(vec![("a", "SELECT 1;")], &path);
+        assert_queries_snapshot(vec![("a", "SELECT 1;")], &path);
+        let result = std::panic::catch_unwind(|| {
+            assert_queries_snapshot(vec![("a", "SELECT 2;")], &path);
+        });
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
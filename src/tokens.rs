@@ -0,0 +1,142 @@
+use alloc::borrow::Cow;
+use core::ops::Range;
+
+use crate::{parse_line, split_lines, LineType, ParseOptions};
+
+/// Kind of line, see [Token].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// Line with tag, like `-- name: select_users`.
+    TagLine {
+        /// Byte range of tag in text.
+        tag: Range<usize>,
+    },
+    /// Line with query text, comment after query is part of line.
+    QueryLine,
+    /// Line with comment only: `--` comment, annotation (`-- returns:`, `-- if:`, ...), line of
+    /// `/* ... */` comment or script comment (`REM`).
+    Comment,
+    /// Empty line or line with whitespace only.
+    BlankLine,
+}
+
+/// Line of SQL text classified by parser rules, see [tokens].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// Kind of line.
+    pub kind: TokenKind,
+    /// Byte range of line in text without line ending.
+    pub span: Range<usize>,
+    /// Line number (1-based).
+    pub line: usize,
+}
+
+/// Iterator over lines of text, see [ParseOptions::tokens].
+#[derive(Debug)]
+pub struct Tokens<'a> {
+    text: &'a str,
+    // Text with `/* ... */` comments replaced by spaces, positions are same as in text.
+    code: Cow<'a, str>,
+    options: Cow<'a, ParseOptions>,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str, options: Cow<'a, ParseOptions>) -> Self {
+        Self {
+            text,
+            code: options.language.remove_block_comments(text),
+            options,
+            pos: if text.starts_with('\u{feff}') { 3 } else { 0 },
+            line: 0,
+        }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let (code, ending) = split_lines(&self.code[self.pos..]).next()?;
+        let start = self.pos;
+        let end = start + code.len();
+        self.pos = end + ending.len();
+        self.line += 1;
+
+        let kind = match parse_line(code, &self.options) {
+            (LineType::Tag, tag) => {
+                let offset = start + (tag.as_ptr() as usize - code.as_ptr() as usize);
+                TokenKind::TagLine {
+                    tag: offset..offset + tag.len(),
+                }
+            }
+            (LineType::Query, _) => TokenKind::QueryLine,
+            (LineType::Empty, _) if self.text[start..end].trim().is_empty() => TokenKind::BlankLine,
+            (LineType::Empty, _) => TokenKind::Comment,
+        };
+        Some(Token {
+            kind,
+            span: start..end,
+            line: self.line,
+        })
+    }
+}
+
+impl ParseOptions {
+    /// Lines of text classified with these options (tag marker, script comments), see [tokens].
+    pub fn tokens<'a>(&'a self, text: &'a str) -> Tokens<'a> {
+        Tokens::new(text, Cow::Borrowed(self))
+    }
+}
+
+/// Lines of text classified by same rules as parser: tag lines, query lines, comments and blank
+/// lines with byte ranges, for formatters, linters and editor tooling.
+///
+/// Byte order mark is skipped. Line endings are `\n`, `\r\n` and `\r`.
+///
+/// ```
+/// use rsyesql::TokenKind;
+///
+/// let text = "-- Users.\n-- name: select\nSELECT 1; -- one\n\n";
+/// let tokens = rsyesql::tokens(text).collect::<Vec<_>>();
+/// assert_eq!(tokens[0].kind, TokenKind::Comment);
+/// assert_eq!(tokens[1].kind, TokenKind::TagLine { tag: 19..25 });
+/// assert_eq!(&text[tokens[2].span.clone()], "SELECT 1; -- one");
+/// assert_eq!(tokens[3].kind, TokenKind::BlankLine);
+/// ```
+pub fn tokens(text: &str) -> Tokens<'_> {
+    Tokens::new(text, Cow::Owned(ParseOptions::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn classified_lines() {
+        let text = "\u{feff}--name:a\r\n  /* doc\n  */\r  \nSELECT /* x */ 1;\n-- returns: id:int";
+        let tokens = tokens(text)
+            .map(|token| (token.kind, &text[token.span], token.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::TagLine { tag: 10..11 }, "--name:a", 1),
+                (TokenKind::Comment, "  /* doc", 2),
+                (TokenKind::Comment, "  */", 3),
+                (TokenKind::BlankLine, "  ", 4),
+                (TokenKind::QueryLine, "SELECT /* x */ 1;", 5),
+                (TokenKind::Comment, "-- returns: id:int", 6),
+            ]
+        );
+
+        let options = ParseOptions::new().script(crate::Script::SqlPlus);
+        let kinds = options
+            .tokens("REM x\nSELECT 1;")
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [TokenKind::Comment, TokenKind::QueryLine]);
+    }
+}
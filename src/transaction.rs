@@ -0,0 +1,99 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Map, Query, Retry};
+
+/// Consecutive queries executed in one transaction, see [transactions].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction<'a> {
+    /// Name from `-- tx: begin <name>` line, `None` for query outside of block.
+    pub name: Option<&'a str>,
+    /// Tags with queries in definition order.
+    pub queries: Vec<(&'a str, &'a Query)>,
+}
+
+impl Transaction<'_> {
+    /// Retry policy of first query with [Query::retry].
+    pub fn retry(&self) -> Option<Retry> {
+        self.queries.iter().find_map(|(_, query)| query.retry())
+    }
+}
+
+/// Group consecutive queries by block of [Query::transaction] (parsed with
+/// [ParseOptions::transactions](crate::ParseOptions::transactions)), every query outside of
+/// `-- tx: begin` / `-- tx: end` block is own group. Blocks with same name are separate groups.
+///
+/// ```
+/// let text = "-- tx: begin users\n-- name: create\nCREATE TABLE users (id INT);\n-- name: index\nCREATE INDEX users_id ON users (id);\n-- tx: end\n-- name: select\nSELECT 1;";
+/// let options = rsyesql::ParseOptions::new().transactions(true);
+/// let queries = options.parse_detailed(text).unwrap();
+/// let transactions = rsyesql::transactions(&queries);
+/// assert_eq!(transactions.len(), 2);
+/// assert_eq!(transactions[0].name, Some("users"));
+/// assert_eq!(transactions[0].queries.len(), 2);
+/// assert_eq!(transactions[1].name, None);
+/// ```
+pub fn transactions(queries: &Map<Query>) -> Vec<Transaction<'_>> {
+    // Transactions with index of block.
+    let mut transactions: Vec<(Option<usize>, Transaction<'_>)> = vec![];
+    for (tag, query) in queries {
+        let block = query.transaction.as_ref().map(|(block, _)| *block);
+        match (transactions.last_mut(), block) {
+            (Some((Some(last), transaction)), Some(block)) if *last == block => {
+                transaction.queries.push((tag, query));
+            }
+            _ => transactions.push((
+                block,
+                Transaction {
+                    name: query.transaction.as_ref().map(|(_, name)| name.as_str()),
+                    queries: vec![(tag, query)],
+                },
+            )),
+        }
+    }
+    transactions
+        .into_iter()
+        .map(|(_, transaction)| transaction)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    #[test]
+    fn grouped() {
+        let text = "-- name: a\nSELECT 1;\n-- name: b\nSELECT 2;\n-- tx: begin x\n-- name: c\nSELECT 3;\n-- name: d\nSELECT 4;\n-- tx: end\n-- tx: begin x\n-- name: e\nSELECT 5;\n-- tx: end";
+        let queries = ParseOptions::new()
+            .transactions(true)
+            .parse_detailed(text)
+            .unwrap();
+        let groups = transactions(&queries)
+            .into_iter()
+            .map(|tx| {
+                let tags = tx.queries.iter().map(|(tag, _)| *tag).collect::<Vec<_>>();
+                (tx.name, tags)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                (None, vec!["a"]),
+                (None, vec!["b"]),
+                (Some("x"), vec!["c", "d"]),
+                (Some("x"), vec!["e"]),
+            ]
+        );
+
+        let text =
+            "-- tx: begin x\n-- name: a\nSELECT 1;\n-- name: b retries=2\nSELECT 2;\n-- tx: end";
+        let queries = ParseOptions::new()
+            .attributes(true)
+            .transactions(true)
+            .parse_detailed(text)
+            .unwrap();
+        let retry = transactions(&queries)[0].retry().unwrap();
+        assert_eq!(retry.retries, 2);
+    }
+}
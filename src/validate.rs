@@ -0,0 +1,120 @@
+use std::fmt;
+
+use indexmap::IndexMap;
+use regex::Regex;
+pub use sqlparser::dialect::Dialect;
+use sqlparser::parser::{Parser, ParserError};
+
+use crate::Query;
+
+/// Syntax error in query found by [validate].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// Tag of invalid query.
+    pub tag: String,
+    /// Source line (1-based) of error, or line of tag if position is not known.
+    pub line: usize,
+    /// Error message from SQL parser.
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"Invalid query "{}" (line: {}): {}"#,
+            self.tag, self.line, self.message
+        )
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check syntax of every query with [sqlparser](https://docs.rs/sqlparser/).
+///
+/// Returns errors for every invalid query, in definition order.
+///
+/// # Example
+///
+/// ```ignore
+/// use sqlparser::dialect::PostgreSqlDialect;
+///
+/// let queries = rsyesql::parse_detailed(include_str!("./queries.sql"))?;
+/// for error in rsyesql::validate(&queries, &PostgreSqlDialect {}) {
+///     eprintln!("{}", error);
+/// }
+/// ```
+pub fn validate(queries: &IndexMap<String, Query>, dialect: &dyn Dialect) -> Vec<ValidationError> {
+    queries
+        .iter()
+        .filter_map(|(tag, query)| {
+            Parser::parse_sql(dialect, &query.text)
+                .err()
+                .map(|error| to_validation_error(tag, query, error))
+        })
+        .collect()
+}
+
+fn to_validation_error(tag: &str, query: &Query, error: ParserError) -> ValidationError {
+    lazy_static! {
+        static ref RE_LOCATION: Regex = Regex::new(r#" at Line: (\d+), Column: (\d+)"#).unwrap();
+    }
+
+    let mut message = match error {
+        ParserError::TokenizerError(message) | ParserError::ParserError(message) => message,
+        error => error.to_string(),
+    };
+
+    let mut line = query.line;
+    if let Some(caps) = RE_LOCATION.captures(&message) {
+        let parse = |idx: usize| caps[idx].parse::<usize>().unwrap_or(1).saturating_sub(1);
+        let (error_line, column) = (parse(1), parse(2));
+        let line_start = match error_line {
+            0 => 0,
+            n => query
+                .text
+                .match_indices('\n')
+                .nth(n - 1)
+                .map_or(0, |(idx, _)| idx + 1),
+        };
+        let offset = query.text[line_start..]
+            .char_indices()
+            .nth(column)
+            .map_or(query.text.len(), |(offset, _)| line_start + offset);
+        line = query.line_at(offset);
+        message = RE_LOCATION.replace(&message, "").into_owned();
+    }
+
+    ValidationError {
+        tag: tag.to_owned(),
+        line,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_detailed;
+    use sqlparser::dialect::GenericDialect;
+
+    #[test]
+    fn validate_valid() {
+        let queries = parse_detailed("-- name: x\nSELECT a, b\nFROM t;").unwrap();
+        assert_eq!(validate(&queries, &GenericDialect {}), vec![]);
+    }
+
+    #[test]
+    fn validate_invalid_line() {
+        let text = "-- name: x\nSELECT 1;\n-- name: y\nSELECT a,\nFROM t\nWHERE;";
+        let queries = parse_detailed(text).unwrap();
+        let errors = validate(&queries, &GenericDialect {});
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].tag, "y");
+        assert_eq!(errors[0].line, 6);
+
+        let options = crate::ParseOptions::new().preserve_newlines(true);
+        let queries = options.parse_detailed(text).unwrap();
+        assert_eq!(validate(&queries, &GenericDialect {})[0].line, 6);
+    }
+}
@@ -0,0 +1,83 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Source of values for `${NAME}` substitution, see
+/// [ParseOptions::substitute_vars](crate::ParseOptions::substitute_vars).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Variables {
+    #[cfg(feature = "std")]
+    Env,
+    Map(Vec<(String, String)>),
+}
+
+impl Variables {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        match self {
+            #[cfg(feature = "std")]
+            Variables::Env => std::env::var(name).ok().map(Cow::Owned),
+            Variables::Map(vars) => vars
+                .iter()
+                .rev()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str().into()),
+        }
+    }
+}
+
+// Replace `${NAME}` in text, on unset variable returns its byte position in text and name.
+pub(crate) fn substitute<'a>(
+    text: &'a str,
+    vars: &Variables,
+) -> Result<Cow<'a, str>, (usize, &'a str)> {
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut rest = 0;
+    while let Some(start) = text[rest..].find("${").map(|idx| rest + idx) {
+        let name_len = text[start + 2..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(text.len() - start - 2);
+        let name = &text[start + 2..start + 2 + name_len];
+        let end = start + 2 + name_len;
+        if name.is_empty()
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || !text[end..].starts_with('}')
+        {
+            rest = start + 2;
+            continue;
+        }
+
+        let value = vars.get(name).ok_or((start, name))?;
+        result.push_str(&text[copied..start]);
+        result.push_str(&value);
+        copied = end + 1;
+        rest = copied;
+    }
+
+    if copied == 0 {
+        return Ok(Cow::Borrowed(text));
+    }
+    result.push_str(&text[copied..]);
+    Ok(Cow::Owned(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::borrow::ToOwned;
+    use alloc::vec;
+
+    #[test]
+    fn substitute_map() {
+        let vars = Variables::Map(vec![("SCHEMA".to_owned(), "app".to_owned())]);
+        assert_eq!(
+            substitute("SELECT * FROM ${SCHEMA}.users, ${SCHEMA}.t", &vars),
+            Ok("SELECT * FROM app.users, app.t".into())
+        );
+        assert_eq!(
+            substitute("SELECT '${', '${1}', '${x'", &vars),
+            Ok("SELECT '${', '${1}', '${x'".into())
+        );
+        assert_eq!(substitute("a ${OTHER}", &vars), Err((2, "OTHER")));
+    }
+}
@@ -0,0 +1,80 @@
+//! [wasm-bindgen](https://docs.rs/wasm-bindgen/) bindings, same parser for browser tooling.
+//!
+//! ```js
+//! import { parse, parseJson } from "rsyesql";
+//!
+//! const queries = parse("-- name: x\nSELECT 1;");
+//! queries.get("x"); // "SELECT 1;"
+//! JSON.parse(parseJson("-- name: x\nSELECT 1;")); // { x: "SELECT 1;" }
+//! ```
+
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+use js_sys::Map;
+use wasm_bindgen::prelude::*;
+
+/// Parse SQL queries as text to JS `Map` (`tag` => `query`) in definition order.
+///
+/// Throws `Error` with message of [ParseError](crate::ParseError).
+#[wasm_bindgen(js_name = parse)]
+pub fn parse(text: &str) -> Result<Map, JsError> {
+    let queries = crate::parse(text)?;
+    let map = Map::new();
+    for (tag, query) in &queries {
+        map.set(&JsValue::from_str(tag), &JsValue::from_str(query));
+    }
+    Ok(map)
+}
+
+/// Parse SQL queries as text to JSON object (`tag` => `query`) in definition order.
+///
+/// Throws `Error` with message of [ParseError](crate::ParseError).
+#[wasm_bindgen(js_name = parseJson)]
+pub fn parse_json(text: &str) -> Result<String, JsError> {
+    Ok(to_json(&crate::parse(text)?))
+}
+
+fn to_json(queries: &IndexMap<String, String>) -> String {
+    let mut json = String::from("{");
+    for (idx, (tag, query)) in queries.iter().enumerate() {
+        if idx > 0 {
+            json.push(',');
+        }
+        push_json_string(&mut json, tag);
+        json.push(':');
+        push_json_string(&mut json, query);
+    }
+    json.push('}');
+    json
+}
+
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_object() {
+        let text = "--name: x\nselect 1;\n--name: \"y\"\nselect '\\';";
+        assert_eq!(
+            to_json(&crate::parse(text).unwrap()),
+            r#"{"x":"select 1;","\"y\"":"select '\\';"}"#
+        );
+    }
+}